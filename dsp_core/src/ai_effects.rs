@@ -1,5 +1,4 @@
 // AI эффекты с поддержкой NPU
-use std::sync::atomic::AtomicBool;
 use std::collections::VecDeque;
 
 /// Результат обработки AI
@@ -29,9 +28,8 @@ pub enum AIProcessingMode {
 
 /// Главный AI процессор
 pub struct AIProcessor {
+    #[cfg_attr(not(all(target_os = "macos", target_arch = "aarch64")), allow(dead_code))]
     config: AIConfig,
-    is_processing: AtomicBool,
-    buffer_queue: VecDeque<Vec<f32>>,
     
     // Статистика производительности
     pub processing_time_history: VecDeque<f32>,
@@ -49,8 +47,6 @@ impl AIProcessor {
     pub fn new(config: AIConfig) -> Self {
         Self {
             config: config.clone(),
-            is_processing: AtomicBool::new(false),
-            buffer_queue: VecDeque::new(),
             processing_time_history: VecDeque::new(),
             npu_load_history: VecDeque::new(),
             
@@ -61,6 +57,18 @@ impl AIProcessor {
         }
     }
     
+    /// Перестраивает процессоры под новую частоту дискретизации и размер блока
+    pub fn reconfigure(&mut self, sample_rate: f32, buffer_size: usize) {
+        self.config.sample_rate = sample_rate;
+        self.config.buffer_size = buffer_size;
+        
+        #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+        {
+            self.core_ml_processor = CoreMLVoiceProcessor::new(&self.config).ok();
+        }
+        self.cpu_processor = CPUVoiceProcessor::new(&self.config);
+    }
+    
     /// Обрабатывает аудио через NPU или CPU
     pub fn process(&mut self, input: &[f32]) -> AIProcessingResult {
         let start_time = std::time::Instant::now();
@@ -239,19 +247,11 @@ impl CoreMLVoiceProcessor {
 }
 
 /// CPU процессор как fallback
-pub struct CPUVoiceProcessor {
-    config: AIConfig,
-    frame_buffer: Vec<f32>,
-    filter_state: Vec<f32>,
-}
+pub struct CPUVoiceProcessor;
 
 impl CPUVoiceProcessor {
-    pub fn new(config: &AIConfig) -> Self {
-        Self {
-            config: config.clone(),
-            frame_buffer: Vec::new(),
-            filter_state: vec![0.0; 8], // 8 коэффициентов фильтра
-        }
+    pub fn new(_config: &AIConfig) -> Self {
+        Self
     }
     
     pub fn process(&mut self, input: &[f32]) -> AIProcessingResult {
@@ -0,0 +1,159 @@
+// Автотюн: определение основного тона, привязка к ладу и плавная подстройка высоты
+use crate::pitch::PitchShifter;
+use crate::pitch_detection::{frequency_to_midi, midi_to_frequency, PitchDetector};
+
+use serde::{Deserialize, Serialize};
+
+/// Окно сдвига высоты тона для автотюна (короче обычного ради меньшей задержки)
+const SHIFTER_WINDOW_SECONDS: f32 = 0.05;
+/// Время подстройки при speed = 1.0; speed делит его (10.0 -> 20 мс, 0.1 -> 2 с)
+const BASE_GLIDE_SECONDS: f32 = 0.2;
+
+/// Лад, к нотам которого привязывается автотюн
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Scale {
+    #[default]
+    Major,       // Натуральный мажор
+    Minor,       // Натуральный минор
+    Chromatic,   // Все 12 нот
+    Custom(u16), // Битовая маска ступеней: бит i - нота на i полутонов выше тоники
+}
+
+impl Scale {
+    /// Маска разрешенных ступеней относительно тоники (12 младших бит)
+    pub fn mask(&self) -> u16 {
+        match self {
+            Scale::Major => 0b1010_1011_0101,     // 0 2 4 5 7 9 11
+            Scale::Minor => 0b0101_1010_1101,     // 0 2 3 5 7 8 10
+            Scale::Chromatic => 0b1111_1111_1111,
+            Scale::Custom(mask) => mask & 0b1111_1111_1111,
+        }
+    }
+
+    /// Проверяет, входит ли нота MIDI в лад с тоникой `key` (0-11, C = 0)
+    pub fn contains(&self, note: i32, key: i32) -> bool {
+        let degree = (note - key).rem_euclid(12);
+        self.mask() & (1 << degree) != 0
+    }
+
+    /// Ближайшая к дробной ноте `note` нота лада (None, если лад пустой)
+    pub fn nearest_note(&self, note: f32, key: i32) -> Option<i32> {
+        let center = note.round() as i32;
+        (center - 6..=center + 6)
+            .filter(|&candidate| self.contains(candidate, key))
+            .min_by(|&a, &b| (a as f32 - note).abs().total_cmp(&(b as f32 - note).abs()))
+    }
+}
+
+/// Состояние автотюна для отображения в UI
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AutoTuneStatus {
+    pub detected_frequency: f32,      // Гц, 0.0 если тон не найден
+    pub detected_note: Option<i32>,   // Ближайшая нота MIDI к найденному тону
+    pub target_note: Option<i32>,     // Нота лада, к которой идет подстройка
+    pub confidence: f32,              // Уверенность детектора 0.0 до 1.0
+    pub correction_semitones: f32,    // Текущий применяемый сдвиг
+}
+
+impl AutoTuneStatus {
+    /// Частота целевой ноты (None, если нота не выбрана)
+    pub fn target_frequency(&self) -> Option<f32> {
+        self.target_note.map(|note| midi_to_frequency(note as f32))
+    }
+}
+
+/// Потоковый автотюн.
+///
+/// Детектор YIN находит основной тон раз в блок (но не чаще раза в 10 мс), тон привязывается к ближайшей ноте лада,
+/// а сдвиг высоты плавно (экспоненциально) движется к нужному значению. В паузах и на шумных
+/// звуках последний сдвиг удерживается, чтобы не было скачков на согласных.
+pub struct AutoTuner {
+    sample_rate: f32,
+    detector: PitchDetector,
+    shifter: PitchShifter,
+    correction: f32,
+    speed: f32,
+    key: i32,
+    scale: Scale,
+    shift: f32,
+    status: AutoTuneStatus,
+}
+
+impl AutoTuner {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            detector: PitchDetector::new(sample_rate),
+            shifter: PitchShifter::with_window(sample_rate, SHIFTER_WINDOW_SECONDS),
+            correction: 1.0,
+            speed: 1.0,
+            key: 0,
+            scale: Scale::Major,
+            shift: 0.0,
+            status: AutoTuneStatus::default(),
+        }
+    }
+
+    /// Задает параметры: `correction` 0.0-1.0 (доля коррекции), `speed` 0.1-10.0, `key` 0-11
+    pub fn configure(&mut self, correction: f32, speed: f32, key: i32, scale: Scale) {
+        self.correction = correction.clamp(0.0, 1.0);
+        self.speed = speed.clamp(0.1, 10.0);
+        self.key = key.rem_euclid(12);
+        self.scale = scale;
+    }
+
+    /// Время подстройки к целевой ноте в секундах
+    pub fn glide_time(&self) -> f32 {
+        BASE_GLIDE_SECONDS / self.speed
+    }
+
+    pub fn status(&self) -> AutoTuneStatus {
+        self.status
+    }
+
+    /// Задержка в сэмплах (вносит сдвиг высоты тона)
+    pub fn latency(&self) -> usize {
+        self.shifter.latency()
+    }
+
+    /// Обрабатывает блок; выход той же длины, что и вход
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        let target_shift = match self.detector.process(input) {
+            Some(estimate) => {
+                let note = frequency_to_midi(estimate.frequency);
+                let target = self.scale.nearest_note(note, self.key);
+
+                self.status.detected_frequency = estimate.frequency;
+                self.status.detected_note = Some(note.round() as i32);
+                self.status.target_note = target;
+                self.status.confidence = estimate.confidence;
+
+                target.map_or(0.0, |target| (target as f32 - note) * self.correction)
+            }
+            None => {
+                self.status.detected_frequency = 0.0;
+                self.status.detected_note = None;
+                self.status.target_note = None;
+                self.status.confidence = 0.0;
+                self.shift
+            }
+        };
+
+        // Экспоненциальное приближение: за glide_time проходится ~63% пути
+        let block_seconds = input.len() as f32 / self.sample_rate;
+        let coefficient = 1.0 - (-block_seconds / self.glide_time()).exp();
+        self.shift += (target_shift - self.shift) * coefficient;
+        self.status.correction_semitones = self.shift;
+
+        self.shifter.set_semitones(self.shift);
+        self.shifter.process(input, output);
+    }
+
+    /// Сбрасывает анализ и накопленный сдвиг
+    pub fn reset(&mut self) {
+        self.detector.reset();
+        self.shifter.reset();
+        self.shift = 0.0;
+        self.status = AutoTuneStatus::default();
+    }
+}
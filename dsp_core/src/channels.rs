@@ -0,0 +1,69 @@
+// Раскладка каналов: сведение/разведение и перевод между чередующимся и планарным форматом
+
+/// Максимальное количество каналов конвейера
+pub const MAX_CHANNELS: usize = 8;
+
+/// Копирует `frames` кадров из `inputs` в `outputs`, приводя количество каналов:
+/// - одинаковое количество - канал в канал;
+/// - моно вход - в каждый выходной канал (разведение моно в стерео);
+/// - моно выход - среднее всех входных каналов;
+/// - иначе выходной канал `i` берет входной `i % входов` (стерео в 4 канала: L R L R).
+pub fn remix<I: AsRef<[f32]>, O: AsMut<[f32]>>(inputs: &[I], outputs: &mut [O], frames: usize) {
+    if inputs.is_empty() {
+        outputs.iter_mut().for_each(|output| output.as_mut()[..frames].fill(0.0));
+        return;
+    }
+
+    if outputs.len() == 1 && inputs.len() > 1 {
+        let output = &mut outputs[0].as_mut()[..frames];
+        let scale = 1.0 / inputs.len() as f32;
+        output.copy_from_slice(&inputs[0].as_ref()[..frames]);
+        for input in &inputs[1..] {
+            for (out, &sample) in output.iter_mut().zip(input.as_ref()) {
+                *out += sample;
+            }
+        }
+        output.iter_mut().for_each(|sample| *sample *= scale);
+        return;
+    }
+
+    for (channel, output) in outputs.iter_mut().enumerate() {
+        let input = inputs[channel % inputs.len()].as_ref();
+        output.as_mut()[..frames].copy_from_slice(&input[..frames]);
+    }
+}
+
+/// Раскладывает чередующиеся кадры (L R L R ...) по отдельным каналам
+pub fn deinterleave<O: AsMut<[f32]>>(interleaved: &[f32], outputs: &mut [O]) {
+    let channels = outputs.len();
+    if channels == 0 {
+        return;
+    }
+    for (frame, samples) in interleaved.chunks_exact(channels).enumerate() {
+        for (output, &sample) in outputs.iter_mut().zip(samples) {
+            output.as_mut()[frame] = sample;
+        }
+    }
+}
+
+/// Собирает отдельные каналы в чередующиеся кадры
+pub fn interleave<I: AsRef<[f32]>>(inputs: &[I], interleaved: &mut [f32]) {
+    let channels = inputs.len();
+    if channels == 0 {
+        return;
+    }
+    for (frame, samples) in interleaved.chunks_exact_mut(channels).enumerate() {
+        for (sample, input) in samples.iter_mut().zip(inputs) {
+            *sample = input.as_ref()[frame];
+        }
+    }
+}
+
+/// Приводит набор планарных буферов к `channels` каналам по `frames` сэмплов.
+/// Память выделяется только при росте, поэтому при постоянном размере блока вызов бесплатный.
+pub fn resize_planar(buffers: &mut Vec<Vec<f32>>, channels: usize, frames: usize) {
+    buffers.resize_with(channels, Vec::new);
+    for buffer in buffers.iter_mut() {
+        buffer.resize(frames, 0.0);
+    }
+}
@@ -0,0 +1,265 @@
+// Сверточная реверберация: равномерно разбитая на блоки свертка через FFT (UPOLS)
+use crate::offline::read_samples;
+use crate::resample::{Resampler, ResamplerQuality};
+
+use hound::WavReader;
+use rustfft::num_complex::Complex32;
+use rustfft::{Fft, FftPlanner};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Максимальная длина импульсного отклика в секундах (длиннее - обрезается)
+pub const MAX_IMPULSE_SECONDS: f32 = 10.0;
+
+#[derive(Debug)]
+pub enum ConvolutionError {
+    Wav(String),
+    EmptyImpulse,
+}
+
+impl std::fmt::Display for ConvolutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConvolutionError::Wav(msg) => write!(f, "Ошибка чтения импульсного отклика: {}", msg),
+            ConvolutionError::EmptyImpulse => write!(f, "Импульсный отклик пустой или беззвучный"),
+        }
+    }
+}
+
+impl std::error::Error for ConvolutionError {}
+
+impl From<hound::Error> for ConvolutionError {
+    fn from(e: hound::Error) -> Self {
+        ConvolutionError::Wav(e.to_string())
+    }
+}
+
+/// Моно импульсный отклик помещения (или телефона, мегафона и т.п.)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImpulseResponse {
+    samples: Vec<f32>,
+    sample_rate: f32,
+}
+
+impl ImpulseResponse {
+    /// Создает отклик из сэмплов, нормируя его к единичной энергии,
+    /// чтобы громкость не зависела от записи отклика
+    pub fn new(samples: Vec<f32>, sample_rate: f32) -> Result<Self, ConvolutionError> {
+        let max_len = (sample_rate * MAX_IMPULSE_SECONDS) as usize;
+        let mut samples = samples;
+        samples.truncate(max_len);
+        // Хвост ниже -120 дБ от пика ничего не дает, только тратит процессор
+        let peak = samples.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+        let end = samples.iter().rposition(|x| x.abs() > peak * 1e-6).map_or(0, |i| i + 1);
+        samples.truncate(end);
+
+        let energy: f32 = samples.iter().map(|x| x * x).sum();
+        if samples.is_empty() || energy <= f32::MIN_POSITIVE {
+            return Err(ConvolutionError::EmptyImpulse);
+        }
+        let gain = 1.0 / energy.sqrt();
+        samples.iter_mut().for_each(|x| *x *= gain);
+
+        Ok(Self { samples, sample_rate })
+    }
+
+    /// Загружает отклик из WAV файла: каналы сводятся в моно, частота приводится к `sample_rate`
+    pub fn from_wav<P: AsRef<Path>>(path: P, sample_rate: f32) -> Result<Self, ConvolutionError> {
+        let mut reader = WavReader::open(path.as_ref())?;
+        let spec = reader.spec();
+        let channels = spec.channels.max(1) as usize;
+
+        let max_frames = (spec.sample_rate as f32 * MAX_IMPULSE_SECONDS) as usize;
+        let mut interleaved = vec![0.0f32; (reader.len() as usize).min(max_frames * channels)];
+        let read = read_samples(&mut reader, &mut interleaved)?;
+        let mono: Vec<f32> = interleaved[..read]
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+
+        Self::new(resample(&mono, spec.sample_rate as f32, sample_rate), sample_rate)
+    }
+
+    /// Тот же отклик на другой частоте дискретизации
+    pub fn resampled(&self, sample_rate: f32) -> Result<Self, ConvolutionError> {
+        Self::new(resample(&self.samples, self.sample_rate, sample_rate), sample_rate)
+    }
+
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Длительность отклика в секундах
+    pub fn duration(&self) -> f32 {
+        self.samples.len() as f32 / self.sample_rate
+    }
+}
+
+/// Потоковая свертка с импульсным откликом.
+///
+/// Отклик делится на части по `block_size` сэмплов, спектры частей считаются заранее.
+/// Каждый входной блок проходит одно прямое и одно обратное FFT размера `2 * block_size`
+/// (overlap-save), а частотная линия задержки суммирует вклад всех частей.
+/// Задержка равна `block_size` - размеру блока конвейера.
+#[derive(Clone)]
+pub struct Convolver {
+    block_size: usize,
+    fft: Arc<dyn Fft<f32>>,
+    ifft: Arc<dyn Fft<f32>>,
+    partitions: Vec<Vec<Complex32>>,    // Спектры частей отклика
+    delay_line: Vec<Vec<Complex32>>,    // Спектры последних входных блоков (кольцо)
+    delay_position: usize,
+
+    input_window: Vec<f32>,             // Предыдущий и текущий входной блок
+    input_fifo: Vec<f32>,
+    output_fifo: Vec<f32>,
+    fifo_position: usize,
+    wet: f32,
+    dry: f32,
+
+    spectrum: Vec<Complex32>,
+    accumulator: Vec<Complex32>,
+    scratch: Vec<Complex32>,
+}
+
+impl Convolver {
+    pub fn new(impulse: &ImpulseResponse, block_size: usize) -> Self {
+        let block_size = block_size.max(1);
+        let fft_size = block_size * 2;
+
+        let mut planner = FftPlanner::new();
+        let fft = planner.plan_fft_forward(fft_size);
+        let ifft = planner.plan_fft_inverse(fft_size);
+        let mut scratch = vec![Complex32::default(); fft.get_inplace_scratch_len().max(ifft.get_inplace_scratch_len())];
+
+        let partitions: Vec<Vec<Complex32>> = impulse
+            .samples()
+            .chunks(block_size)
+            .map(|part| {
+                let mut spectrum = vec![Complex32::default(); fft_size];
+                for (bin, &sample) in spectrum.iter_mut().zip(part) {
+                    bin.re = sample;
+                }
+                fft.process_with_scratch(&mut spectrum, &mut scratch);
+                spectrum
+            })
+            .collect();
+        let partition_count = partitions.len();
+
+        Self {
+            block_size,
+            fft,
+            ifft,
+            partitions,
+            delay_line: vec![vec![Complex32::default(); fft_size]; partition_count],
+            delay_position: 0,
+            input_window: vec![0.0; fft_size],
+            input_fifo: vec![0.0; block_size],
+            output_fifo: vec![0.0; block_size],
+            fifo_position: 0,
+            wet: 1.0,
+            dry: 0.0,
+            spectrum: vec![Complex32::default(); fft_size],
+            accumulator: vec![Complex32::default(); fft_size],
+            scratch,
+        }
+    }
+
+    /// Задержка в сэмплах
+    pub fn latency(&self) -> usize {
+        self.block_size
+    }
+
+    /// Уровни свернутого и исходного сигнала 0.0 - 1.0 (исходный задерживается вместе со сверткой)
+    pub fn set_mix(&mut self, wet: f32, dry: f32) {
+        self.wet = wet.clamp(0.0, 1.0);
+        self.dry = dry.clamp(0.0, 1.0);
+    }
+
+    /// Количество частей, на которые разбит отклик
+    pub fn partition_count(&self) -> usize {
+        self.partitions.len()
+    }
+
+    /// Обрабатывает один сэмпл (выход задержан на `block_size`)
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        // Вторая половина окна - предыдущий блок, то есть вход ровно `block_size` сэмплов назад
+        let dry = self.input_window[self.block_size + self.fifo_position];
+        self.input_fifo[self.fifo_position] = input;
+        let output = self.output_fifo[self.fifo_position] * self.wet + dry * self.dry;
+
+        self.fifo_position += 1;
+        if self.fifo_position == self.block_size {
+            self.fifo_position = 0;
+            self.process_partition();
+        }
+
+        output
+    }
+
+    /// Обрабатывает блок любой длины; выход той же длины, что и вход
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        for (&sample, out) in input.iter().zip(output.iter_mut()) {
+            *out = self.process_sample(sample);
+        }
+    }
+
+    /// Очищает хвост свертки
+    pub fn reset(&mut self) {
+        self.delay_line.iter_mut().for_each(|spectrum| spectrum.fill(Complex32::default()));
+        self.input_window.fill(0.0);
+        self.input_fifo.fill(0.0);
+        self.output_fifo.fill(0.0);
+        self.fifo_position = 0;
+    }
+
+    fn process_partition(&mut self) {
+        let n = self.block_size;
+
+        // Окно из двух блоков: предыдущий + новый
+        self.input_window.copy_within(n.., 0);
+        self.input_window[n..].copy_from_slice(&self.input_fifo);
+
+        // Новый спектр записывается на место самого старого
+        let line_len = self.delay_line.len();
+        self.delay_position = (self.delay_position + line_len - 1) % line_len;
+        let newest = &mut self.delay_line[self.delay_position];
+        for (bin, &sample) in newest.iter_mut().zip(&self.input_window) {
+            *bin = Complex32::new(sample, 0.0);
+        }
+        self.fft.process_with_scratch(newest, &mut self.scratch);
+
+        // Y = сумма X(k - p) * H(p)
+        self.accumulator.fill(Complex32::default());
+        for (p, partition) in self.partitions.iter().enumerate() {
+            let input_spectrum = &self.delay_line[(self.delay_position + p) % line_len];
+            for ((acc, x), h) in self.accumulator.iter_mut().zip(input_spectrum).zip(partition) {
+                *acc += x * h;
+            }
+        }
+
+        self.spectrum.copy_from_slice(&self.accumulator);
+        self.ifft.process_with_scratch(&mut self.spectrum, &mut self.scratch);
+
+        // Overlap-save: верная половина - вторая
+        let scale = 1.0 / (2 * n) as f32;
+        for (out, bin) in self.output_fifo.iter_mut().zip(&self.spectrum[n..]) {
+            *out = bin.re * scale;
+        }
+    }
+}
+
+// Helper функции
+
+/// Приводит сэмплы отклика от частоты `from` к частоте `to`
+fn resample(samples: &[f32], from: f32, to: f32) -> Vec<f32> {
+    let mut resampler = Resampler::with_quality(from, to, ResamplerQuality::High);
+    let mut resampled = Vec::with_capacity((samples.len() as f64 * resampler.ratio()) as usize + 1);
+    resampler.process(samples, &mut resampled);
+    resampler.flush(&mut resampled);
+    resampled
+}
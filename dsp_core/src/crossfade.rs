@@ -0,0 +1,205 @@
+// Плавная смена эффектов: уходящий эффект затухает, новый нарастает, эхо и реверберация доигрывают
+use crate::smoothing::{SmoothedValue, SmoothingMode};
+use crate::{DspProcessor, EffectType};
+
+/// Хвост тише этого уровня (-80 дБ) считается затихшим
+const TAIL_SILENCE: f32 = 1e-4;
+/// Хвост дольше этого времени обрывается плавным затуханием
+pub const MAX_TAIL_SECONDS: f32 = 12.0;
+
+/// Уходящий эффект со своим DSP состоянием.
+///
+/// Эффект без хвоста затухает по выходу. Эффекту с хвостом затухает вход,
+/// а выход остается полным, пока эхо и реверберация не стихнут сами.
+pub struct RetiringEffect {
+    pub effect: EffectType,
+    pub processor: DspProcessor,
+    input_gain: SmoothedValue,
+    output_gain: SmoothedValue,
+    tail_samples: usize,
+    max_tail_samples: usize,
+    finished: bool,
+}
+
+impl RetiringEffect {
+    fn new(effect: EffectType, processor: DspProcessor, gain: f32, fade_time: f32, sample_rate: f32) -> Self {
+        let mut input_gain = SmoothedValue::new(SmoothingMode::Linear, fade_time, sample_rate);
+        let mut output_gain = SmoothedValue::new(SmoothingMode::Linear, fade_time, sample_rate);
+        if effect.has_tail() {
+            input_gain.set_target(gain);
+            input_gain.set_target(0.0);
+            output_gain.set_target(1.0);
+        } else {
+            input_gain.set_target(1.0);
+            output_gain.set_target(gain);
+            output_gain.set_target(0.0);
+        }
+        Self {
+            effect,
+            processor,
+            input_gain,
+            output_gain,
+            tail_samples: 0,
+            max_tail_samples: (MAX_TAIL_SECONDS * sample_rate) as usize,
+            finished: false,
+        }
+    }
+
+    /// Усиление входа на следующем сэмпле
+    #[inline]
+    pub fn next_input_gain(&mut self) -> f32 {
+        self.input_gain.next_value()
+    }
+
+    /// Усиление выхода на следующем сэмпле
+    #[inline]
+    pub fn next_output_gain(&mut self) -> f32 {
+        self.output_gain.next_value()
+    }
+
+    /// Обрывает хвост затуханием выхода
+    fn release(&mut self) {
+        self.output_gain.set_target(0.0);
+    }
+
+    fn is_releasing(&self) -> bool {
+        self.output_gain.target() == 0.0
+    }
+
+    /// Учитывает обработанный блок с пиковым уровнем выхода `peak`
+    pub fn finish_block(&mut self, frames: usize, peak: f32) {
+        if self.output_gain.value() == 0.0 && !self.output_gain.is_smoothing() {
+            self.finished = true;
+            return;
+        }
+        if self.input_gain.value() > 0.0 || self.input_gain.is_smoothing() {
+            return;
+        }
+        // Вход уже закрыт - доигрывает хвост
+        self.tail_samples += frames;
+        if peak < TAIL_SILENCE {
+            self.finished = true;
+        } else if self.tail_samples > self.max_tail_samples {
+            self.release();
+        }
+    }
+}
+
+/// Переключатель эффектов конвейера.
+///
+/// У активного эффекта свой `DspProcessor`, у каждого уходящего - свой из запаса,
+/// поэтому состояние эффектов (линии задержки, хвосты, сдвиг тона) не смешивается.
+/// Если запас кончился, самый старый хвост обрывается затуханием, а переключение ждет,
+/// пока освободится процессор (не дольше времени перехода).
+pub struct EffectSwitcher {
+    active: EffectType,
+    fade_in: SmoothedValue,
+    retiring: Vec<RetiringEffect>,
+    spares: Vec<DspProcessor>,
+    fade_time: f32,
+    sample_rate: f32,
+}
+
+impl EffectSwitcher {
+    pub fn new(spares: Vec<DspProcessor>, fade_time: f32, sample_rate: f32) -> Self {
+        let mut fade_in = SmoothedValue::new(SmoothingMode::Linear, fade_time, sample_rate);
+        fade_in.set_target(1.0);
+        Self {
+            active: EffectType::None,
+            fade_in,
+            retiring: Vec::with_capacity(spares.len()),
+            spares,
+            fade_time: fade_time.max(0.0),
+            sample_rate,
+        }
+    }
+
+    /// Эффект, на который идет (или закончен) переход
+    pub fn active(&self) -> EffectType {
+        self.active
+    }
+
+    /// Длительность перехода в секундах
+    pub fn fade_time(&self) -> f32 {
+        self.fade_time
+    }
+
+    pub fn set_fade_time(&mut self, seconds: f32) {
+        self.fade_time = seconds.max(0.0);
+        self.fade_in.set_time(self.fade_time, self.sample_rate);
+    }
+
+    /// Идет ли переход или доигрывает хвост
+    pub fn is_transitioning(&self) -> bool {
+        self.fade_in.is_smoothing() || !self.retiring.is_empty()
+    }
+
+    /// Начинает переход на `target`: `processor` (состояние активного эффекта) уходит доигрывать,
+    /// вместо него ставится очищенный процессор из запаса. false - запаса нет, повторить позже.
+    pub fn switch(&mut self, target: EffectType, processor: &mut DspProcessor) -> bool {
+        if target == self.active {
+            return true;
+        }
+        let Some(mut fresh) = self.spares.pop() else {
+            if !self.retiring.iter().any(RetiringEffect::is_releasing) {
+                if let Some(oldest) = self.retiring.first_mut() {
+                    oldest.release();
+                }
+            }
+            return false;
+        };
+        fresh.reset();
+        std::mem::swap(processor, &mut fresh);
+
+        // Новый эффект нарастает с нуля, старый затухает от того уровня, которого успел достичь
+        let gain = self.fade_in.value();
+        self.retiring.push(RetiringEffect::new(self.active, fresh, gain, self.fade_time, self.sample_rate));
+        self.active = target;
+        self.fade_in.reset(0.0);
+        self.fade_in.set_target(1.0);
+        true
+    }
+
+    /// Сразу делает `target` активным, без перехода (например, до первого блока - переходить не от чего)
+    pub fn jump(&mut self, target: EffectType) {
+        self.active = target;
+        self.fade_in.reset(1.0);
+    }
+
+    /// Усиление активного эффекта на следующем сэмпле
+    #[inline]
+    pub fn next_fade_in(&mut self) -> f32 {
+        self.fade_in.next_value()
+    }
+
+    pub fn retiring_mut(&mut self) -> &mut [RetiringEffect] {
+        &mut self.retiring
+    }
+
+    /// Нужен ли в этом блоке выход AI процессора
+    pub fn uses_ai(&self) -> bool {
+        self.active.is_ai() || self.retiring.iter().any(|retiring| retiring.effect.is_ai())
+    }
+
+    /// Возвращает в запас процессоры стихших эффектов
+    pub fn recycle(&mut self) {
+        while let Some(position) = self.retiring.iter().position(|retiring| retiring.finished) {
+            let retiring = self.retiring.remove(position);
+            self.spares.push(retiring.processor);
+        }
+    }
+
+    /// Все процессоры, кроме активного (для настроек, общих для всех процессоров)
+    pub fn processors_mut(&mut self) -> impl Iterator<Item = &mut DspProcessor> {
+        self.spares.iter_mut().chain(self.retiring.iter_mut().map(|retiring| &mut retiring.processor))
+    }
+
+    /// Сбрасывает переходы и заменяет запас (при перенастройке конвейера)
+    pub fn reset(&mut self, spares: Vec<DspProcessor>, sample_rate: f32) {
+        self.retiring.clear();
+        self.spares = spares;
+        self.sample_rate = sample_rate;
+        self.fade_in = SmoothedValue::new(SmoothingMode::Linear, self.fade_time, sample_rate);
+        self.fade_in.set_target(1.0);
+    }
+}
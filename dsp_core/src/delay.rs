@@ -0,0 +1,375 @@
+// Линия задержки с дробным чтением: эхо, пинг-понг и основа для хоруса, флэнжера и ленточного эха
+use crate::smoothing::{SmoothedValue, SmoothingMode};
+use crate::BiquadFilter;
+
+/// Запас сэмплов за концом буфера для интерполяции по 4 точкам
+const INTERPOLATION_GUARD: usize = 4;
+/// Время плавного изменения задержки по умолчанию, секунды
+const DEFAULT_GLIDE_TIME: f32 = 0.1;
+
+/// Интерполяция при чтении между сэмплами
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DelayInterpolation {
+    /// Линейная: дешево, слегка срезает верх при дробной задержке
+    #[default]
+    Linear,
+    /// Allpass первого порядка: ровная АЧХ, но для быстрой модуляции не подходит (фазовые хвосты)
+    Allpass,
+    /// Кубическая (Эрмит по 4 точкам): ровнее линейной, подходит для модуляции
+    Cubic,
+}
+
+/// Длительность ноты для синхронизации с темпом
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteValue {
+    Whole,
+    Half,
+    Quarter,
+    Eighth,
+    Sixteenth,
+    ThirtySecond,
+    DottedHalf,
+    DottedQuarter,
+    DottedEighth,
+    TripletQuarter,
+    TripletEighth,
+    TripletSixteenth,
+}
+
+impl NoteValue {
+    /// Длительность в долях (четвертях)
+    pub fn beats(&self) -> f32 {
+        match self {
+            NoteValue::Whole => 4.0,
+            NoteValue::Half => 2.0,
+            NoteValue::Quarter => 1.0,
+            NoteValue::Eighth => 0.5,
+            NoteValue::Sixteenth => 0.25,
+            NoteValue::ThirtySecond => 0.125,
+            NoteValue::DottedHalf => 3.0,
+            NoteValue::DottedQuarter => 1.5,
+            NoteValue::DottedEighth => 0.75,
+            NoteValue::TripletQuarter => 2.0 / 3.0,
+            NoteValue::TripletEighth => 1.0 / 3.0,
+            NoteValue::TripletSixteenth => 1.0 / 6.0,
+        }
+    }
+
+    /// Длительность ноты в секундах при темпе `bpm` (четвертей в минуту)
+    pub fn seconds(&self, bpm: f32) -> f32 {
+        60.0 / bpm.max(1.0) * self.beats()
+    }
+}
+
+/// Кольцевой буфер с чтением на дробной задержке
+pub struct DelayLine {
+    buffer: Vec<f32>,
+    write_pos: usize,
+    allpass_state: f32, // Предыдущий выход allpass интерполяции
+}
+
+impl DelayLine {
+    pub fn new(max_delay_samples: usize) -> Self {
+        Self {
+            buffer: vec![0.0; max_delay_samples.max(1) + INTERPOLATION_GUARD],
+            write_pos: 0,
+            allpass_state: 0.0,
+        }
+    }
+
+    /// Максимальная задержка в сэмплах
+    pub fn max_delay(&self) -> f32 {
+        (self.buffer.len() - INTERPOLATION_GUARD) as f32
+    }
+
+    /// Сигнал `delay` сэмплов назад (1.0 - последний записанный сэмпл). Вызывается до `write`.
+    pub fn read(&mut self, delay: f32, interpolation: DelayInterpolation) -> f32 {
+        let delay = delay.clamp(1.0, self.max_delay());
+        let whole = delay.floor();
+        let fraction = delay - whole;
+        let whole = whole as usize;
+
+        match interpolation {
+            DelayInterpolation::Linear => {
+                let a = self.at(whole);
+                let b = self.at(whole + 1);
+                a + (b - a) * fraction
+            },
+            DelayInterpolation::Allpass => {
+                // y = c * x[n-d] + x[n-d-1] - c * y[n-1], дробная часть задает коэффициент c
+                let coefficient = (1.0 - fraction) / (1.0 + fraction);
+                let output = coefficient * self.at(whole) + self.at(whole + 1) - coefficient * self.allpass_state;
+                self.allpass_state = output;
+                output
+            },
+            DelayInterpolation::Cubic => {
+                // Точки вокруг позиции: x[n-d+1], x[n-d], x[n-d-1], x[n-d-2]
+                let newer = if whole > 1 { self.at(whole - 1) } else { self.at(whole) };
+                let a = self.at(whole);
+                let b = self.at(whole + 1);
+                let older = self.at(whole + 2);
+                let c1 = 0.5 * (b - newer);
+                let c2 = newer - 2.5 * a + 2.0 * b - 0.5 * older;
+                let c3 = 0.5 * (older - newer) + 1.5 * (a - b);
+                ((c3 * fraction + c2) * fraction + c1) * fraction + a
+            },
+        }
+    }
+
+    pub fn write(&mut self, sample: f32) {
+        self.buffer[self.write_pos] = sample;
+        self.write_pos = (self.write_pos + 1) % self.buffer.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.fill(0.0);
+        self.write_pos = 0;
+        self.allpass_state = 0.0;
+    }
+
+    #[inline]
+    fn at(&self, delay: usize) -> f32 {
+        let len = self.buffer.len();
+        self.buffer[(self.write_pos + len - delay) % len]
+    }
+}
+
+/// Эхо с дробной задержкой.
+///
+/// Изменение времени задержки идет плавно (как у ленточного эха - с кратким сдвигом тона вместо щелчка),
+/// время можно модулировать синусоидой (хорус, флэнжер, "плывущая" лента), а в обратной связи
+/// стоят фильтры, чтобы повторы темнели или тончали.
+pub struct DelayEffect {
+    line: DelayLine,
+    sample_rate: f32,
+    interpolation: DelayInterpolation,
+    delay: SmoothedValue,     // Задержка в сэмплах
+    glide_time: f32,
+    primed: bool,             // Первая установка времени применяется сразу
+
+    feedback: f32,
+    mix: f32,
+
+    // Модуляция задержки
+    modulation_depth: f32,    // Сэмплы
+    modulation_phase: f32,    // 0.0 - 1.0
+    modulation_increment: f32,
+
+    // Фильтры обратной связи
+    feedback_lowpass: BiquadFilter,
+    feedback_highpass: BiquadFilter,
+    feedback_filter: Option<(f32, f32)>, // (срез ВЧ, срез НЧ) в Гц
+}
+
+impl DelayEffect {
+    pub fn new(max_delay_samples: usize) -> Self {
+        let sample_rate = 44100.0;
+        let mut delay = SmoothedValue::new(SmoothingMode::Linear, DEFAULT_GLIDE_TIME, sample_rate);
+        delay.set_target((max_delay_samples / 4).max(1) as f32);
+        Self {
+            line: DelayLine::new(max_delay_samples),
+            sample_rate,
+            interpolation: DelayInterpolation::default(),
+            delay,
+            glide_time: DEFAULT_GLIDE_TIME,
+            primed: false,
+            feedback: 0.3,
+            mix: 0.3,
+            modulation_depth: 0.0,
+            modulation_phase: 0.0,
+            modulation_increment: 0.0,
+            feedback_lowpass: BiquadFilter::new(),
+            feedback_highpass: BiquadFilter::new(),
+            feedback_filter: None,
+        }
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.read_delayed();
+        self.write_with_feedback(input, delayed);
+        input + delayed * self.mix
+    }
+
+    /// Только задержанный сигнал (без исходного и без микса) - для хоруса, флэнжера и пинг-понга.
+    /// После него нужно вызвать `write_with_feedback`.
+    pub fn read_delayed(&mut self) -> f32 {
+        self.primed = true;
+        let mut delay = self.delay.next_value();
+        if self.modulation_depth > 0.0 {
+            delay += self.modulation_depth * (self.modulation_phase * std::f32::consts::TAU).sin();
+            self.modulation_phase = (self.modulation_phase + self.modulation_increment).fract();
+        }
+        self.line.read(delay, self.interpolation)
+    }
+
+    /// Записывает вход и обратную связь от `delayed` (через фильтры обратной связи)
+    pub fn write_with_feedback(&mut self, input: f32, delayed: f32) {
+        let mut feedback = delayed * self.feedback;
+        if self.feedback_filter.is_some() {
+            feedback = self.feedback_lowpass.process(self.feedback_highpass.process(feedback));
+        }
+        self.line.write(input + feedback);
+    }
+
+    /// Время задержки в секундах (изменение идет плавно за время `set_glide_time`)
+    pub fn set_delay_time(&mut self, time_sec: f32, sample_rate: f32) {
+        if sample_rate != self.sample_rate {
+            self.set_sample_rate(sample_rate);
+        }
+        let samples = (time_sec * sample_rate).clamp(1.0, self.line.max_delay());
+        if self.primed {
+            self.delay.set_target(samples);
+        } else {
+            self.delay.reset(samples);
+        }
+    }
+
+    /// Время задержки по темпу: `bpm` четвертей в минуту, длительность ноты `note`
+    pub fn set_tempo_sync(&mut self, bpm: f32, note: NoteValue) {
+        self.set_delay_time(note.seconds(bpm), self.sample_rate);
+    }
+
+    /// Текущая задержка в секундах (во время перехода - промежуточная)
+    pub fn delay_time(&self) -> f32 {
+        self.delay.value() / self.sample_rate
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        let seconds = self.delay.target() / self.sample_rate;
+        self.sample_rate = sample_rate;
+        self.delay.set_time(self.glide_time, sample_rate);
+        self.delay.reset((seconds * sample_rate).clamp(1.0, self.line.max_delay()));
+        if let Some((low_cut, high_cut)) = self.feedback_filter {
+            self.set_feedback_filter(low_cut, high_cut);
+        }
+    }
+
+    /// Время плавного изменения задержки в секундах (0 - скачком)
+    pub fn set_glide_time(&mut self, seconds: f32) {
+        self.glide_time = seconds.max(0.0);
+        self.delay.set_time(self.glide_time, self.sample_rate);
+    }
+
+    pub fn set_interpolation(&mut self, interpolation: DelayInterpolation) {
+        self.interpolation = interpolation;
+    }
+
+    /// Модуляция задержки синусоидой: частота в Гц и глубина в секундах
+    pub fn set_modulation(&mut self, rate_hz: f32, depth_sec: f32) {
+        self.modulation_increment = rate_hz.max(0.0) / self.sample_rate;
+        self.modulation_depth = (depth_sec.max(0.0) * self.sample_rate).min(self.line.max_delay() * 0.5);
+    }
+
+    /// Фаза модуляции 0.0 - 1.0 (разные фазы в каналах дают ширину)
+    pub fn set_modulation_phase(&mut self, phase: f32) {
+        self.modulation_phase = phase.rem_euclid(1.0);
+    }
+
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback.clamp(0.0, 0.95);
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// Фильтры в обратной связи: срез низких `low_cut` и высоких `high_cut` частот в Гц
+    pub fn set_feedback_filter(&mut self, low_cut: f32, high_cut: f32) {
+        let nyquist = self.sample_rate * 0.49;
+        let low_cut = low_cut.clamp(10.0, nyquist);
+        let high_cut = high_cut.clamp(low_cut, nyquist);
+        self.feedback_highpass.highpass(low_cut, self.sample_rate, std::f32::consts::FRAC_1_SQRT_2);
+        self.feedback_lowpass.lowpass(high_cut, self.sample_rate, std::f32::consts::FRAC_1_SQRT_2);
+        self.feedback_filter = Some((low_cut, high_cut));
+    }
+
+    /// Убирает фильтры из обратной связи
+    pub fn clear_feedback_filter(&mut self) {
+        self.feedback_filter = None;
+    }
+
+    /// Очищает линию задержки
+    pub fn clear(&mut self) {
+        self.line.clear();
+        self.feedback_lowpass.reset();
+        self.feedback_highpass.reset();
+    }
+}
+
+/// Стерео эхо из двух линий. В режиме пинг-понг вход (моно сумма) попадает только в левую линию,
+/// а повторы перебрасываются между каналами: L, R, L, ...
+pub struct StereoDelay {
+    pub left: DelayEffect,
+    pub right: DelayEffect,
+    ping_pong: bool,
+    mix: f32,
+}
+
+impl StereoDelay {
+    pub fn new(max_delay_samples: usize, sample_rate: f32) -> Self {
+        let mut left = DelayEffect::new(max_delay_samples);
+        let mut right = DelayEffect::new(max_delay_samples);
+        left.set_sample_rate(sample_rate);
+        right.set_sample_rate(sample_rate);
+        Self { left, right, ping_pong: false, mix: 0.3 }
+    }
+
+    pub fn set_ping_pong(&mut self, enabled: bool) {
+        self.ping_pong = enabled;
+    }
+
+    pub fn set_delay_time(&mut self, time_sec: f32) {
+        let sample_rate = self.left.sample_rate;
+        self.left.set_delay_time(time_sec, sample_rate);
+        self.right.set_delay_time(time_sec, sample_rate);
+    }
+
+    pub fn set_tempo_sync(&mut self, bpm: f32, note: NoteValue) {
+        self.set_delay_time(note.seconds(bpm));
+    }
+
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.left.set_feedback(feedback);
+        self.right.set_feedback(feedback);
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// Модуляция в каналах идет в противофазе - повторы "расходятся" по панораме
+    pub fn set_modulation(&mut self, rate_hz: f32, depth_sec: f32) {
+        self.left.set_modulation(rate_hz, depth_sec);
+        self.right.set_modulation(rate_hz, depth_sec);
+        self.right.set_modulation_phase(self.left.modulation_phase + 0.5);
+    }
+
+    pub fn set_feedback_filter(&mut self, low_cut: f32, high_cut: f32) {
+        self.left.set_feedback_filter(low_cut, high_cut);
+        self.right.set_feedback_filter(low_cut, high_cut);
+    }
+
+    pub fn set_interpolation(&mut self, interpolation: DelayInterpolation) {
+        self.left.set_interpolation(interpolation);
+        self.right.set_interpolation(interpolation);
+    }
+
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let delayed_left = self.left.read_delayed();
+        let delayed_right = self.right.read_delayed();
+        if self.ping_pong {
+            // Повтор из левой линии уходит в правую и обратно, теряя `feedback` на каждом переходе
+            self.left.write_with_feedback((left + right) * 0.5, delayed_right);
+            self.right.write_with_feedback(0.0, delayed_left);
+        } else {
+            self.left.write_with_feedback(left, delayed_left);
+            self.right.write_with_feedback(right, delayed_right);
+        }
+        (left + delayed_left * self.mix, right + delayed_right * self.mix)
+    }
+
+    pub fn clear(&mut self) {
+        self.left.clear();
+        self.right.clear();
+    }
+}
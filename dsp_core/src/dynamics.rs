@@ -0,0 +1,411 @@
+// Динамическая обработка: гейт/экспандер, компрессор и true-peak лимитер с заглядыванием вперед
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::sync::atomic::Ordering;
+
+use atomic_float::AtomicF32;
+
+use crate::channels::MAX_CHANNELS;
+
+/// Заглядывание лимитера вперед, секунды (на столько же задерживается выход)
+pub const LIMITER_LOOKAHEAD: f32 = 0.0015;
+/// Гейт не закрывается раньше этого времени после того, как сигнал ушел под порог (без дребезга)
+const GATE_HOLD: f32 = 0.05;
+/// Детектор уровня гейта: быстрая атака и спад, чтобы порог ловил паузы между словами
+const GATE_DETECTOR_ATTACK: f32 = 0.0005;
+const GATE_DETECTOR_RELEASE: f32 = 0.03;
+/// Межсэмпловые пики ищутся в 4 раза чаще сэмплов (3 промежуточные точки)
+const TRUE_PEAK_PHASES: usize = 3;
+/// Длина интерполятора пиков (окно sinc по обе стороны от промежутка)
+const TRUE_PEAK_TAPS: usize = 8;
+/// Уровень тишины для детекторов, дБ
+const SILENCE_DB: f32 = -120.0;
+/// Мягкое ограничение (`soft_clip`) не трогает сигнал ниже этого уровня
+const SOFT_CLIP_KNEE: f32 = 0.8;
+
+/// Ослабление каждой ступени динамики в дБ (0 - ступень не работает). Для C API.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GainReduction {
+    pub gate: f32,
+    pub compressor: f32,
+    pub limiter: f32,
+}
+
+/// Индикаторы ослабления, которые аудио поток публикует раз в блок (наибольшее за блок).
+/// Читаются из UI потока без блокировки конвейера.
+#[derive(Debug, Default)]
+pub struct DynamicsMeter {
+    gate: AtomicF32,
+    compressor: AtomicF32,
+    limiter: AtomicF32,
+}
+
+impl DynamicsMeter {
+    pub fn publish(&self, reduction: GainReduction) {
+        self.gate.store(reduction.gate, Ordering::Relaxed);
+        self.compressor.store(reduction.compressor, Ordering::Relaxed);
+        self.limiter.store(reduction.limiter, Ordering::Relaxed);
+    }
+
+    pub fn load(&self) -> GainReduction {
+        GainReduction {
+            gate: self.gate.load(Ordering::Relaxed),
+            compressor: self.compressor.load(Ordering::Relaxed),
+            limiter: self.limiter.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Гейт / экспандер вниз. Ниже порога сигнал ослабляется на `(порог - уровень) * (ratio - 1)` дБ,
+/// но не больше `range`: ratio 2 - мягкий экспандер, от 10 - гейт, который глушит паузы.
+/// Усиление общее для всех каналов кадра (по самому громкому каналу).
+pub struct Gate {
+    sample_rate: f32,
+    threshold: f32, // дБ
+    ratio: f32,
+    range: f32,     // дБ, наибольшее ослабление
+    attack: f32,    // Коэффициент открытия
+    release: f32,   // Коэффициент закрытия
+    detector: f32,  // Огибающая уровня (линейная)
+    detector_attack: f32,
+    detector_release: f32,
+    hold: usize,
+    hold_left: usize,
+    reduction: f32, // Текущее ослабление, дБ
+}
+
+impl Gate {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut gate = Self {
+            sample_rate,
+            threshold: -50.0,
+            ratio: 10.0,
+            range: 60.0,
+            attack: 0.0,
+            release: 0.0,
+            detector: 0.0,
+            detector_attack: time_coefficient(GATE_DETECTOR_ATTACK, sample_rate),
+            detector_release: time_coefficient(GATE_DETECTOR_RELEASE, sample_rate),
+            hold: (GATE_HOLD * sample_rate) as usize,
+            hold_left: 0,
+            reduction: 0.0,
+        };
+        gate.set_times(0.001, 0.1);
+        gate
+    }
+
+    /// Порог в дБ, ratio (1 - 100) и наибольшее ослабление в дБ
+    pub fn set_threshold(&mut self, threshold: f32, ratio: f32, range: f32) {
+        self.threshold = threshold.clamp(-90.0, 0.0);
+        self.ratio = ratio.clamp(1.0, 100.0);
+        self.range = range.clamp(0.0, 90.0);
+    }
+
+    /// Время открытия и закрытия в секундах
+    pub fn set_times(&mut self, attack: f32, release: f32) {
+        self.attack = time_coefficient(attack.max(0.0), self.sample_rate);
+        self.release = time_coefficient(release.max(0.0), self.sample_rate);
+    }
+
+    /// Текущее ослабление в дБ
+    pub fn reduction(&self) -> f32 {
+        self.reduction
+    }
+
+    /// Обрабатывает кадр на месте, возвращает ослабление в дБ
+    pub fn process_frame(&mut self, frame: &mut [f32]) -> f32 {
+        let peak = frame_peak(frame);
+        let coefficient = if peak > self.detector { self.detector_attack } else { self.detector_release };
+        self.detector = peak + (self.detector - peak) * coefficient;
+
+        let level = linear_to_db(self.detector);
+        let target = if level >= self.threshold {
+            self.hold_left = self.hold;
+            0.0
+        } else {
+            ((self.threshold - level) * (self.ratio - 1.0)).min(self.range)
+        };
+
+        if target < self.reduction {
+            self.reduction = target + (self.reduction - target) * self.attack;
+        } else if self.hold_left > 0 {
+            self.hold_left -= 1;
+        } else {
+            self.reduction = target + (self.reduction - target) * self.release;
+        }
+
+        let gain = db_to_linear(-self.reduction);
+        frame.iter_mut().for_each(|sample| *sample *= gain);
+        self.reduction
+    }
+
+    pub fn reset(&mut self) {
+        self.detector = 0.0;
+        self.hold_left = 0;
+        self.reduction = 0.0;
+    }
+}
+
+/// Компрессор вниз с мягким коленом. Выше порога уровень растет в `ratio` раз медленнее;
+/// в пределах колена (`knee` дБ вокруг порога) ratio нарастает плавно.
+/// Уровень - пиковая огибающая (мгновенный подъем, спад за время восстановления), а само
+/// ослабление сглаживается атакой и восстановлением, поэтому усиление не дрожит с периодом волны.
+pub struct Compressor {
+    sample_rate: f32,
+    threshold: f32, // дБ
+    ratio: f32,
+    knee: f32,      // дБ
+    makeup: f32,    // Линейное усиление после компрессии
+    attack: f32,
+    release: f32,
+    detector: f32,  // Пиковая огибающая (линейная)
+    reduction: f32, // дБ
+}
+
+impl Compressor {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut compressor = Self {
+            sample_rate,
+            threshold: -18.0,
+            ratio: 4.0,
+            knee: 6.0,
+            makeup: 1.0,
+            attack: 0.0,
+            release: 0.0,
+            detector: 0.0,
+            reduction: 0.0,
+        };
+        compressor.set_times(0.005, 0.1);
+        compressor
+    }
+
+    /// Порог в дБ, ratio (1 - 20) и ширина колена в дБ (0 - жесткое колено)
+    pub fn set_threshold(&mut self, threshold: f32, ratio: f32, knee: f32) {
+        self.threshold = threshold.clamp(-60.0, 0.0);
+        self.ratio = ratio.clamp(1.0, 20.0);
+        self.knee = knee.clamp(0.0, 24.0);
+    }
+
+    /// Время атаки и восстановления в секундах
+    pub fn set_times(&mut self, attack: f32, release: f32) {
+        self.attack = time_coefficient(attack.max(0.0), self.sample_rate);
+        self.release = time_coefficient(release.max(0.0), self.sample_rate);
+    }
+
+    /// Компенсация уровня после компрессии, дБ
+    pub fn set_makeup(&mut self, makeup: f32) {
+        self.makeup = db_to_linear(makeup.clamp(0.0, 24.0));
+    }
+
+    pub fn reduction(&self) -> f32 {
+        self.reduction
+    }
+
+    /// Ослабление в дБ для уровня `level` дБ (статическая характеристика)
+    pub fn static_reduction(&self, level: f32) -> f32 {
+        let over = level - self.threshold;
+        let slope = 1.0 - 1.0 / self.ratio;
+        if 2.0 * over <= -self.knee {
+            0.0
+        } else if 2.0 * over < self.knee {
+            slope * (over + self.knee * 0.5).powi(2) / (2.0 * self.knee)
+        } else {
+            slope * over
+        }
+    }
+
+    /// Обрабатывает кадр на месте, возвращает ослабление в дБ
+    pub fn process_frame(&mut self, frame: &mut [f32]) -> f32 {
+        let peak = frame_peak(frame);
+        self.detector = if peak > self.detector { peak } else { peak + (self.detector - peak) * self.release };
+        let target = self.static_reduction(linear_to_db(self.detector));
+        let coefficient = if target > self.reduction { self.attack } else { self.release };
+        self.reduction = target + (self.reduction - target) * coefficient;
+
+        let gain = db_to_linear(-self.reduction) * self.makeup;
+        frame.iter_mut().for_each(|sample| *sample *= gain);
+        self.reduction
+    }
+
+    pub fn reset(&mut self) {
+        self.detector = 0.0;
+        self.reduction = 0.0;
+    }
+}
+
+/// Лимитер межсэмпловых (true-peak) пиков с заглядыванием вперед.
+///
+/// Пики ищутся с 4-кратной передискретизацией. Нужное усиление держится минимумом по окну
+/// заглядывания, а затем усредняется по тому же окну: к моменту, когда пик выходит из
+/// линии задержки, усиление уже опустилось до нужного, поэтому выход не превышает потолок
+/// без жесткого ограничения. Задержка - `latency()` сэмплов.
+pub struct Limiter {
+    sample_rate: f32,
+    ceiling: f32,            // Линейный потолок
+    release: f32,
+    lookahead: usize,
+    interpolator: [[f32; TRUE_PEAK_TAPS]; TRUE_PEAK_PHASES],
+    history: Vec<[f32; TRUE_PEAK_TAPS]>, // Последние сэмплы каждого канала для поиска пиков
+    delay: Vec<VecDeque<f32>>,           // Задержанный сигнал по каналам
+    minimum: VecDeque<(usize, f32)>,     // Монотонная очередь минимума усиления по окну
+    held: f32,                           // Минимум после восстановления
+    average: VecDeque<f32>,              // Окно усреднения
+    average_sum: f64,
+    position: usize,
+    reduction: f32,                      // дБ
+}
+
+impl Limiter {
+    pub fn new(sample_rate: f32, channels: usize) -> Self {
+        let channels = channels.clamp(1, MAX_CHANNELS);
+        let lookahead = ((LIMITER_LOOKAHEAD * sample_rate).round() as usize).max(1);
+        let delay_length = lookahead - 1 + TRUE_PEAK_TAPS / 2;
+
+        // Оконный sinc для точек 1/4, 2/4, 3/4 между сэмплами TRUE_PEAK_TAPS/2 - 1 и TRUE_PEAK_TAPS/2
+        let mut interpolator = [[0.0f32; TRUE_PEAK_TAPS]; TRUE_PEAK_PHASES];
+        for (phase, taps) in interpolator.iter_mut().enumerate() {
+            let fraction = (phase + 1) as f32 / (TRUE_PEAK_PHASES + 1) as f32;
+            for (tap, coefficient) in taps.iter_mut().enumerate() {
+                let distance = tap as f32 - (TRUE_PEAK_TAPS / 2 - 1) as f32 - fraction;
+                let window = 0.5 + 0.5 * (PI * distance / (TRUE_PEAK_TAPS / 2) as f32).cos();
+                *coefficient = sinc(distance) * window;
+            }
+            let sum: f32 = taps.iter().sum();
+            taps.iter_mut().for_each(|coefficient| *coefficient /= sum);
+        }
+
+        let mut limiter = Self {
+            sample_rate,
+            ceiling: db_to_linear(-1.0),
+            release: time_coefficient(0.05, sample_rate),
+            lookahead,
+            interpolator,
+            history: vec![[0.0; TRUE_PEAK_TAPS]; channels],
+            delay: vec![VecDeque::with_capacity(delay_length + 1); channels],
+            minimum: VecDeque::with_capacity(lookahead + 1),
+            held: 1.0,
+            average: VecDeque::with_capacity(lookahead + 1),
+            average_sum: 0.0,
+            position: 0,
+            reduction: 0.0,
+        };
+        limiter.reset();
+        limiter
+    }
+
+    /// Потолок в дБTP (-24 - 0) и время восстановления в секундах
+    pub fn set_ceiling(&mut self, ceiling: f32, release: f32) {
+        self.ceiling = db_to_linear(ceiling.clamp(-24.0, 0.0));
+        self.release = time_coefficient(release.max(0.0), self.sample_rate);
+    }
+
+    /// Задержка выхода в сэмплах
+    pub fn latency(&self) -> usize {
+        self.lookahead - 1 + TRUE_PEAK_TAPS / 2
+    }
+
+    pub fn reduction(&self) -> f32 {
+        self.reduction
+    }
+
+    /// Обрабатывает кадр на месте (выход задержан на `latency()`), возвращает ослабление в дБ
+    pub fn process_frame(&mut self, frame: &mut [f32]) -> f32 {
+        // Пик нового промежутка между сэмплами с учетом межсэмпловых точек
+        let mut peak = 0.0f32;
+        for (history, &sample) in self.history.iter_mut().zip(frame.iter()) {
+            history.copy_within(1.., 0);
+            history[TRUE_PEAK_TAPS - 1] = sample;
+            peak = peak.max(history[TRUE_PEAK_TAPS / 2 - 1].abs()).max(history[TRUE_PEAK_TAPS / 2].abs());
+            for taps in &self.interpolator {
+                let value: f32 = taps.iter().zip(history.iter()).map(|(c, x)| c * x).sum();
+                peak = peak.max(value.abs());
+            }
+        }
+        let needed = if peak > self.ceiling { self.ceiling / peak } else { 1.0 };
+
+        // Минимум по окну заглядывания (монотонная очередь)
+        while self.minimum.back().is_some_and(|&(_, gain)| gain >= needed) {
+            self.minimum.pop_back();
+        }
+        self.minimum.push_back((self.position, needed));
+        while self.minimum.front().is_some_and(|&(position, _)| position + self.lookahead <= self.position) {
+            self.minimum.pop_front();
+        }
+        self.position += 1;
+        let minimum = self.minimum.front().map_or(1.0, |&(_, gain)| gain);
+
+        // Опускается сразу, восстанавливается плавно; усреднение сглаживает спуск
+        self.held = if minimum < self.held { minimum } else { minimum + (self.held - minimum) * self.release };
+        self.average.push_back(self.held);
+        self.average_sum += self.held as f64;
+        if let Some(oldest) = self.average.pop_front() {
+            self.average_sum -= oldest as f64;
+        }
+        let gain = ((self.average_sum / self.lookahead as f64) as f32).min(1.0);
+
+        for (delay, sample) in self.delay.iter_mut().zip(frame.iter_mut()) {
+            delay.push_back(*sample);
+            *sample = delay.pop_front().unwrap_or(0.0) * gain;
+        }
+        self.reduction = -linear_to_db(gain);
+        self.reduction
+    }
+
+    /// Очищает задержку и возвращает усиление к 1
+    pub fn reset(&mut self) {
+        let delay_length = self.latency();
+        for delay in &mut self.delay {
+            delay.clear();
+            delay.resize(delay_length, 0.0);
+        }
+        self.history.iter_mut().for_each(|history| history.fill(0.0));
+        self.minimum.clear();
+        self.average.clear();
+        self.average.resize(self.lookahead, 1.0);
+        self.average_sum = self.lookahead as f64;
+        self.held = 1.0;
+        self.position = 0;
+        self.reduction = 0.0;
+    }
+}
+
+/// Мягкое ограничение пиков вместо жесткого `clamp(-1.0, 1.0)`: ниже 0.8 сигнал не меняется,
+/// выше плавно подходит к 1.0 без излома формы волны
+#[inline]
+pub fn soft_clip(sample: f32) -> f32 {
+    let level = sample.abs();
+    if level <= SOFT_CLIP_KNEE {
+        return sample;
+    }
+    let headroom = 1.0 - SOFT_CLIP_KNEE;
+    sample.signum() * (SOFT_CLIP_KNEE + headroom * ((level - SOFT_CLIP_KNEE) / headroom).tanh())
+}
+
+// Helper функции
+
+#[inline]
+pub fn db_to_linear(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+#[inline]
+pub fn linear_to_db(value: f32) -> f32 {
+    if value <= 0.0 { SILENCE_DB } else { (20.0 * value.log10()).max(SILENCE_DB) }
+}
+
+/// Коэффициент однополюсного сглаживания с постоянной времени `time` секунд (0 - мгновенно)
+fn time_coefficient(time: f32, sample_rate: f32) -> f32 {
+    if time * sample_rate < 1.0 { 0.0 } else { (-1.0 / (time * sample_rate)).exp() }
+}
+
+/// Наибольший модуль сэмпла в кадре
+#[inline]
+fn frame_peak(frame: &[f32]) -> f32 {
+    frame.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+}
+
+/// Нормированный sinc
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 { 1.0 } else { (PI * x).sin() / (PI * x) }
+}
@@ -0,0 +1,229 @@
+// Параметрический эквалайзер: N полос на biquad фильтрах и суммарная АЧХ для графика
+use serde::{Deserialize, Serialize};
+
+use crate::channels::MAX_CHANNELS;
+use crate::BiquadFilter;
+
+/// Наибольшее число полос в одной ступени эквалайзера
+pub const MAX_EQ_BANDS: usize = 8;
+/// Допустимые частоты полос, Гц (сверху еще и не выше 0.49 частоты дискретизации)
+const MIN_EQ_FREQUENCY: f32 = 20.0;
+const MAX_EQ_FREQUENCY: f32 = 20000.0;
+
+/// Форма полосы эквалайзера
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EqBandType {
+    /// Колокол: подъем или завал вокруг частоты
+    #[default]
+    Peaking,
+    LowShelf,
+    HighShelf,
+    Lowpass,
+    Highpass,
+    /// Режекция узкой полосы (усиление не используется)
+    Notch,
+    /// Только поворот фазы (усиление не используется)
+    Allpass,
+}
+
+impl EqBandType {
+    /// Преобразует значение из C API обратно в enum (неизвестное - колокол)
+    pub fn from_u32(value: u32) -> Self {
+        match value {
+            1 => EqBandType::LowShelf,
+            2 => EqBandType::HighShelf,
+            3 => EqBandType::Lowpass,
+            4 => EqBandType::Highpass,
+            5 => EqBandType::Notch,
+            6 => EqBandType::Allpass,
+            _ => EqBandType::Peaking,
+        }
+    }
+}
+
+/// Место ступени эквалайзера в конвейере
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqStage {
+    Pre,  // До эффекта (после среза низа/верха)
+    Post, // После эффекта, до выходного усиления
+}
+
+impl EqStage {
+    pub fn from_u32(value: u32) -> Self {
+        if value == 1 { EqStage::Post } else { EqStage::Pre }
+    }
+}
+
+/// Настройки одной полосы
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EqBand {
+    pub enabled: bool,
+    pub band_type: EqBandType,
+    pub frequency: f32, // Гц
+    pub gain: f32,      // дБ, -24 - 24
+    pub q: f32,         // 0.1 - 18
+}
+
+impl Default for EqBand {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            band_type: EqBandType::Peaking,
+            frequency: 1000.0,
+            gain: 0.0,
+            q: std::f32::consts::FRAC_1_SQRT_2,
+        }
+    }
+}
+
+impl EqBand {
+    /// Та же полоса с параметрами в допустимых пределах для частоты дискретизации `sample_rate`
+    fn clamped(&self, sample_rate: f32) -> Self {
+        Self {
+            frequency: self.frequency.clamp(MIN_EQ_FREQUENCY, MAX_EQ_FREQUENCY.min(sample_rate * 0.49)),
+            gain: self.gain.clamp(-24.0, 24.0),
+            q: self.q.clamp(0.1, 18.0),
+            ..*self
+        }
+    }
+
+    /// Настраивает фильтр под полосу
+    fn apply(&self, filter: &mut BiquadFilter, sample_rate: f32) {
+        match self.band_type {
+            EqBandType::Peaking => filter.peaking(self.frequency, sample_rate, self.q, self.gain),
+            EqBandType::LowShelf => filter.low_shelf(self.frequency, sample_rate, self.q, self.gain),
+            EqBandType::HighShelf => filter.high_shelf(self.frequency, sample_rate, self.q, self.gain),
+            EqBandType::Lowpass => filter.lowpass(self.frequency, sample_rate, self.q),
+            EqBandType::Highpass => filter.highpass(self.frequency, sample_rate, self.q),
+            EqBandType::Notch => filter.notch(self.frequency, sample_rate, self.q),
+            EqBandType::Allpass => filter.allpass(self.frequency, sample_rate, self.q),
+        }
+    }
+
+    /// Полоса меняет сигнал (колокол и полки с нулевым усилением пропускаются)
+    fn is_active(&self) -> bool {
+        let gain_only = matches!(self.band_type, EqBandType::Peaking | EqBandType::LowShelf | EqBandType::HighShelf);
+        self.enabled && !(gain_only && self.gain == 0.0)
+    }
+}
+
+/// Ступень параметрического эквалайзера: полосы применяются по порядку,
+/// у каждого канала свои фильтры, настройки полос общие.
+pub struct ParametricEq {
+    sample_rate: f32,
+    bands: Vec<EqBand>,
+    filters: Vec<Vec<BiquadFilter>>, // [полоса][канал]
+    channels: usize,
+}
+
+impl ParametricEq {
+    pub fn new(sample_rate: f32, channels: usize) -> Self {
+        Self {
+            sample_rate,
+            bands: Vec::with_capacity(MAX_EQ_BANDS),
+            filters: Vec::with_capacity(MAX_EQ_BANDS),
+            channels: channels.clamp(1, MAX_CHANNELS),
+        }
+    }
+
+    pub fn bands(&self) -> &[EqBand] {
+        &self.bands
+    }
+
+    /// Заменяет все полосы (не больше `MAX_EQ_BANDS`); история фильтров сбрасывается
+    pub fn set_bands(&mut self, bands: &[EqBand]) -> Result<(), String> {
+        if bands.len() > MAX_EQ_BANDS {
+            return Err(format!("Слишком много полос эквалайзера: {} (максимум {})", bands.len(), MAX_EQ_BANDS));
+        }
+        self.bands.clear();
+        self.filters.clear();
+        for band in bands {
+            self.push_band(band);
+        }
+        Ok(())
+    }
+
+    /// Меняет одну полосу; `index` равный числу полос добавляет новую.
+    /// История фильтра сохраняется, поэтому движение ручки не щелкает.
+    pub fn set_band(&mut self, index: usize, band: EqBand) -> Result<(), String> {
+        if index > self.bands.len() || index >= MAX_EQ_BANDS {
+            return Err(format!("Нет полосы эквалайзера {} (полос: {})", index, self.bands.len()));
+        }
+        if index == self.bands.len() {
+            self.push_band(&band);
+            return Ok(());
+        }
+        let band = band.clamped(self.sample_rate);
+        for filter in &mut self.filters[index] {
+            band.apply(filter, self.sample_rate);
+        }
+        self.bands[index] = band;
+        Ok(())
+    }
+
+    /// Удаляет все полосы
+    pub fn clear(&mut self) {
+        self.bands.clear();
+        self.filters.clear();
+    }
+
+    /// Обрабатывает кадр на месте (по сэмплу на канал)
+    #[inline]
+    pub fn process_frame(&mut self, frame: &mut [f32]) {
+        for (band, filters) in self.bands.iter().zip(&mut self.filters) {
+            if !band.is_active() {
+                continue;
+            }
+            for (sample, filter) in frame.iter_mut().zip(filters.iter_mut()) {
+                *sample = filter.process(*sample);
+            }
+        }
+    }
+
+    /// Очищает историю фильтров
+    pub fn reset(&mut self) {
+        self.filters.iter_mut().flatten().for_each(BiquadFilter::reset);
+    }
+
+    /// Суммарная АЧХ включенных полос в дБ на частоте `frequency`
+    pub fn response(&self, frequency: f32) -> f32 {
+        let gain: f32 = self.bands.iter().zip(&self.filters)
+            .filter(|(band, _)| band.is_active())
+            .map(|(_, filters)| filters[0].magnitude(frequency, self.sample_rate))
+            .product();
+        20.0 * gain.max(1e-6).log10()
+    }
+
+    /// АЧХ в дБ на каждой из частот `frequencies` (например, для графика в интерфейсе)
+    pub fn magnitude_response(&self, frequencies: &[f32]) -> Vec<f32> {
+        frequencies.iter().map(|&frequency| self.response(frequency)).collect()
+    }
+
+    fn push_band(&mut self, band: &EqBand) {
+        let band = band.clamped(self.sample_rate);
+        let filters = (0..self.channels)
+            .map(|_| {
+                let mut filter = BiquadFilter::new();
+                band.apply(&mut filter, self.sample_rate);
+                filter
+            })
+            .collect();
+        self.bands.push(band);
+        self.filters.push(filters);
+    }
+}
+
+// Helper функции
+
+/// `count` частот, равномерно распределенных по октавам от `low` до `high` Гц (ось графика АЧХ)
+pub fn log_frequencies(low: f32, high: f32, count: usize) -> Vec<f32> {
+    match count {
+        0 => Vec::new(),
+        1 => vec![low],
+        _ => {
+            let ratio = (high / low).powf(1.0 / (count - 1) as f32);
+            (0..count).map(|i| low * ratio.powi(i as i32)).collect()
+        }
+    }
+}
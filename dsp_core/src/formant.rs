@@ -0,0 +1,225 @@
+// Сдвиг формант через деформацию спектральной огибающей (LPC) с сохранением высоты тона
+use rustfft::num_complex::Complex32;
+use rustfft::{Fft, FftPlanner};
+use std::sync::Arc;
+
+/// Максимальное усиление огибающей в одной полосе (защита от подъема шума)
+const MAX_ENVELOPE_GAIN: f32 = 10.0;
+/// Минимальная энергия кадра, ниже которой кадр считается тишиной
+const SILENCE_ENERGY: f32 = 1e-9;
+
+/// Потоковый сдвиг формант.
+///
+/// Каждый кадр STFT делится на огибающую (модель LPC) и возбуждение. Огибающая растягивается
+/// по частоте на `factor`, возбуждение (гармоники основного тона) остается на месте,
+/// поэтому высота голоса не меняется.
+pub struct FormantShifter {
+    frame_size: usize,
+    hop_size: usize,
+    factor: f32,
+
+    fft: Arc<dyn Fft<f32>>,
+    ifft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+
+    // Потоковое состояние
+    input_fifo: Vec<f32>,
+    output_fifo: Vec<f32>,
+    output_accum: Vec<f32>,
+    fifo_position: usize,
+
+    // Рабочие буферы кадра
+    spectrum: Vec<Complex32>,
+    lpc_spectrum: Vec<Complex32>,
+    envelope: Vec<f32>,
+    autocorrelation: Vec<f32>,
+    lpc: Vec<f32>,
+    lpc_scratch: Vec<f32>,
+    fft_scratch: Vec<Complex32>,
+}
+
+impl FormantShifter {
+    pub fn new(sample_rate: f32) -> Self {
+        // Кадр ~23 мс (1024 при 44.1 кГц), перекрытие 75%
+        let frame_size = ((sample_rate.max(8000.0) * 0.023) as usize).next_power_of_two();
+        let hop_size = frame_size / 4;
+        // Классическое правило: 2 полюса на каждый кГц полосы + запас на наклон спектра
+        let lpc_order = ((sample_rate / 1000.0) as usize + 2).clamp(8, 48);
+
+        let mut planner = FftPlanner::new();
+        let fft = planner.plan_fft_forward(frame_size);
+        let ifft = planner.plan_fft_inverse(frame_size);
+        let scratch_len = fft.get_inplace_scratch_len().max(ifft.get_inplace_scratch_len());
+
+        let window = (0..frame_size)
+            .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / frame_size as f32).cos())
+            .collect();
+
+        Self {
+            frame_size,
+            hop_size,
+            factor: 1.0,
+            fft,
+            ifft,
+            window,
+            input_fifo: vec![0.0; frame_size],
+            output_fifo: vec![0.0; frame_size],
+            output_accum: vec![0.0; frame_size],
+            fifo_position: frame_size - hop_size,
+            spectrum: vec![Complex32::default(); frame_size],
+            lpc_spectrum: vec![Complex32::default(); frame_size],
+            envelope: vec![0.0; frame_size / 2 + 1],
+            autocorrelation: vec![0.0; lpc_order + 1],
+            lpc: vec![0.0; lpc_order + 1],
+            lpc_scratch: vec![0.0; lpc_order + 1],
+            fft_scratch: vec![Complex32::default(); scratch_len],
+        }
+    }
+
+    /// Устанавливает сдвиг формант: -2.0 (вниз на октаву) ... 0.0 (без изменений) ... +2.0 (вверх на октаву)
+    pub fn set_shift(&mut self, shift: f32) {
+        self.factor = 2.0_f32.powf(shift.clamp(-2.0, 2.0) * 0.5);
+    }
+
+    /// Коэффициент растяжения огибающей по частоте
+    pub fn factor(&self) -> f32 {
+        self.factor
+    }
+
+    /// Задержка в сэмплах (один кадр)
+    pub fn latency(&self) -> usize {
+        self.frame_size
+    }
+
+    /// Обрабатывает блок; выход той же длины, что и вход
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        // Первые N - hop сэмплов FIFO - хвост предыдущего кадра
+        let tail = self.frame_size - self.hop_size;
+
+        for (&sample, out) in input.iter().zip(output.iter_mut()) {
+            self.input_fifo[self.fifo_position] = sample;
+            *out = self.output_fifo[self.fifo_position - tail];
+            self.fifo_position += 1;
+
+            if self.fifo_position == self.frame_size {
+                self.fifo_position = tail;
+                self.process_frame();
+            }
+        }
+    }
+
+    /// Сбрасывает накопленное состояние
+    pub fn reset(&mut self) {
+        self.input_fifo.fill(0.0);
+        self.output_fifo.fill(0.0);
+        self.output_accum.fill(0.0);
+        self.fifo_position = self.frame_size - self.hop_size;
+    }
+
+    fn process_frame(&mut self) {
+        let n = self.frame_size;
+        let half = n / 2;
+
+        for ((bin, &sample), &w) in self.spectrum.iter_mut().zip(&self.input_fifo).zip(&self.window) {
+            *bin = Complex32::new(sample * w, 0.0);
+        }
+
+        // Без сдвига и в тишине кадр проходит без изменений
+        let shift_needed = (self.factor - 1.0).abs() > 1e-4 && self.compute_envelope();
+
+        if shift_needed {
+            self.fft.process_with_scratch(&mut self.spectrum, &mut self.fft_scratch);
+
+            for k in 0..=half {
+                let source = k as f32 / self.factor;
+                let warped = interpolate(&self.envelope, source);
+                let gain = (warped / self.envelope[k].max(f32::MIN_POSITIVE)).min(MAX_ENVELOPE_GAIN);
+                self.spectrum[k] *= gain;
+            }
+            // Восстанавливаем сопряженную симметрию вещественного сигнала
+            for k in 1..half {
+                self.spectrum[n - k] = self.spectrum[k].conj();
+            }
+
+            self.ifft.process_with_scratch(&mut self.spectrum, &mut self.fft_scratch);
+        }
+
+        // Окно синтеза + нормировка перекрытия (сумма квадратов Ханна при шаге N/4 = 1.5)
+        let scale = if shift_needed { 1.0 / (n as f32 * 1.5) } else { 1.0 / 1.5 };
+        for ((acc, bin), &w) in self.output_accum.iter_mut().zip(&self.spectrum).zip(&self.window) {
+            *acc += bin.re * w * scale;
+        }
+
+        self.output_fifo[..self.hop_size].copy_from_slice(&self.output_accum[..self.hop_size]);
+        self.output_accum.copy_within(self.hop_size.., 0);
+        self.output_accum[n - self.hop_size..].fill(0.0);
+        self.input_fifo.copy_within(self.hop_size.., 0);
+    }
+
+    /// Считает огибающую кадра |1/A(e^jw)| по LPC модели. Возвращает false для тишины.
+    fn compute_envelope(&mut self) -> bool {
+        let frame = &self.spectrum;
+        for (lag, r) in self.autocorrelation.iter_mut().enumerate() {
+            *r = (lag..frame.len()).map(|i| frame[i].re * frame[i - lag].re).sum();
+        }
+        if self.autocorrelation[0] < SILENCE_ENERGY {
+            return false;
+        }
+        // Небольшая коррекция белым шумом делает фильтр устойчивым на тональных кадрах
+        self.autocorrelation[0] *= 1.0001;
+
+        if !levinson_durbin(&self.autocorrelation, &mut self.lpc, &mut self.lpc_scratch) {
+            return false;
+        }
+
+        self.lpc_spectrum.fill(Complex32::default());
+        for (bin, &a) in self.lpc_spectrum.iter_mut().zip(&self.lpc) {
+            bin.re = a;
+        }
+        self.fft.process_with_scratch(&mut self.lpc_spectrum, &mut self.fft_scratch);
+
+        for (e, a) in self.envelope.iter_mut().zip(&self.lpc_spectrum) {
+            *e = 1.0 / a.norm().max(1e-6);
+        }
+        true
+    }
+}
+
+// Helper функции
+
+/// Рекурсия Левинсона-Дурбина: коэффициенты A(z) = 1 + a1 z^-1 + ... + ap z^-p.
+/// Возвращает false, если фильтр получается неустойчивым.
+fn levinson_durbin(r: &[f32], a: &mut [f32], scratch: &mut [f32]) -> bool {
+    let order = r.len() - 1;
+    a.fill(0.0);
+    a[0] = 1.0;
+    let mut error = r[0];
+
+    for i in 1..=order {
+        let acc: f32 = (1..i).map(|j| a[j] * r[i - j]).sum::<f32>() + r[i];
+        let k = -acc / error;
+        if !k.is_finite() || k.abs() >= 1.0 {
+            return false;
+        }
+
+        scratch[..=i].copy_from_slice(&a[..=i]);
+        for j in 1..i {
+            a[j] = scratch[j] + k * scratch[i - j];
+        }
+        a[i] = k;
+        error *= 1.0 - k * k;
+    }
+
+    true
+}
+
+/// Линейная интерполяция огибающей в дробном бине (за Найквистом - последнее значение)
+fn interpolate(envelope: &[f32], position: f32) -> f32 {
+    let last = envelope.len() - 1;
+    if position >= last as f32 {
+        return envelope[last];
+    }
+    let index = position as usize;
+    let frac = position - index as f32;
+    envelope[index] * (1.0 - frac) + envelope[index + 1] * frac
+}
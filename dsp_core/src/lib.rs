@@ -1,5 +1,6 @@
-use std::ffi::c_void;
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::ffi::{c_char, c_void, CStr};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
 use atomic_float::AtomicF32;
 use ringbuf::HeapRb;
 use crossbeam_channel::{Receiver, Sender};
@@ -11,9 +12,90 @@ use platform::PlatformAudio;
 
 // AI эффекты модуль
 pub mod ai_effects;
-mod neural_engine;
+pub mod neural_engine;
 use ai_effects::{AIProcessor, AIConfig, AIProcessingMode};
-use neural_engine::{NeuralVoiceProcessor, VoiceEffect, NeuralConfig, QualityPreset};
+use neural_engine::{NeuralConfig, NeuralVoiceProcessor, QualityPreset, VoiceEffect};
+use autotune::{AutoTuneStatus, Scale};
+use pitch_detection::{PitchDetector, PitchEstimate, PitchMonitor, PitchReading};
+
+// Потоковый сдвиг высоты тона
+pub mod pitch;
+use pitch::PitchShifter;
+
+// Сдвиг формант
+pub mod formant;
+
+// Линии задержки: эхо, пинг-понг, модуляция
+pub mod delay;
+pub use delay::DelayEffect;
+
+// Модуляционные эффекты: хорус, флэнжер, фейзер, вибрато, тремоло
+pub mod modulation;
+use modulation::LfoShape;
+
+// Робот: кольцевой модулятор и канальный вокодер
+pub mod vocoder;
+use vocoder::{CarrierWaveform, Vocoder};
+
+// Алгоритмическая реверберация
+pub mod reverb;
+use reverb::Reverb;
+
+// Сверточная реверберация с импульсными откликами
+pub mod convolution;
+use convolution::{ConvolutionError, Convolver, ImpulseResponse};
+
+// Определение основного тона и автотюн
+pub mod pitch_detection;
+pub mod autotune;
+
+// Офлайн обработка WAV файлов
+pub mod offline;
+
+// Пресеты (JSON)
+pub mod preset;
+use preset::{Preset, PresetError};
+
+// Передискретизация (файлы и переход между частотой устройства и обработки)
+pub mod resample;
+use resample::RateConverter;
+
+// Сглаживание параметров
+pub mod smoothing;
+use smoothing::{SmoothedValue, SmoothingConfig, SmoothingMode};
+
+// Плавная смена эффектов
+pub mod crossfade;
+use crossfade::EffectSwitcher;
+
+// Раскладка каналов: стерео и многоканальная обработка
+pub mod channels;
+use channels::MAX_CHANNELS;
+
+// Динамика: гейт/экспандер, компрессор, true-peak лимитер
+pub mod dynamics;
+use dynamics::{Compressor, DynamicsMeter, Gate, GainReduction, Limiter};
+
+// Параметрический эквалайзер до и после эффекта
+pub mod equalizer;
+use equalizer::{EqBand, EqBandType, EqStage, ParametricEq, MAX_EQ_BANDS};
+
+/// Поддерживаемый диапазон частот дискретизации, Гц
+pub const MIN_SAMPLE_RATE: f32 = 8000.0;
+pub const MAX_SAMPLE_RATE: f32 = 384000.0;
+/// Максимальная задержка эха в секундах
+const MAX_DELAY_SECONDS: f32 = 2.0;
+/// Сколько уходящих эффектов может доигрывать одновременно
+const RETIRING_EFFECTS: usize = 2;
+/// Длительность перехода между эффектами по умолчанию, секунды
+const DEFAULT_EFFECT_CROSSFADE: f32 = 0.05;
+/// Число полос вокодера эффекта Robot
+const ROBOT_VOCODER_BANDS: usize = 20;
+/// Срез низа и верха (`highpass_freq`/`lowpass_freq`) на краях диапазона выключен
+const MIN_CUT_FREQUENCY: f32 = 20.0;
+const MAX_CUT_FREQUENCY: f32 = 20000.0;
+/// Число полос вокодера с внешней несущей (музыке нужно больше полос, чем пиле)
+const SIDECHAIN_VOCODER_BANDS: usize = 28;
 
 /// Статистика производительности системы
 #[derive(Debug, Clone, Default)]
@@ -37,12 +119,56 @@ pub enum EffectType {
     Radio,       // Рация - bandpass filter + distortion
     Cathedral,   // Собор - большой reverb
     Underwater,  // Под водой - lowpass + modulation
+    Robot,       // Робот - канальный вокодер с внутренней несущей (CPU)
     // AI эффекты (выполняются на NPU)
-    Robot,       // Робот
     Demon,       // Демон
     Alien,       // Пришелец
     // Комплексный демонстрационный эффект
     VoiceChanger, // Полная цепочка: DSP → AI → Post-processing
+    // Свертка с загруженным импульсным откликом (помещение, телефон, мегафон)
+    Convolution,
+    // Вокодер: огибающая голоса на внешней несущей (сайдчейн - синтезатор, музыка)
+    Vocoder,
+}
+
+impl EffectType {
+    /// Преобразует значение из `AudioParameters::current_effect` (или из C API) обратно в enum
+    pub fn from_u32(value: u32) -> Self {
+        match value {
+            1 => EffectType::Monster,
+            2 => EffectType::HighPitch,
+            3 => EffectType::Cave,
+            4 => EffectType::Radio,
+            5 => EffectType::Cathedral,
+            6 => EffectType::Underwater,
+            7 => EffectType::Robot,
+            8 => EffectType::Demon,
+            9 => EffectType::Alien,
+            10 => EffectType::VoiceChanger,
+            11 => EffectType::Convolution,
+            12 => EffectType::Vocoder,
+            _ => EffectType::None,
+        }
+    }
+    
+    /// Сдвиг высоты тона (отношение частот), который эффект выставляет при выборе
+    pub fn default_pitch_shift(&self) -> Option<f32> {
+        match self {
+            EffectType::Monster => Some(0.5),   // Октава вниз
+            EffectType::HighPitch => Some(1.5), // Квинта вверх
+            _ => None,
+        }
+    }
+    
+    /// Эффект обрабатывается AI процессором блоком целиком
+    pub fn is_ai(&self) -> bool {
+        matches!(self, EffectType::Demon | EffectType::Alien | EffectType::VoiceChanger)
+    }
+    
+    /// У эффекта есть хвост (эхо, реверберация), который должен доиграть после выключения
+    pub fn has_tail(&self) -> bool {
+        matches!(self, EffectType::Cave | EffectType::Cathedral | EffectType::Convolution)
+    }
 }
 
 /// Типы генераторов шума
@@ -54,9 +180,21 @@ pub enum NoiseType {
     Brown,   // Коричневый шум
 }
 
+impl NoiseType {
+    /// Преобразует значение из `AudioParameters::noise_type` (или из C API) обратно в enum
+    pub fn from_u32(value: u32) -> Self {
+        match value {
+            1 => NoiseType::White,
+            2 => NoiseType::Pink,
+            3 => NoiseType::Brown,
+            _ => NoiseType::None,
+        }
+    }
+}
+
 /// Параметры аудио конвейера, управляемые атомарно
 pub struct AudioParameters {
-    // Основные параметры
+    // Основные параметры (только для чтения: меняются через AudioPipeline::reconfigure)
     pub sample_rate: AtomicF32,
     pub buffer_size: AtomicU32,
     pub input_gain: AtomicF32,
@@ -72,7 +210,7 @@ pub struct AudioParameters {
     pub noise_level: AtomicF32,     // 0.0 - 1.0
     
     // DSP параметры
-    pub pitch_shift: AtomicF32,     // 0.5 - 2.0 (полутона)
+    pub pitch_shift: AtomicF32,     // 0.5 - 2.0 (отношение частот для Monster/HighPitch)
     pub reverb_size: AtomicF32,     // 0.0 - 1.0
     pub reverb_damping: AtomicF32,  // 0.0 - 1.0
     pub delay_time: AtomicF32,      // 0.0 - 1.0 секунд
@@ -83,6 +221,30 @@ pub struct AudioParameters {
     pub highpass_freq: AtomicF32,   // 20 - 20000 Hz
     pub bandpass_center: AtomicF32, // 100 - 8000 Hz
     pub bandpass_q: AtomicF32,      // 0.1 - 10.0
+    
+    // Робот
+    pub robot_pitch: AtomicF32,     // 40 - 1000 Hz, высота несущей вокодера
+    
+    // Динамика (уровни в дБ, времена в секундах): гейт и компрессор до эффекта, лимитер на выходе
+    pub gate_enabled: AtomicBool,
+    pub gate_threshold: AtomicF32,       // -90 - 0 дБ
+    pub gate_ratio: AtomicF32,           // 1 - 100 (2 - экспандер, от 10 - гейт)
+    pub gate_range: AtomicF32,           // 0 - 90 дБ, наибольшее ослабление
+    pub gate_attack: AtomicF32,
+    pub gate_release: AtomicF32,
+    pub compressor_enabled: AtomicBool,
+    pub compressor_threshold: AtomicF32, // -60 - 0 дБ
+    pub compressor_ratio: AtomicF32,     // 1 - 20
+    pub compressor_knee: AtomicF32,      // 0 - 24 дБ
+    pub compressor_attack: AtomicF32,
+    pub compressor_release: AtomicF32,
+    pub compressor_makeup: AtomicF32,    // 0 - 24 дБ
+    pub limiter_enabled: AtomicBool,
+    pub limiter_ceiling: AtomicF32,      // -24 - 0 дБTP
+    pub limiter_release: AtomicF32,
+    
+    // Анализ
+    pub pitch_tracking: AtomicBool, // Отслеживание основного тона входа (включает тюнер, по умолчанию выключено)
 }
 
 impl Default for AudioParameters {
@@ -101,12 +263,106 @@ impl Default for AudioParameters {
             reverb_size: AtomicF32::new(0.5),
             reverb_damping: AtomicF32::new(0.5),
             delay_time: AtomicF32::new(0.3),
-            delay_feedback: AtomicF32::new(0.3),
+            delay_feedback: AtomicF32::new(0.4),
             lowpass_freq: AtomicF32::new(20000.0),
             highpass_freq: AtomicF32::new(20.0),
             bandpass_center: AtomicF32::new(1000.0),
-            bandpass_q: AtomicF32::new(1.0),
+            bandpass_q: AtomicF32::new(2.0),
+            robot_pitch: AtomicF32::new(110.0),
+            gate_enabled: AtomicBool::new(false),
+            gate_threshold: AtomicF32::new(-50.0),
+            gate_ratio: AtomicF32::new(10.0),
+            gate_range: AtomicF32::new(60.0),
+            gate_attack: AtomicF32::new(0.001),
+            gate_release: AtomicF32::new(0.1),
+            compressor_enabled: AtomicBool::new(false),
+            compressor_threshold: AtomicF32::new(-18.0),
+            compressor_ratio: AtomicF32::new(4.0),
+            compressor_knee: AtomicF32::new(6.0),
+            compressor_attack: AtomicF32::new(0.005),
+            compressor_release: AtomicF32::new(0.1),
+            compressor_makeup: AtomicF32::new(0.0),
+            limiter_enabled: AtomicBool::new(false),
+            limiter_ceiling: AtomicF32::new(-1.0),
+            limiter_release: AtomicF32::new(0.05),
+            pitch_tracking: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Сеттеры с проверкой диапазонов. Работают по `&self`, поэтому UI меняет параметры через
+/// `AudioPipeline::parameters()`, не блокируя конвейер (и не отнимая его у аудио потока).
+impl AudioParameters {
+    /// Выбирает эффект; эффекты со сдвигом высоты тона выставляют свой `pitch_shift`
+    pub fn set_effect(&self, effect: EffectType) {
+        if let Some(ratio) = effect.default_pitch_shift() {
+            self.set_pitch_shift(ratio);
         }
+        self.current_effect.store(effect as u32, Ordering::Relaxed);
+    }
+    
+    /// Сдвиг высоты тона для Monster/HighPitch (отношение частот 0.5 - 2.0)
+    pub fn set_pitch_shift(&self, ratio: f32) {
+        self.pitch_shift.store(ratio.clamp(0.5, 2.0), Ordering::Relaxed);
+    }
+    
+    /// Размер помещения и затухание реверберации для Cave/Cathedral (0.0 - 1.0)
+    pub fn set_reverb(&self, size: f32, damping: f32) {
+        self.reverb_size.store(size.clamp(0.0, 1.0), Ordering::Relaxed);
+        self.reverb_damping.store(damping.clamp(0.0, 1.0), Ordering::Relaxed);
+    }
+    
+    /// Высота голоса эффекта Robot в Гц (40 - 1000)
+    pub fn set_robot_pitch(&self, frequency: f32) {
+        self.robot_pitch.store(frequency.clamp(40.0, 1000.0), Ordering::Relaxed);
+    }
+    
+    /// Срез низа и верха перед эффектом, Гц (20 Гц и 20 кГц - срез выключен)
+    pub fn set_filters(&self, highpass_freq: f32, lowpass_freq: f32) {
+        self.highpass_freq.store(highpass_freq.clamp(MIN_CUT_FREQUENCY, MAX_CUT_FREQUENCY), Ordering::Relaxed);
+        self.lowpass_freq.store(lowpass_freq.clamp(MIN_CUT_FREQUENCY, MAX_CUT_FREQUENCY), Ordering::Relaxed);
+    }
+    
+    /// Гейт / экспандер: порог в дБ, ratio (2 - экспандер, от 10 - гейт) и наибольшее ослабление в дБ
+    pub fn set_gate(&self, enabled: bool, threshold: f32, ratio: f32, range: f32) {
+        self.gate_enabled.store(enabled, Ordering::Relaxed);
+        self.gate_threshold.store(threshold.clamp(-90.0, 0.0), Ordering::Relaxed);
+        self.gate_ratio.store(ratio.clamp(1.0, 100.0), Ordering::Relaxed);
+        self.gate_range.store(range.clamp(0.0, 90.0), Ordering::Relaxed);
+    }
+    
+    /// Время открытия и закрытия гейта в секундах
+    pub fn set_gate_times(&self, attack: f32, release: f32) {
+        self.gate_attack.store(attack.clamp(0.0, 1.0), Ordering::Relaxed);
+        self.gate_release.store(release.clamp(0.0, 5.0), Ordering::Relaxed);
+    }
+    
+    /// Компрессор: порог в дБ, ratio, колено и компенсация уровня в дБ
+    pub fn set_compressor(&self, enabled: bool, threshold: f32, ratio: f32, knee: f32, makeup: f32) {
+        self.compressor_enabled.store(enabled, Ordering::Relaxed);
+        self.compressor_threshold.store(threshold.clamp(-60.0, 0.0), Ordering::Relaxed);
+        self.compressor_ratio.store(ratio.clamp(1.0, 20.0), Ordering::Relaxed);
+        self.compressor_knee.store(knee.clamp(0.0, 24.0), Ordering::Relaxed);
+        self.compressor_makeup.store(makeup.clamp(0.0, 24.0), Ordering::Relaxed);
+    }
+    
+    /// Время атаки и восстановления компрессора в секундах
+    pub fn set_compressor_times(&self, attack: f32, release: f32) {
+        self.compressor_attack.store(attack.clamp(0.0, 1.0), Ordering::Relaxed);
+        self.compressor_release.store(release.clamp(0.0, 5.0), Ordering::Relaxed);
+    }
+    
+    /// Лимитер: потолок в дБTP и время восстановления в секундах
+    pub fn set_limiter(&self, enabled: bool, ceiling: f32, release: f32) {
+        self.limiter_enabled.store(enabled, Ordering::Relaxed);
+        self.limiter_ceiling.store(ceiling.clamp(-24.0, 0.0), Ordering::Relaxed);
+        self.limiter_release.store(release.clamp(0.001, 5.0), Ordering::Relaxed);
+    }
+    
+    /// Тип и уровень шума (0.0 - 1.0)
+    pub fn set_noise(&self, noise_type: NoiseType, level: f32) {
+        self.noise_type.store(noise_type as u32, Ordering::Relaxed);
+        self.noise_level.store(level.clamp(0.0, 1.0), Ordering::Relaxed);
     }
 }
 
@@ -156,7 +412,7 @@ impl NoiseGenerator {
         let white = self.white_noise();
         self.pink_state[0] = 0.99886 * self.pink_state[0] + white * 0.0555179;
         self.pink_state[1] = 0.99332 * self.pink_state[1] + white * 0.0750759;
-        self.pink_state[2] = 0.96900 * self.pink_state[2] + white * 0.1538520;
+        self.pink_state[2] = 0.96900 * self.pink_state[2] + white * 0.153_852;
         self.pink_state[3] = 0.86650 * self.pink_state[3] + white * 0.3104856;
         self.pink_state[4] = 0.55000 * self.pink_state[4] + white * 0.5329522;
         self.pink_state[5] = -0.7616 * self.pink_state[5] - white * 0.0168980;
@@ -174,55 +430,33 @@ impl NoiseGenerator {
     }
 }
 
-/// Простой delay эффект
-pub struct DelayEffect {
-    buffer: Vec<f32>,
-    write_pos: usize,
-    delay_samples: usize,
-    feedback: f32,
-    mix: f32,
+impl Default for NoiseGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl DelayEffect {
-    pub fn new(max_delay_samples: usize) -> Self {
-        Self {
-            buffer: vec![0.0; max_delay_samples],
-            write_pos: 0,
-            delay_samples: max_delay_samples / 4,
-            feedback: 0.3,
-            mix: 0.3,
-        }
-    }
-    
-    pub fn process(&mut self, input: f32) -> f32 {
-        let read_pos = (self.write_pos + self.buffer.len() - self.delay_samples) % self.buffer.len();
-        let delayed = self.buffer[read_pos];
-        
-        self.buffer[self.write_pos] = input + delayed * self.feedback;
-        self.write_pos = (self.write_pos + 1) % self.buffer.len();
-        
-        input + delayed * self.mix
-    }
-    
-    pub fn set_delay_time(&mut self, time_sec: f32, sample_rate: f32) {
-        self.delay_samples = ((time_sec * sample_rate) as usize).min(self.buffer.len() - 1);
-    }
-    
-    pub fn set_feedback(&mut self, feedback: f32) {
-        self.feedback = feedback.clamp(0.0, 0.95);
-    }
-    
-    pub fn set_mix(&mut self, mix: f32) {
-        self.mix = mix.clamp(0.0, 1.0);
-    }
+/// Форма АЧХ biquad фильтра
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FilterShape {
+    Lowpass,
+    Highpass,
+    Bandpass,
+    Peaking,
+    LowShelf,
+    HighShelf,
+    Notch,
+    Allpass,
 }
 
-/// Простой biquad фильтр
+/// Biquad фильтр (формулы RBJ Audio EQ Cookbook)
 pub struct BiquadFilter {
     b0: f32, b1: f32, b2: f32,
     a1: f32, a2: f32,
     x1: f32, x2: f32,
     y1: f32, y2: f32,
+    // Последний расчет (форма, частота, частота дискретизации, Q, усиление): повтор не пересчитывает коэффициенты
+    design: Option<(FilterShape, f32, f32, f32, f32)>,
 }
 
 impl BiquadFilter {
@@ -232,61 +466,136 @@ impl BiquadFilter {
             a1: 0.0, a2: 0.0,
             x1: 0.0, x2: 0.0,
             y1: 0.0, y2: 0.0,
+            design: None,
         }
     }
     
     pub fn lowpass(&mut self, freq: f32, sample_rate: f32, q: f32) {
-        let omega = 2.0 * std::f32::consts::PI * freq / sample_rate;
-        let sin_omega = omega.sin();
-        let cos_omega = omega.cos();
-        let alpha = sin_omega / (2.0 * q);
-        
-        let b0 = (1.0 - cos_omega) / 2.0;
-        let b1 = 1.0 - cos_omega;
-        let b2 = (1.0 - cos_omega) / 2.0;
-        let a0 = 1.0 + alpha;
-        let a1 = -2.0 * cos_omega;
-        let a2 = 1.0 - alpha;
-        
-        self.b0 = b0 / a0;
-        self.b1 = b1 / a0;
-        self.b2 = b2 / a0;
-        self.a1 = a1 / a0;
-        self.a2 = a2 / a0;
+        self.design(FilterShape::Lowpass, freq, sample_rate, q, 0.0);
     }
     
     pub fn highpass(&mut self, freq: f32, sample_rate: f32, q: f32) {
-        let omega = 2.0 * std::f32::consts::PI * freq / sample_rate;
-        let sin_omega = omega.sin();
-        let cos_omega = omega.cos();
-        let alpha = sin_omega / (2.0 * q);
+        self.design(FilterShape::Highpass, freq, sample_rate, q, 0.0);
+    }
+    
+    pub fn bandpass(&mut self, freq: f32, sample_rate: f32, q: f32) {
+        self.design(FilterShape::Bandpass, freq, sample_rate, q, 0.0);
+    }
+    
+    /// Колокол: подъем или завал `gain_db` вокруг `freq`, ширина задается Q
+    pub fn peaking(&mut self, freq: f32, sample_rate: f32, q: f32, gain_db: f32) {
+        self.design(FilterShape::Peaking, freq, sample_rate, q, gain_db);
+    }
+    
+    /// Полка: все ниже `freq` поднимается или опускается на `gain_db` (Q 0.707 - без горба у перегиба)
+    pub fn low_shelf(&mut self, freq: f32, sample_rate: f32, q: f32, gain_db: f32) {
+        self.design(FilterShape::LowShelf, freq, sample_rate, q, gain_db);
+    }
+    
+    /// Полка: все выше `freq` поднимается или опускается на `gain_db`
+    pub fn high_shelf(&mut self, freq: f32, sample_rate: f32, q: f32, gain_db: f32) {
+        self.design(FilterShape::HighShelf, freq, sample_rate, q, gain_db);
+    }
+    
+    /// Режекторный фильтр: глубокий провал на `freq` (сетевой фон, свист)
+    pub fn notch(&mut self, freq: f32, sample_rate: f32, q: f32) {
+        self.design(FilterShape::Notch, freq, sample_rate, q, 0.0);
+    }
+    
+    /// Фазовый фильтр: АЧХ ровная, фаза поворачивается на 180° вокруг `freq`
+    pub fn allpass(&mut self, freq: f32, sample_rate: f32, q: f32) {
+        self.design(FilterShape::Allpass, freq, sample_rate, q, 0.0);
+    }
+    
+    pub fn process(&mut self, input: f32) -> f32 {
+        let output = self.b0 * input + self.b1 * self.x1 + self.b2 * self.x2
+                   - self.a1 * self.y1 - self.a2 * self.y2;
         
-        let b0 = (1.0 + cos_omega) / 2.0;
-        let b1 = -(1.0 + cos_omega);
-        let b2 = (1.0 + cos_omega) / 2.0;
-        let a0 = 1.0 + alpha;
-        let a1 = -2.0 * cos_omega;
-        let a2 = 1.0 - alpha;
+        self.x2 = self.x1;
+        self.x1 = input;
+        self.y2 = self.y1;
+        self.y1 = output;
         
-        self.b0 = b0 / a0;
-        self.b1 = b1 / a0;
-        self.b2 = b2 / a0;
-        self.a1 = a1 / a0;
-        self.a2 = a2 / a0;
+        output
     }
     
-    pub fn bandpass(&mut self, freq: f32, sample_rate: f32, q: f32) {
+    /// Очищает историю фильтра (коэффициенты остаются)
+    pub fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.x2 = 0.0;
+        self.y1 = 0.0;
+        self.y2 = 0.0;
+    }
+    
+    /// Усиление фильтра на частоте `freq` (модуль передаточной функции, 1.0 - без изменений)
+    pub fn magnitude(&self, freq: f32, sample_rate: f32) -> f32 {
+        let omega = 2.0 * std::f64::consts::PI * freq as f64 / sample_rate as f64;
+        let (cos1, sin1) = (omega.cos(), omega.sin());
+        let (cos2, sin2) = ((2.0 * omega).cos(), (2.0 * omega).sin());
+        let (b0, b1, b2) = (self.b0 as f64, self.b1 as f64, self.b2 as f64);
+        let (a1, a2) = (self.a1 as f64, self.a2 as f64);
+        let numerator = (b0 + b1 * cos1 + b2 * cos2).powi(2) + (b1 * sin1 + b2 * sin2).powi(2);
+        let denominator = (1.0 + a1 * cos1 + a2 * cos2).powi(2) + (a1 * sin1 + a2 * sin2).powi(2);
+        (numerator / denominator).sqrt() as f32
+    }
+    
+    /// Считает коэффициенты формы `shape`; те же параметры повторно не пересчитываются
+    fn design(&mut self, shape: FilterShape, freq: f32, sample_rate: f32, q: f32, gain_db: f32) {
+        let design = Some((shape, freq, sample_rate, q, gain_db));
+        if self.design == design {
+            return;
+        }
+        self.design = design;
+        
         let omega = 2.0 * std::f32::consts::PI * freq / sample_rate;
         let sin_omega = omega.sin();
         let cos_omega = omega.cos();
         let alpha = sin_omega / (2.0 * q);
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let shelf = 2.0 * a.sqrt() * alpha;
         
-        let b0 = alpha;
-        let b1 = 0.0;
-        let b2 = -alpha;
-        let a0 = 1.0 + alpha;
-        let a1 = -2.0 * cos_omega;
-        let a2 = 1.0 - alpha;
+        let (b0, b1, b2, a0, a1, a2) = match shape {
+            FilterShape::Lowpass => (
+                (1.0 - cos_omega) / 2.0, 1.0 - cos_omega, (1.0 - cos_omega) / 2.0,
+                1.0 + alpha, -2.0 * cos_omega, 1.0 - alpha,
+            ),
+            FilterShape::Highpass => (
+                (1.0 + cos_omega) / 2.0, -(1.0 + cos_omega), (1.0 + cos_omega) / 2.0,
+                1.0 + alpha, -2.0 * cos_omega, 1.0 - alpha,
+            ),
+            FilterShape::Bandpass => (
+                alpha, 0.0, -alpha,
+                1.0 + alpha, -2.0 * cos_omega, 1.0 - alpha,
+            ),
+            FilterShape::Peaking => (
+                1.0 + alpha * a, -2.0 * cos_omega, 1.0 - alpha * a,
+                1.0 + alpha / a, -2.0 * cos_omega, 1.0 - alpha / a,
+            ),
+            FilterShape::LowShelf => (
+                a * ((a + 1.0) - (a - 1.0) * cos_omega + shelf),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos_omega),
+                a * ((a + 1.0) - (a - 1.0) * cos_omega - shelf),
+                (a + 1.0) + (a - 1.0) * cos_omega + shelf,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos_omega),
+                (a + 1.0) + (a - 1.0) * cos_omega - shelf,
+            ),
+            FilterShape::HighShelf => (
+                a * ((a + 1.0) + (a - 1.0) * cos_omega + shelf),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_omega),
+                a * ((a + 1.0) + (a - 1.0) * cos_omega - shelf),
+                (a + 1.0) - (a - 1.0) * cos_omega + shelf,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos_omega),
+                (a + 1.0) - (a - 1.0) * cos_omega - shelf,
+            ),
+            FilterShape::Notch => (
+                1.0, -2.0 * cos_omega, 1.0,
+                1.0 + alpha, -2.0 * cos_omega, 1.0 - alpha,
+            ),
+            FilterShape::Allpass => (
+                1.0 - alpha, -2.0 * cos_omega, 1.0 + alpha,
+                1.0 + alpha, -2.0 * cos_omega, 1.0 - alpha,
+            ),
+        };
         
         self.b0 = b0 / a0;
         self.b1 = b1 / a0;
@@ -294,159 +603,516 @@ impl BiquadFilter {
         self.a1 = a1 / a0;
         self.a2 = a2 / a0;
     }
-    
-    pub fn process(&mut self, input: f32) -> f32 {
-        let output = self.b0 * input + self.b1 * self.x1 + self.b2 * self.x2
-                   - self.a1 * self.y1 - self.a2 * self.y2;
-        
-        self.x2 = self.x1;
-        self.x1 = input;
-        self.y2 = self.y1;
-        self.y1 = output;
-        
-        output
+}
+
+impl Default for BiquadFilter {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-/// DSP процессор
-pub struct DspProcessor {
+/// Параметры эффекта на один кадр, снятые с `AudioParameters`. Сглаживается только центр полосы
+/// рации; остальные берутся как есть (время задержки плавно меняет сама линия задержки).
+#[derive(Debug, Clone, Copy)]
+pub struct FrameParameters {
+    pub pitch_shift: f32,
+    pub delay_time: f32,
+    pub delay_feedback: f32,
+    pub bandpass_center: f32, // Сглаженный
+    pub bandpass_q: f32,
+    pub robot_pitch: f32,
+}
+
+/// DSP состояние одного канала: у каждого канала свои фильтры, линии задержки и сдвиг тона
+pub struct DspChannel {
     pub delay: DelayEffect,
     pub lowpass: BiquadFilter,
     pub highpass: BiquadFilter,
     pub bandpass: BiquadFilter,
-    pub sample_rate: f32,
+    pub pitch_shifter: PitchShifter,
+    pub convolver: Option<Convolver>, // Появляется после загрузки импульсного отклика
+    pub robot: Vocoder,
+    pub vocoder: Vocoder, // Вокодер с внешней несущей
 }
 
-impl DspProcessor {
+impl DspChannel {
     pub fn new(sample_rate: f32, max_delay_samples: usize) -> Self {
+        let mut delay = DelayEffect::new(max_delay_samples);
+        delay.set_sample_rate(sample_rate);
         Self {
-            delay: DelayEffect::new(max_delay_samples),
+            delay,
             lowpass: BiquadFilter::new(),
             highpass: BiquadFilter::new(),
             bandpass: BiquadFilter::new(),
-            sample_rate,
+            pitch_shifter: PitchShifter::new(sample_rate),
+            convolver: None,
+            robot: Vocoder::new(sample_rate, ROBOT_VOCODER_BANDS),
+            vocoder: Vocoder::new(sample_rate, SIDECHAIN_VOCODER_BANDS),
         }
     }
     
-    pub fn process_effect(&mut self, input: f32, effect_type: EffectType, params: &AudioParameters) -> f32 {
+    /// Очищает линии задержки, фильтры, сдвиг тона и хвост свертки
+    pub fn reset(&mut self) {
+        self.delay.clear();
+        self.lowpass.reset();
+        self.highpass.reset();
+        self.bandpass.reset();
+        self.pitch_shifter.reset();
+        self.robot.reset();
+        self.vocoder.reset();
+        if let Some(ref mut convolver) = self.convolver {
+            convolver.reset();
+        }
+    }
+    
+    /// Канальная часть эффекта. Хвост Cave/Cathedral общий для всех каналов
+    /// и добавляется в `DspProcessor::process_frame`.
+    pub fn process_effect(&mut self, input: f32, effect_type: EffectType, params: &FrameParameters, sample_rate: f32) -> f32 {
         match effect_type {
             EffectType::None => input,
             
             EffectType::Monster => {
                 // Монстр: понижение тона + искажение
-                let pitched = input * 0.5; // Простое понижение
+                self.pitch_shifter.set_ratio(params.pitch_shift);
+                let pitched = self.pitch_shifter.process_sample(input);
                 let distorted = (pitched * 3.0).tanh(); // Мягкое искажение
                 distorted * 0.8
             },
             
             EffectType::HighPitch => {
                 // Высокий тон: повышение частоты
-                input * 1.5 // Простое повышение
+                self.pitch_shifter.set_ratio(params.pitch_shift);
+                self.pitch_shifter.process_sample(input)
             },
             
             EffectType::Cave => {
-                // Пещера: эхо + реверб
-                self.delay.set_delay_time(params.delay_time.load(Ordering::Relaxed), self.sample_rate);
-                self.delay.set_feedback(0.6);
-                self.delay.set_mix(0.4);
+                // Пещера: редкое эхо (дальше - плотный хвост среднего размера)
+                self.delay.set_delay_time(params.delay_time, sample_rate);
+                self.delay.set_feedback(params.delay_feedback);
+                self.delay.set_mix(0.3);
                 self.delay.process(input)
             },
             
             EffectType::Radio => {
                 // Рация: полосовой фильтр + искажение
                 self.bandpass.bandpass(
-                    params.bandpass_center.load(Ordering::Relaxed),
-                    self.sample_rate,
-                    2.0
+                    params.bandpass_center,
+                    sample_rate,
+                    params.bandpass_q
                 );
                 let filtered = self.bandpass.process(input);
                 (filtered * 2.0).tanh() * 0.7
             },
             
-            EffectType::Cathedral => {
-                // Собор: большой реверб
-                self.delay.set_delay_time(0.8, self.sample_rate);
-                self.delay.set_feedback(0.7);
-                self.delay.set_mix(0.6);
-                self.delay.process(input)
-            },
+            // Собор: только общий хвост
+            EffectType::Cathedral => input,
             
             EffectType::Underwater => {
                 // Под водой: низкие частоты + модуляция
-                self.lowpass.lowpass(800.0, self.sample_rate, 1.0);
+                self.lowpass.lowpass(800.0, sample_rate, 1.0);
                 self.lowpass.process(input) * 0.8
             },
             
+            EffectType::Convolution => {
+                // Без отклика свертка пропускает сигнал без изменений
+                match self.convolver {
+                    Some(ref mut convolver) => convolver.process_sample(input),
+                    None => input,
+                }
+            },
+            
+            EffectType::Robot => {
+                // Робот: огибающая голоса на пилообразной несущей фиксированной высоты
+                self.robot.set_pitch(params.robot_pitch);
+                self.robot.process(input)
+            },
+            
+            // Вокодер без сайдчейна звучит на внутренней несущей
+            EffectType::Vocoder => self.process_vocoder(input, None, params),
+            
             // AI эффекты - заглушки (в реальности будут обрабатываться через Core ML)
-            EffectType::Robot | EffectType::Demon | EffectType::Alien | EffectType::VoiceChanger => {
+            EffectType::Demon | EffectType::Alien | EffectType::VoiceChanger => {
                 // Для AI эффектов возвращаем входной сигнал
                 // В реальной реализации здесь будет вызов AI модели
                 input
             },
         }
     }
+    
+    /// Вокодер на сэмпле несущей `carrier` (сайдчейн этого канала).
+    /// Без несущей голос ложится на внутреннюю пилу высоты `robot_pitch`.
+    pub fn process_vocoder(&mut self, input: f32, carrier: Option<f32>, params: &FrameParameters) -> f32 {
+        match carrier {
+            Some(carrier) => self.vocoder.process_with_carrier(input, carrier),
+            None => {
+                self.vocoder.set_pitch(params.robot_pitch);
+                self.vocoder.process(input)
+            },
+        }
+    }
 }
 
-/// Главная структура аудио конвейера
-pub struct AudioPipeline {
-    pub parameters: AudioParameters,
-    pub noise_generator: NoiseGenerator,
-    pub dsp_processor: DspProcessor,
+/// DSP процессор
+pub struct DspProcessor {
+    pub channels: Vec<DspChannel>,
+    pub reverb: Reverb, // Одна реверберация на все каналы - ее стерео выход дает ширину
+    pub sample_rate: f32,
+    // Сглаживание частоты фильтра (время задержки сглаживает сама линия задержки)
+    smoothed_bandpass_center: SmoothedValue,
+}
+
+impl DspProcessor {
+    pub fn new(sample_rate: f32, max_delay_samples: usize) -> Self {
+        Self::with_channels(sample_rate, max_delay_samples, 1)
+    }
     
-    // AI процессор для NPU обработки
-    pub ai_processor: AIProcessor,
+    /// Создает процессор на `channels` каналов (1 - `MAX_CHANNELS`)
+    pub fn with_channels(sample_rate: f32, max_delay_samples: usize, channels: usize) -> Self {
+        Self {
+            channels: (0..channels.clamp(1, MAX_CHANNELS))
+                .map(|_| DspChannel::new(sample_rate, max_delay_samples))
+                .collect(),
+            reverb: Reverb::new(sample_rate),
+            sample_rate,
+            smoothed_bandpass_center: SmoothedValue::new(SmoothingMode::OnePole, SmoothingConfig::default().filter_time, sample_rate),
+        }
+    }
     
-    // Neural Engine процессор (Apple Silicon M1/M2/M3)
-    pub neural_processor: Option<NeuralVoiceProcessor>,
+    /// Очищает состояние всех каналов и хвост реверберации
+    pub fn reset(&mut self) {
+        self.channels.iter_mut().for_each(DspChannel::reset);
+        self.reverb.clear();
+    }
     
-    // Буферы для обработки
-    pub input_buffer: HeapRb<f32>,
-    pub output_buffer: HeapRb<f32>,
+    /// Обрабатывает блок эффектом вместе с миксом (до выходного усиления).
+    /// `mix` - микс на каждый сэмпл, `ai` - выход AI процессора для AI эффектов,
+    /// `carrier` - внешняя несущая эффекта Vocoder по каналам (None - сайдчейна нет).
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_effect(
+        &mut self,
+        effect_type: EffectType,
+        input: &[Vec<f32>],
+        ai: &[Vec<f32>],
+        carrier: Option<&[Vec<f32>]>,
+        mix: &[f32],
+        params: &AudioParameters,
+        output: &mut [Vec<f32>],
+    ) {
+        let channel_count = input.len().min(output.len());
+        let mut frame = [0.0f32; MAX_CHANNELS];
+        let mut carrier_frame = [0.0f32; MAX_CHANNELS];
+        match effect_type {
+            EffectType::None => {
+                for (out, samples) in output.iter_mut().zip(input) {
+                    out[..mix.len()].copy_from_slice(&samples[..mix.len()]);
+                }
+            },
+            // AI эффекты заменяют сигнал целиком
+            EffectType::Demon | EffectType::Alien => {
+                for (out, samples) in output.iter_mut().zip(ai) {
+                    out[..mix.len()].copy_from_slice(&samples[..mix.len()]);
+                }
+            },
+            // VoiceChanger: выход AI плюс пещера поверх него
+            EffectType::VoiceChanger => {
+                for (i, &effect_mix) in mix.iter().enumerate() {
+                    for (sample, channel) in frame.iter_mut().zip(ai) {
+                        *sample = channel[i];
+                    }
+                    self.process_frame(&mut frame[..channel_count], EffectType::Cave, params);
+                    for ((out, channel), &processed) in output.iter_mut().zip(ai).zip(frame.iter()) {
+                        out[i] = channel[i] * (1.0 - effect_mix) + processed * effect_mix;
+                    }
+                }
+            },
+            _ => {
+                for (i, &effect_mix) in mix.iter().enumerate() {
+                    for (sample, channel) in frame.iter_mut().zip(input) {
+                        *sample = channel[i];
+                    }
+                    if let Some(carrier) = carrier {
+                        for (sample, channel) in carrier_frame.iter_mut().zip(carrier) {
+                            *sample = channel[i];
+                        }
+                    }
+                    let carrier = carrier.map(|_| &carrier_frame[..channel_count]);
+                    self.process_frame_with_carrier(&mut frame[..channel_count], carrier, effect_type, params);
+                    for ((out, channel), &processed) in output.iter_mut().zip(input).zip(frame.iter()) {
+                        out[i] = channel[i] * (1.0 - effect_mix) + processed * effect_mix;
+                    }
+                }
+            },
+        }
+    }
     
-    // Каналы для коммуникации с AI процессором
-    pub ai_input_sender: Option<Sender<Vec<f32>>>,
-    pub ai_output_receiver: Option<Receiver<Vec<f32>>>,
+    /// Задает времена сглаживания задержки и частот фильтров
+    pub fn set_smoothing(&mut self, config: &SmoothingConfig) {
+        for channel in &mut self.channels {
+            channel.delay.set_glide_time(config.delay_time);
+        }
+        self.smoothed_bandpass_center.set_time(config.filter_time, self.sample_rate);
+    }
     
-    // Платформо-специфичная аудио подсистема
-    pub platform_audio: Option<platform::PlatformAudioImpl>,
+    /// Обрабатывает моно сэмпл (первый канал)
+    pub fn process_effect(&mut self, input: f32, effect_type: EffectType, params: &AudioParameters) -> f32 {
+        let mut frame = [input];
+        self.process_frame(&mut frame, effect_type, params);
+        frame[0]
+    }
     
-    // Счетчики и статистика
-    pub samples_processed: u64,
-    pub is_processing: AtomicBool,
+    /// Обрабатывает кадр: по сэмплу на канал, лишние сэмплы сверх количества каналов не трогаются
+    pub fn process_frame(&mut self, frame: &mut [f32], effect_type: EffectType, params: &AudioParameters) {
+        self.process_frame_with_carrier(frame, None, effect_type, params);
+    }
     
-    // Статистика производительности
-    pub performance_stats: PerformanceStats,
-}
-
-impl AudioPipeline {
-    pub fn new(sample_rate: f32, buffer_size: usize) -> Self {
-        let max_delay_samples = (sample_rate * 2.0) as usize; // 2 секунды максимум
-        
-        // Создаем конфигурацию для AI процессора
-        let ai_config = AIConfig {
-            sample_rate,
-            buffer_size,
-            model_path: None,
-            use_npu: true,
-            processing_mode: AIProcessingMode::Balanced,
+    /// Обрабатывает кадр с внешней несущей вокодера (по сэмплу на канал)
+    pub fn process_frame_with_carrier(&mut self, frame: &mut [f32], carrier: Option<&[f32]>, effect_type: EffectType, params: &AudioParameters) {
+        self.smoothed_bandpass_center.set_target(params.bandpass_center.load(Ordering::Relaxed));
+        let frame_params = FrameParameters {
+            pitch_shift: params.pitch_shift.load(Ordering::Relaxed),
+            delay_time: params.delay_time.load(Ordering::Relaxed),
+            delay_feedback: params.delay_feedback.load(Ordering::Relaxed),
+            bandpass_center: self.smoothed_bandpass_center.next_value(),
+            bandpass_q: params.bandpass_q.load(Ordering::Relaxed),
+            robot_pitch: params.robot_pitch.load(Ordering::Relaxed),
         };
         
-        Self {
-            parameters: AudioParameters::default(),
-            noise_generator: NoiseGenerator::new(),
-            dsp_processor: DspProcessor::new(sample_rate, max_delay_samples),
-            ai_processor: AIProcessor::new(ai_config),
+        for (index, (channel, sample)) in self.channels.iter_mut().zip(frame.iter_mut()).enumerate() {
+            *sample = match (effect_type, carrier) {
+                (EffectType::Vocoder, Some(carrier)) => channel.process_vocoder(*sample, carrier.get(index).copied(), &frame_params),
+                _ => channel.process_effect(*sample, effect_type, &frame_params, self.sample_rate),
+            };
+        }
         
-        // Инициализируем Neural Engine процессор на Apple Silicon
-        neural_processor: {
-            #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
-            {
-                let neural_config = NeuralConfig {
-                    sample_rate,
-                    buffer_size,
-                    max_effects: 8,
+        let size = params.reverb_size.load(Ordering::Relaxed);
+        let damping = params.reverb_damping.load(Ordering::Relaxed);
+        match effect_type {
+            EffectType::Cave => {
+                self.reverb.set_room_size(size * 0.8);
+                self.reverb.set_damping(damping);
+                self.reverb.set_pre_delay(0.015);
+                self.reverb.set_mix(0.35, 0.8);
+                self.apply_reverb(frame);
+            },
+            EffectType::Cathedral => {
+                // Собор: длинный хвост с заметной предзадержкой и мягким затуханием
+                self.reverb.set_room_size(0.8 + size * 0.2);
+                self.reverb.set_damping(damping * 0.6);
+                self.reverb.set_pre_delay(0.04);
+                self.reverb.set_mix(0.5, 0.6);
+                self.apply_reverb(frame);
+            },
+            _ => {}
+        }
+    }
+    
+    /// Задержка, которую вносит эффект, в сэмплах
+    pub fn latency(&self, effect_type: EffectType) -> usize {
+        let channel = &self.channels[0];
+        match effect_type {
+            EffectType::Monster | EffectType::HighPitch => channel.pitch_shifter.sample_latency(),
+            EffectType::Convolution => channel.convolver.as_ref().map_or(0, |c| c.latency()),
+            _ => 0,
+        }
+    }
+    
+    /// Устанавливает свертку во все каналы (у каждого канала своя копия хвоста)
+    pub fn set_convolver(&mut self, convolver: Convolver) {
+        for channel in &mut self.channels {
+            channel.convolver = Some(convolver.clone());
+        }
+    }
+    
+    /// Хвост реверберации от моно суммы кадра: левый выход идет в четные каналы, правый - в нечетные.
+    /// В моно кадр попадает среднее обоих выходов.
+    fn apply_reverb(&mut self, frame: &mut [f32]) {
+        let channels = frame.len().min(self.channels.len());
+        if channels == 0 {
+            return;
+        }
+        let frame = &mut frame[..channels];
+        let mono = frame.iter().sum::<f32>() / channels as f32;
+        let dry = self.reverb.dry();
+        let (left, right) = self.reverb.process_wet(mono);
+        
+        if channels == 1 {
+            frame[0] = frame[0] * dry + (left + right) * 0.5;
+            return;
+        }
+        for (channel, sample) in frame.iter_mut().enumerate() {
+            *sample = *sample * dry + if channel % 2 == 0 { left } else { right };
+        }
+    }
+}
+
+/// Запущенный платформенный аудио поток (`AudioPipeline::start_audio_stream`).
+///
+/// Пока поток идет, устройством владеет он, а не конвейер: callback держит конвейер по слабой
+/// ссылке, и если последний `Arc` конвейера освободится прямо в аудио потоке, устройство
+/// не будет останавливать само себя из своего же потока. `stop` (или `drop`) останавливает
+/// поток и возвращает устройство конвейеру, если тот еще жив.
+pub struct AudioStream {
+    platform_audio: Option<platform::PlatformAudioImpl>,
+    pipeline: Weak<Mutex<AudioPipeline>>,
+    dropouts: Arc<AtomicU64>,
+}
+
+impl AudioStream {
+    /// Статистика устройства; блоки, пропущенные callback'ом из-за занятого конвейера, входят в underrun
+    pub fn stream_stats(&self) -> platform::StreamStats {
+        let mut stats = self.platform_audio
+            .as_ref()
+            .map(|p| p.stream_stats())
+            .unwrap_or_default();
+        stats.underruns += self.dropouts.load(Ordering::Relaxed);
+        stats
+    }
+    
+    /// Останавливает поток и возвращает устройство конвейеру
+    pub fn stop(mut self) -> Result<(), String> {
+        self.finish()
+    }
+    
+    fn finish(&mut self) -> Result<(), String> {
+        let Some(mut platform_audio) = self.platform_audio.take() else {
+            return Ok(());
+        };
+        let result = platform_audio.stop().map_err(|e| e.to_string());
+        if let Some(pipeline) = self.pipeline.upgrade() {
+            let mut pipeline = pipeline.lock().unwrap_or_else(PoisonError::into_inner);
+            pipeline.stop_processing();
+            pipeline.platform_audio = Some(platform_audio);
+        }
+        result
+    }
+}
+
+impl Drop for AudioStream {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Главная структура аудио конвейера
+pub struct AudioPipeline {
+    pub parameters: Arc<AudioParameters>, // Общие с UI (см. `parameters()`)
+    pub noise_generator: NoiseGenerator,
+    pub dsp_processor: DspProcessor,
+    
+    // AI процессор для NPU обработки
+    pub ai_processor: AIProcessor,
+    
+    // Цепочка голосовых эффектов (есть на всех платформах, на Apple Silicon - с Neural Engine)
+    pub neural_processor: Option<NeuralVoiceProcessor>,
+    
+    // Отслеживание основного тона входа (тюнер)
+    pub pitch_detector: PitchDetector,
+    pub pitch_monitor: Arc<PitchMonitor>,
+    
+    // Срез низа и верха по каналам (`highpass_freq`/`lowpass_freq`) и эквалайзер до и после эффекта
+    cut_filters: Vec<[BiquadFilter; 2]>,
+    cut_frequencies: [f32; 2], // На что рассчитаны `cut_filters` (NaN - не рассчитаны)
+    smoothed_highpass: SmoothedValue,
+    smoothed_lowpass: SmoothedValue,
+    pre_eq: ParametricEq,
+    post_eq: ParametricEq,
+    
+    // Динамика: гейт и компрессор на входе эффекта, лимитер после выходного усиления
+    gate: Gate,
+    compressor: Compressor,
+    limiter: Limiter,
+    pub dynamics_meter: Arc<DynamicsMeter>,
+    
+    // Буферы для обработки
+    pub input_buffer: HeapRb<f32>,
+    pub output_buffer: HeapRb<f32>,
+    work_buffers: Vec<Vec<f32>>,     // Каналы конвейера (обработка идет на месте)
+    mono_buffer: Vec<f32>,           // Моно сумма входа для тюнера
+    planar_input: Vec<Vec<f32>>,     // Разложенный чередующийся вход
+    planar_output: Vec<Vec<f32>>,    // Выход до сборки в чередующийся формат
+    
+    // Переход с частоты устройства на частоту обработки (None - частоты совпадают)
+    rate_converter: Option<RateConverter>,
+    
+    // Сайдчейн: внешняя несущая вокодера (музыка, синтезатор), разведенная по каналам
+    sidechain_buffers: Vec<Vec<f32>>,
+    sidechain_converter: Option<RateConverter>, // Несущая идет на частоту обработки отдельно от входа
+    sidechain_active: bool,                     // Несущая пришла в текущем блоке
+    
+    // Загруженный импульсный отклик (нужен, чтобы пересобрать свертку при смене частоты)
+    impulse_response: Option<ImpulseResponse>,
+    
+    // Сглаживание усиления, микса и уровня шума (цели обновляются раз в блок)
+    smoothing: SmoothingConfig,
+    smoothed_input_gain: SmoothedValue,
+    smoothed_output_gain: SmoothedValue,
+    smoothed_effect_mix: SmoothedValue,
+    smoothed_noise_level: SmoothedValue,
+    
+    // Переход между эффектами: запасные DSP процессоры для доигрывающих хвостов
+    effect_switcher: EffectSwitcher,
+    effect_buffers: Vec<Vec<f32>>,   // Выход активного эффекта
+    retiring_input: Vec<Vec<f32>>,   // Вход уходящего эффекта (с затуханием)
+    retiring_output: Vec<Vec<f32>>,
+    ai_buffers: Vec<Vec<f32>>,       // Выход AI процессора, разведенный по каналам
+    mix_ramp: Vec<f32>,              // Сглаженный микс на каждый сэмпл блока
+    
+    // Каналы для коммуникации с AI процессором
+    pub ai_input_sender: Option<Sender<Vec<f32>>>,
+    pub ai_output_receiver: Option<Receiver<Vec<f32>>>,
+    
+    // Платформо-специфичная аудио подсистема (пока поток запущен, ею владеет `AudioStream`)
+    pub platform_audio: Option<platform::PlatformAudioImpl>,
+    stream_dropouts: Arc<AtomicU64>, // Блоки, которые callback потока выдал тишиной (конвейер был занят)
+    
+    // Счетчики и статистика
+    pub samples_processed: u64,
+    pub is_processing: AtomicBool,
+    
+    // Статистика производительности
+    pub performance_stats: PerformanceStats,
+}
+
+impl AudioPipeline {
+    pub fn new(sample_rate: f32, buffer_size: usize) -> Self {
+        Self::with_channels(sample_rate, buffer_size, 1)
+    }
+    
+    /// Создает конвейер на `channels` каналов (1 - `MAX_CHANNELS`).
+    /// Вход и выход могут иметь другое количество каналов - они сводятся или разводятся на границе.
+    pub fn with_channels(sample_rate: f32, buffer_size: usize, channels: usize) -> Self {
+        let channels = channels.clamp(1, MAX_CHANNELS);
+        let max_delay_samples = (sample_rate * MAX_DELAY_SECONDS) as usize;
+        
+        // Создаем конфигурацию для AI процессора
+        let ai_config = AIConfig {
+            sample_rate,
+            buffer_size,
+            model_path: None,
+            use_npu: true,
+            processing_mode: AIProcessingMode::Balanced,
+        };
+        
+        let parameters = AudioParameters::default();
+        parameters.sample_rate.store(sample_rate, Ordering::Relaxed);
+        parameters.buffer_size.store(buffer_size as u32, Ordering::Relaxed);
+        let smoothing = SmoothingConfig::default();
+        
+        Self {
+            parameters: Arc::new(parameters),
+            noise_generator: NoiseGenerator::new(),
+            dsp_processor: DspProcessor::with_channels(sample_rate, max_delay_samples, channels),
+            ai_processor: AIProcessor::new(ai_config),
+        
+            // Цепочка голосовых эффектов работает на любой платформе (на Apple Silicon - с Neural Engine)
+            neural_processor: {
+                let neural_config = NeuralConfig {
+                    sample_rate,
+                    buffer_size,
+                    max_effects: 8,
                     quality_preset: QualityPreset::Medium,
                     enable_real_time: true,
+                    channels,
                 };
                 
                 match NeuralVoiceProcessor::new(neural_config) {
@@ -459,17 +1125,49 @@ impl AudioPipeline {
                         None
                     }
                 }
-            }
-            #[cfg(not(all(target_os = "macos", target_arch = "aarch64")))]
-            {
-                None
-            }
-        },
+            },
+            pitch_detector: PitchDetector::new(sample_rate),
+            pitch_monitor: Arc::new(PitchMonitor::default()),
+            cut_filters: (0..channels).map(|_| [BiquadFilter::new(), BiquadFilter::new()]).collect(),
+            cut_frequencies: [f32::NAN; 2],
+            smoothed_highpass: SmoothedValue::new(SmoothingMode::OnePole, smoothing.filter_time, sample_rate),
+            smoothed_lowpass: SmoothedValue::new(SmoothingMode::OnePole, smoothing.filter_time, sample_rate),
+            pre_eq: ParametricEq::new(sample_rate, channels),
+            post_eq: ParametricEq::new(sample_rate, channels),
+            gate: Gate::new(sample_rate),
+            compressor: Compressor::new(sample_rate),
+            limiter: Limiter::new(sample_rate, channels),
+            dynamics_meter: Arc::new(DynamicsMeter::default()),
             input_buffer: HeapRb::new(buffer_size * 4),
             output_buffer: HeapRb::new(buffer_size * 4),
+            work_buffers: vec![vec![0.0; buffer_size]; channels],
+            mono_buffer: vec![0.0; buffer_size],
+            planar_input: Vec::new(),
+            planar_output: Vec::new(),
+            rate_converter: None,
+            sidechain_buffers: Vec::new(),
+            sidechain_converter: None,
+            sidechain_active: false,
+            impulse_response: None,
+            smoothing,
+            smoothed_input_gain: SmoothedValue::new(SmoothingMode::Linear, smoothing.gain_time, sample_rate),
+            smoothed_output_gain: SmoothedValue::new(SmoothingMode::Linear, smoothing.gain_time, sample_rate),
+            smoothed_effect_mix: SmoothedValue::new(SmoothingMode::Linear, smoothing.mix_time, sample_rate),
+            smoothed_noise_level: SmoothedValue::new(SmoothingMode::Linear, smoothing.gain_time, sample_rate),
+            effect_switcher: EffectSwitcher::new(
+                (0..RETIRING_EFFECTS).map(|_| DspProcessor::with_channels(sample_rate, max_delay_samples, channels)).collect(),
+                DEFAULT_EFFECT_CROSSFADE,
+                sample_rate,
+            ),
+            effect_buffers: vec![vec![0.0; buffer_size]; channels],
+            retiring_input: vec![vec![0.0; buffer_size]; channels],
+            retiring_output: vec![vec![0.0; buffer_size]; channels],
+            ai_buffers: vec![vec![0.0; buffer_size]; channels],
+            mix_ramp: vec![0.0; buffer_size],
             ai_input_sender: None,
             ai_output_receiver: None,
             platform_audio: None,
+            stream_dropouts: Arc::new(AtomicU64::new(0)),
             samples_processed: 0,
             is_processing: AtomicBool::new(false),
             performance_stats: PerformanceStats::default(),
@@ -480,8 +1178,6 @@ impl AudioPipeline {
     pub fn new_with_platform() -> Result<Self, Box<dyn std::error::Error>> {
         use platform::{PlatformAudio, PlatformAudioImpl};
         
-        let mut pipeline = Self::new(44100.0, 512);
-        
         match PlatformAudioImpl::initialize() {
             Ok(platform_audio) => {
                 let sample_rate = platform_audio.get_sample_rate();
@@ -489,9 +1185,8 @@ impl AudioPipeline {
                 
                 println!("🎯 Платформа инициализирована: {}", platform_audio.platform_info());
                 
-                // Обновляем параметры на основе возможностей платформы
-                pipeline.parameters.sample_rate.store(sample_rate, Ordering::Relaxed);
-                pipeline.parameters.buffer_size.store(buffer_size as u32, Ordering::Relaxed);
+                // Создаем конвейер под параметры, которые согласовала платформа
+                let mut pipeline = Self::new(sample_rate, buffer_size);
                 pipeline.platform_audio = Some(platform_audio);
                 
                 Ok(pipeline)
@@ -499,192 +1194,753 @@ impl AudioPipeline {
             Err(e) => {
                 println!("⚠️  Не удалось инициализировать платформу: {}", e);
                 println!("ℹ️  Используется базовая реализация без платформо-специфичных оптимизаций");
-                Ok(pipeline)
+                Ok(Self::new(44100.0, 512))
             }
         }
     }
     
+    /// Создает callback для платформенного аудио потока, который прогоняет блоки через конвейер.
+    ///
+    /// Конвейер захватывается по слабой ссылке. Если он занят другим потоком или уже
+    /// освобожден, callback выдает тишину вместо блокировки аудио потока. Блок, пропущенный
+    /// из-за занятого конвейера, считается underrun в `get_stream_stats`. Чтобы ручки UI
+    /// не занимали конвейер, параметры меняются через `parameters()` без блокировки.
+    pub fn process_callback(pipeline: &Arc<Mutex<AudioPipeline>>) -> platform::ProcessCallback {
+        let dropouts = pipeline.lock().unwrap_or_else(PoisonError::into_inner).stream_dropouts.clone();
+        let pipeline = Arc::downgrade(pipeline);
+        Box::new(move |input: &[f32], output: &mut [f32]| {
+            let Some(pipeline) = pipeline.upgrade() else {
+                output.fill(0.0);
+                return;
+            };
+            match pipeline.try_lock() {
+                Ok(mut pipeline) => pipeline.process_block(input, output),
+                Err(_) => {
+                    dropouts.fetch_add(1, Ordering::Relaxed);
+                    output.fill(0.0);
+                }
+            };
+        })
+    }
+    
+    /// Подключает конвейер к платформенному аудио потоку и запускает обработку.
+    /// Дальше платформа сама забирает вход с устройства, вызывает `process_block`
+    /// и отдает результат на выход. На время работы устройство переходит к `AudioStream`.
+    pub fn start_audio_stream(pipeline: &Arc<Mutex<AudioPipeline>>) -> Result<AudioStream, String> {
+        let callback = Self::process_callback(pipeline);
+        let mut guard = pipeline.lock().map_err(|_| "Конвейер недоступен".to_string())?;
+        let mut platform_audio = guard
+            .platform_audio
+            .take()
+            .ok_or_else(|| "Платформенное аудио не инициализировано".to_string())?;
+        
+        platform_audio.set_process_callback(callback);
+        if let Err(e) = platform_audio.start() {
+            guard.platform_audio = Some(platform_audio);
+            return Err(e.to_string());
+        }
+        guard.stream_dropouts.store(0, Ordering::Relaxed);
+        guard.start_processing();
+        Ok(AudioStream {
+            platform_audio: Some(platform_audio),
+            pipeline: Arc::downgrade(pipeline),
+            dropouts: guard.stream_dropouts.clone(),
+        })
+    }
+    
+    /// Получает статистику платформенного аудио потока (блоки, overrun/underrun).
+    /// Блоки, пропущенные callback'ом из-за занятого конвейера, входят в underrun.
+    /// Пока поток запущен, статистику устройства отдает `AudioStream::stream_stats`.
+    pub fn get_stream_stats(&self) -> platform::StreamStats {
+        let mut stats = self.platform_audio
+            .as_ref()
+            .map(|p| p.stream_stats())
+            .unwrap_or_default();
+        stats.underruns += self.stream_dropouts.load(Ordering::Relaxed);
+        stats
+    }
+    
+    /// Обрабатывает моно блок (вход и выход по одному каналу)
     pub fn process_block(&mut self, input: &[f32], output: &mut [f32]) {
+        self.process_planar(&[input], &mut [output]);
+    }
+    
+    /// Обрабатывает моно блок со второй входной линией (сайдчейном). Сайдчейн - несущая
+    /// эффекта `Vocoder`: голос со входа накладывается на него (например, на музыкальную подложку).
+    pub fn process_block_with_sidechain(&mut self, input: &[f32], sidechain: &[f32], output: &mut [f32]) {
+        self.process_planar_with_sidechain(&[input], &[sidechain], &mut [output]);
+    }
+    
+    /// Обрабатывает чередующиеся кадры (L R L R ...). Количество каналов входа и выхода
+    /// может отличаться от количества каналов конвейера: моно вход разводится по всем каналам,
+    /// многоканальный выход в моно сводится средним.
+    pub fn process_interleaved(&mut self, input: &[f32], input_channels: usize, output: &mut [f32], output_channels: usize) {
+        if input_channels == 0 || output_channels == 0 {
+            output.fill(0.0);
+            return;
+        }
+        let frames = (input.len() / input_channels).min(output.len() / output_channels);
+        
+        let mut planar_input = std::mem::take(&mut self.planar_input);
+        let mut planar_output = std::mem::take(&mut self.planar_output);
+        channels::resize_planar(&mut planar_input, input_channels, frames);
+        channels::resize_planar(&mut planar_output, output_channels, frames);
+        
+        channels::deinterleave(&input[..frames * input_channels], &mut planar_input);
+        self.process_planar(&planar_input, &mut planar_output);
+        channels::interleave(&planar_output, &mut output[..frames * output_channels]);
+        
+        self.planar_input = planar_input;
+        self.planar_output = planar_output;
+    }
+    
+    /// Обрабатывает планарные каналы (по буферу на канал). Обрабатывается столько кадров,
+    /// сколько есть в самом коротком буфере.
+    pub fn process_planar<I: AsRef<[f32]>, O: AsMut<[f32]>>(&mut self, inputs: &[I], outputs: &mut [O]) {
+        self.run_planar(inputs, None::<&[&[f32]]>, outputs);
+    }
+    
+    /// Обрабатывает планарные каналы с сайдчейном. Каналы сайдчейна приводятся к каналам
+    /// конвейера так же, как вход (моно несущая разводится по всем каналам).
+    pub fn process_planar_with_sidechain<I, S, O>(&mut self, inputs: &[I], sidechain: &[S], outputs: &mut [O])
+    where
+        I: AsRef<[f32]>,
+        S: AsRef<[f32]>,
+        O: AsMut<[f32]>,
+    {
+        self.run_planar(inputs, Some(sidechain), outputs);
+    }
+    
+    /// Общий путь планарной обработки (сайдчейн необязателен)
+    fn run_planar<I, S, O>(&mut self, inputs: &[I], sidechain: Option<&[S]>, outputs: &mut [O])
+    where
+        I: AsRef<[f32]>,
+        S: AsRef<[f32]>,
+        O: AsMut<[f32]>,
+    {
         if !self.is_processing.load(Ordering::Relaxed) {
             // Если обработка отключена, заполняем тишиной
-            output.fill(0.0);
+            outputs.iter_mut().for_each(|output| output.as_mut().fill(0.0));
             return;
         }
         
-        let input_gain = self.parameters.input_gain.load(Ordering::Relaxed);
-        let output_gain = self.parameters.output_gain.load(Ordering::Relaxed);
+        let frames = inputs.iter().map(|input| input.as_ref().len())
+            .chain(sidechain.into_iter().flatten().map(|channel| channel.as_ref().len()))
+            .chain(outputs.iter_mut().map(|output| output.as_mut().len()))
+            .min()
+            .unwrap_or(0);
+        
+        // Несущая переводится на частоту обработки тем же путем, что и вход
+        self.sidechain_active = sidechain.is_some();
+        if let Some(sidechain) = sidechain {
+            let channel_count = self.channels();
+            channels::resize_planar(&mut self.sidechain_buffers, channel_count, frames);
+            channels::remix(sidechain, &mut self.sidechain_buffers, frames);
+            if let Some(ref mut converter) = self.sidechain_converter {
+                let converted = converter.convert_input(&self.sidechain_buffers);
+                for (buffer, samples) in self.sidechain_buffers.iter_mut().zip(converted) {
+                    buffer.clear();
+                    buffer.extend_from_slice(samples);
+                }
+            }
+        }
+        
+        let mut work = std::mem::take(&mut self.work_buffers);
+        channels::resize_planar(&mut work, self.channels(), frames);
+        channels::remix(inputs, &mut work, frames);
+        match self.rate_converter.take() {
+            Some(mut converter) => {
+                converter.process(&mut work, |buffers| self.process_channels(buffers));
+                self.rate_converter = Some(converter);
+            }
+            None => self.process_channels(&mut work),
+        }
+        channels::remix(&work, outputs, frames);
+        self.work_buffers = work;
+        
+        self.samples_processed += frames as u64;
+    }
+    
+    /// Количество каналов обработки
+    pub fn channels(&self) -> usize {
+        self.dsp_processor.channels.len()
+    }
+    
+    /// Обрабатывает каналы конвейера на месте (все буферы одной длины, частота обработки)
+    fn process_channels(&mut self, work: &mut [Vec<f32>]) {
+        let frames = work.first().map_or(0, |channel| channel.len());
+        let channel_count = work.len();
+        
+        // Тон анализируется по исходному входу (моно сумме), до эффектов и шума
+        if self.parameters.pitch_tracking.load(Ordering::Relaxed) {
+            self.mono_buffer.resize(frames, 0.0);
+            channels::remix(work, std::slice::from_mut(&mut self.mono_buffer), frames);
+            let estimate = self.pitch_detector.process(&self.mono_buffer);
+            self.pitch_monitor.publish(estimate);
+        }
+        
+        // Усиление, микс и уровень шума меняются плавно: цели раз в блок, значения на каждом сэмпле
+        self.smoothed_input_gain.set_target(self.parameters.input_gain.load(Ordering::Relaxed));
+        self.smoothed_output_gain.set_target(self.parameters.output_gain.load(Ordering::Relaxed));
+        self.smoothed_effect_mix.set_target(self.parameters.effect_mix.load(Ordering::Relaxed));
+        self.smoothed_noise_level.set_target(self.parameters.noise_level.load(Ordering::Relaxed));
         let effect_type_raw = self.parameters.current_effect.load(Ordering::Relaxed);
-        let effect_mix = self.parameters.effect_mix.load(Ordering::Relaxed);
         let effect_bypass = self.parameters.effect_bypass.load(Ordering::Relaxed);
         
-        // Преобразуем u32 обратно в enum
-        let effect_type = match effect_type_raw {
-            0 => EffectType::None,
-            1 => EffectType::Monster,
-            2 => EffectType::HighPitch,
-            3 => EffectType::Cave,
-            4 => EffectType::Radio,
-            5 => EffectType::Cathedral,
-            6 => EffectType::Underwater,
-            7 => EffectType::Robot,
-            8 => EffectType::Demon,
-            9 => EffectType::Alien,
-            10 => EffectType::VoiceChanger,
-            _ => EffectType::None,
-        };
+        // Преобразуем u32 обратно в enum; bypass - переход на "без эффекта"
+        let effect_type = if effect_bypass { EffectType::None } else { EffectType::from_u32(effect_type_raw) };
+        if self.samples_processed == 0 {
+            self.effect_switcher.jump(effect_type);
+        } else if effect_type != self.effect_switcher.active() {
+            // Без свободного процессора переход откладывается до следующего блока
+            self.effect_switcher.switch(effect_type, &mut self.dsp_processor);
+        }
+        let active_effect = self.effect_switcher.active();
         
         // Обновляем параметры генератора шума
         let noise_type_raw = self.parameters.noise_type.load(Ordering::Relaxed);
-        self.noise_generator.noise_type = match noise_type_raw {
-            0 => NoiseType::None,
-            1 => NoiseType::White,
-            2 => NoiseType::Pink,
-            3 => NoiseType::Brown,
-            _ => NoiseType::None,
-        };
-        self.noise_generator.level = self.parameters.noise_level.load(Ordering::Relaxed);
-        
-        // Для AI эффектов обрабатываем весь блок сразу
-        if matches!(effect_type, EffectType::Robot | EffectType::Demon | EffectType::Alien | EffectType::VoiceChanger) && !effect_bypass {
-            // Подготавливаем входной буфер для AI
-            let mut ai_input = Vec::with_capacity(input.len());
-            for &input_sample in input.iter() {
-                let mut sample = input_sample * input_gain;
-                sample += self.noise_generator.generate_sample();
-                ai_input.push(sample);
+        self.noise_generator.noise_type = NoiseType::from_u32(noise_type_raw);
+        
+        // Входной усилитель, срез низа/верха, эквалайзер, гейт и компрессор, затем шум (шум общий
+        // для всех каналов кадра и в динамику не попадает, иначе гейт открывался бы от него)
+        let (gate_enabled, compressor_enabled, limiter_enabled) = self.update_dynamics();
+        self.smoothed_highpass.set_target(self.parameters.highpass_freq.load(Ordering::Relaxed));
+        self.smoothed_lowpass.set_target(self.parameters.lowpass_freq.load(Ordering::Relaxed));
+        let sample_rate = self.sample_rate();
+        let max_cut = MAX_CUT_FREQUENCY.min(sample_rate * 0.49);
+        let mut reduction = GainReduction::default();
+        let mut frame = [0.0f32; MAX_CHANNELS];
+        let frame = &mut frame[..channel_count];
+        for i in 0..frames {
+            let input_gain = self.smoothed_input_gain.next_value();
+            self.noise_generator.level = self.smoothed_noise_level.next_value();
+            let noise = self.noise_generator.generate_sample();
+            for (sample, channel) in frame.iter_mut().zip(work.iter()) {
+                *sample = channel[i] * input_gain;
             }
-            
-            // Обрабатываем через AI
-            let ai_result = self.ai_processor.process(&ai_input);
-            let mut processed_output = ai_result.output.clone();
-            
-            // Neural Engine обработка (Apple Silicon M1/M2/M3)
-            if let Some(ref mut neural) = self.neural_processor {
-                match neural.process(&processed_output) {
-                    Ok(neural_result) => {
-                        processed_output = neural_result.output;
-                        // Логируем только значительную активность Neural Engine
-                        if neural_result.neural_engine_load > 10.0 {
-                            println!("🧠 Neural Engine: Нагрузка {:.1}%, Задержка {:.0} нс, Качество {:.2}", 
-                                neural_result.neural_engine_load, 
-                                neural_result.latency_ns,
-                                neural_result.quality_score);
-                        }
+            // Коэффициенты пересчитываются только пока частота скользит (или после скачка без сглаживания)
+            let highpass = self.smoothed_highpass.next_value();
+            let lowpass = self.smoothed_lowpass.next_value();
+            let redesign_highpass = highpass != self.cut_frequencies[0];
+            let redesign_lowpass = lowpass != self.cut_frequencies[1];
+            self.cut_frequencies = [highpass, lowpass];
+            for (sample, [highpass_filter, lowpass_filter]) in frame.iter_mut().zip(self.cut_filters.iter_mut()) {
+                if highpass > MIN_CUT_FREQUENCY {
+                    if redesign_highpass {
+                        highpass_filter.highpass(highpass.min(max_cut), sample_rate, std::f32::consts::FRAC_1_SQRT_2);
                     }
-                    Err(e) => {
-                        println!("⚠️ Ошибка Neural Engine: {}", e);
+                    *sample = highpass_filter.process(*sample);
+                } else if redesign_highpass {
+                    highpass_filter.reset();
+                }
+                if lowpass < max_cut {
+                    if redesign_lowpass {
+                        lowpass_filter.lowpass(lowpass.max(MIN_CUT_FREQUENCY), sample_rate, std::f32::consts::FRAC_1_SQRT_2);
                     }
+                    *sample = lowpass_filter.process(*sample);
+                } else if redesign_lowpass {
+                    lowpass_filter.reset();
                 }
             }
+            self.pre_eq.process_frame(frame);
+            if gate_enabled {
+                reduction.gate = reduction.gate.max(self.gate.process_frame(frame));
+            }
+            if compressor_enabled {
+                reduction.compressor = reduction.compressor.max(self.compressor.process_frame(frame));
+            }
+            for (channel, &sample) in work.iter_mut().zip(frame.iter()) {
+                channel[i] = sample + noise;
+            }
+        }
+        
+        // Цепочка голосовых эффектов - вставка перед эффектом конвейера, для любого эффекта
+        if let Some(ref mut neural) = self.neural_processor {
+            if !neural.effects().is_empty() {
+                // Ошибка возможна только при рассогласовании числа каналов; из аудио потока не печатаем
+                let _ = neural.process_planar(work);
+            }
+        }
+        
+        self.mix_ramp.resize(frames, 0.0);
+        for mix in self.mix_ramp.iter_mut() {
+            *mix = self.smoothed_effect_mix.next_value();
+        }
+        for buffers in [&mut self.effect_buffers, &mut self.retiring_input, &mut self.retiring_output, &mut self.ai_buffers] {
+            buffers.resize(channel_count, Vec::new());
+            buffers.iter_mut().for_each(|buffer| buffer.resize(frames, 0.0));
+        }
+        // После передискретизации длина несущей может разойтись со входом на сэмпл - выравниваем тишиной
+        let carrier = if self.sidechain_active {
+            channels::resize_planar(&mut self.sidechain_buffers, channel_count, frames);
+            Some(self.sidechain_buffers.as_slice())
+        } else {
+            None
+        };
+        
+        // AI модель работает с моно: подаем ей сумму каналов и разводим результат обратно
+        if self.effect_switcher.uses_ai() {
+            self.mono_buffer.resize(frames, 0.0);
+            channels::remix(work, std::slice::from_mut(&mut self.mono_buffer), frames);
+            let ai_result = self.ai_processor.process(&self.mono_buffer);
+            let len = ai_result.output.len().min(frames);
+            for channel in self.ai_buffers.iter_mut() {
+                channel[..len].copy_from_slice(&ai_result.output[..len]);
+                channel[len..].fill(0.0);
+            }
             
             // Обновляем статистику производительности
             self.performance_stats.ai_processing_time = ai_result.latency_ms;
             self.performance_stats.npu_usage = ai_result.npu_utilization;
-            
-            // Для VoiceChanger применяем дополнительную DSP обработку
-            if effect_type == EffectType::VoiceChanger {
-                for (i, &ai_sample) in processed_output.iter().enumerate() {
-                    if i >= output.len() { break; }
-                    let dsp_processed = self.dsp_processor.process_effect(ai_sample, EffectType::Cave, &self.parameters);
-                    let mixed = ai_sample * (1.0 - effect_mix) + dsp_processed * effect_mix;
-                    output[i] = mixed * output_gain;
-                }
-            } else {
-                // Для других AI эффектов применяем только микс
-                for (i, &ai_sample) in processed_output.iter().enumerate() {
-                    if i >= output.len() { break; }
-                    output[i] = ai_sample * output_gain;
+        }
+        
+        // Активный эффект (нарастает после переключения)
+        self.dsp_processor.render_effect(active_effect, work, &self.ai_buffers, carrier, &self.mix_ramp, &self.parameters, &mut self.effect_buffers);
+        for i in 0..frames {
+            let fade = self.effect_switcher.next_fade_in();
+            self.effect_buffers.iter_mut().for_each(|channel| channel[i] *= fade);
+        }
+        
+        // Уходящие эффекты: затухание входа (хвосты доигрывают) или выхода
+        for retiring in self.effect_switcher.retiring_mut() {
+            for i in 0..frames {
+                let gain = retiring.next_input_gain();
+                for (input, channel) in self.retiring_input.iter_mut().zip(work.iter()) {
+                    input[i] = channel[i] * gain;
                 }
             }
-        } else {
-            // Обычная DSP обработка для не-AI эффектов
-            for (i, &input_sample) in input.iter().enumerate() {
-                if i >= output.len() { break; }
-                
-                // Применяем входной усилитель
-                let mut sample = input_sample * input_gain;
-                
-                // Добавляем шум
-                sample += self.noise_generator.generate_sample();
-                
-                // Применяем эффект (если не в bypass режиме)
-                if !effect_bypass {
-                    let processed = self.dsp_processor.process_effect(sample, effect_type, &self.parameters);
-                    sample = sample * (1.0 - effect_mix) + processed * effect_mix;
+            retiring.processor.render_effect(retiring.effect, &self.retiring_input, &self.ai_buffers, carrier, &self.mix_ramp, &self.parameters, &mut self.retiring_output);
+            
+            let mut peak = 0.0f32;
+            for i in 0..frames {
+                let gain = retiring.next_output_gain();
+                for (out, retired) in self.effect_buffers.iter_mut().zip(self.retiring_output.iter()) {
+                    let sample = retired[i] * gain;
+                    peak = peak.max(sample.abs());
+                    out[i] += sample;
                 }
-                
-                // Применяем выходной усилитель и записываем
-                output[i] = sample * output_gain;
             }
+            retiring.finish_block(frames, peak);
         }
+        self.effect_switcher.recycle();
         
-        self.samples_processed += input.len() as u64;
+        // Применяем выходной усилитель, лимитер и записываем
+        for i in 0..frames {
+            let output_gain = self.smoothed_output_gain.next_value();
+            for (sample, processed) in frame.iter_mut().zip(self.effect_buffers.iter()) {
+                *sample = processed[i];
+            }
+            self.post_eq.process_frame(frame);
+            frame.iter_mut().for_each(|sample| *sample *= output_gain);
+            if limiter_enabled {
+                reduction.limiter = reduction.limiter.max(self.limiter.process_frame(frame));
+            }
+            for (channel, &sample) in work.iter_mut().zip(frame.iter()) {
+                channel[i] = sample;
+            }
+        }
+        self.dynamics_meter.publish(reduction);
     }
     
-    pub fn set_effect(&mut self, effect: EffectType) {
-        self.parameters.current_effect.store(effect as u32, Ordering::Relaxed);
+    /// Переносит параметры динамики из `AudioParameters` в ступени (раз в блок).
+    /// Выключенная ступень сбрасывается, чтобы после включения не было старого ослабления.
+    /// Возвращает, какие ступени включены: гейт, компрессор, лимитер.
+    fn update_dynamics(&mut self) -> (bool, bool, bool) {
+        let p = &self.parameters;
+        let gate_enabled = p.gate_enabled.load(Ordering::Relaxed);
+        if gate_enabled {
+            self.gate.set_threshold(
+                p.gate_threshold.load(Ordering::Relaxed),
+                p.gate_ratio.load(Ordering::Relaxed),
+                p.gate_range.load(Ordering::Relaxed),
+            );
+            self.gate.set_times(p.gate_attack.load(Ordering::Relaxed), p.gate_release.load(Ordering::Relaxed));
+        } else {
+            self.gate.reset();
+        }
+        
+        let compressor_enabled = p.compressor_enabled.load(Ordering::Relaxed);
+        if compressor_enabled {
+            self.compressor.set_threshold(
+                p.compressor_threshold.load(Ordering::Relaxed),
+                p.compressor_ratio.load(Ordering::Relaxed),
+                p.compressor_knee.load(Ordering::Relaxed),
+            );
+            self.compressor.set_times(p.compressor_attack.load(Ordering::Relaxed), p.compressor_release.load(Ordering::Relaxed));
+            self.compressor.set_makeup(p.compressor_makeup.load(Ordering::Relaxed));
+        } else {
+            self.compressor.reset();
+        }
+        
+        let limiter_enabled = p.limiter_enabled.load(Ordering::Relaxed);
+        if limiter_enabled {
+            self.limiter.set_ceiling(p.limiter_ceiling.load(Ordering::Relaxed), p.limiter_release.load(Ordering::Relaxed));
+        } else {
+            self.limiter.reset();
+        }
+        (gate_enabled, compressor_enabled, limiter_enabled)
     }
     
-    pub fn set_noise(&mut self, noise_type: NoiseType, level: f32) {
-        self.parameters.noise_type.store(noise_type as u32, Ordering::Relaxed);
-        self.parameters.noise_level.store(level.clamp(0.0, 1.0), Ordering::Relaxed);
+    /// Задает времена сглаживания параметров (0 - изменение скачком)
+    pub fn set_smoothing(&mut self, config: SmoothingConfig) {
+        let sample_rate = self.sample_rate();
+        self.smoothing = config;
+        self.smoothed_input_gain.set_time(config.gain_time, sample_rate);
+        self.smoothed_output_gain.set_time(config.gain_time, sample_rate);
+        self.smoothed_noise_level.set_time(config.gain_time, sample_rate);
+        self.smoothed_effect_mix.set_time(config.mix_time, sample_rate);
+        self.smoothed_highpass.set_time(config.filter_time, sample_rate);
+        self.smoothed_lowpass.set_time(config.filter_time, sample_rate);
+        self.dsp_processor.set_smoothing(&config);
+        self.effect_switcher.processors_mut().for_each(|processor| processor.set_smoothing(&config));
     }
     
-    pub fn start_processing(&mut self) {
-        self.is_processing.store(true, Ordering::Relaxed);
+    /// Длительность перехода при смене эффекта, секунды (0 - мгновенно, хвосты все равно доигрывают)
+    pub fn set_effect_crossfade(&mut self, seconds: f32) {
+        self.effect_switcher.set_fade_time(seconds);
     }
     
-    pub fn stop_processing(&mut self) {
-        self.is_processing.store(false, Ordering::Relaxed);
+    pub fn effect_crossfade(&self) -> f32 {
+        self.effect_switcher.fade_time()
     }
     
-    /// Получает информацию о платформе
-    pub fn platform_info(&self) -> String {
-        if let Some(ref platform) = self.platform_audio {
-            platform.platform_info()
-        } else {
-            "Базовая реализация (без платформо-специфичных оптимизаций)".to_string()
-        }
+    pub fn smoothing(&self) -> SmoothingConfig {
+        self.smoothing
     }
     
-    /// Проверяет поддержку низкой задержки
-    pub fn supports_low_latency(&self) -> bool {
-        self.platform_audio
-            .as_ref()
-            .map(|p| p.supports_low_latency())
-            .unwrap_or(false)
+    /// Выбирает эффект; эффекты со сдвигом высоты тона выставляют свой `pitch_shift`
+    pub fn set_effect(&mut self, effect: EffectType) {
+        self.parameters.set_effect(effect);
     }
     
-    /// Проверяет, поддерживается ли NPU (только для macOS Apple Silicon)
-    #[cfg(target_os = "macos")]
-    pub fn supports_neural_engine(&self) -> bool {
-        if let Some(ref platform_audio) = self.platform_audio {
-            platform_audio.supports_neural_engine()
-        } else {
-            self.ai_processor.supports_npu()
-        }
+    /// Устанавливает сдвиг высоты тона для Monster/HighPitch (отношение частот 0.5 - 2.0)
+    pub fn set_pitch_shift(&mut self, ratio: f32) {
+        self.parameters.set_pitch_shift(ratio);
     }
     
-    /// Заглушка для не-macOS платформ
-    #[cfg(not(target_os = "macos"))]
-    pub fn supports_neural_engine(&self) -> bool {
-        self.ai_processor.supports_npu()
+    /// Загружает импульсный отклик из WAV для эффекта `Convolution`.
+    /// Отклик делится на части по размеру блока конвейера, поэтому задержка равна `buffer_size`.
+    pub fn load_impulse_response<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), ConvolutionError> {
+        let impulse = ImpulseResponse::from_wav(path, self.dsp_processor.sample_rate)?;
+        self.set_impulse_response(&impulse);
+        Ok(())
     }
     
-    /// Получает статистику производительности
-    pub fn get_performance_stats(&self) -> PerformanceStats {
-        let mut stats = self.performance_stats.clone();
-        stats.npu_usage = self.ai_processor.get_average_npu_load();
-        stats.ai_processing_time = self.ai_processor.get_average_latency();
-        stats
+    /// Устанавливает импульсный отклик для эффекта `Convolution`
+    pub fn set_impulse_response(&mut self, impulse: &ImpulseResponse) {
+        let block_size = self.parameters.buffer_size.load(Ordering::Relaxed) as usize;
+        let convolver = Convolver::new(impulse, block_size);
+        println!("🏛️ Импульсный отклик: {:.2} с, {} частей по {} сэмплов",
+            impulse.duration(), convolver.partition_count(), block_size);
+        self.effect_switcher.processors_mut().for_each(|processor| processor.set_convolver(convolver.clone()));
+        self.dsp_processor.set_convolver(convolver);
+        self.impulse_response = Some(impulse.clone());
     }
     
-    /// Получает детальную информацию о системе
-    pub fn get_system_info(&self) -> String {
-        let neural_info = if self.neural_processor.is_some() {
-            format!("\n🧠 Neural Engine: {}\n💫 Neural задержка: {:.0} нс\n🎛️ Neural нагрузка: {:.1}%",
-                self.neural_engine_info(),
-                self.get_neural_latency_ns(),
+    /// Меняет частоту дискретизации, сохраняя размер блока
+    pub fn set_sample_rate(&mut self, sample_rate: f32) -> Result<(), String> {
+        let buffer_size = self.parameters.buffer_size.load(Ordering::Relaxed) as usize;
+        self.reconfigure(sample_rate, buffer_size)
+    }
+    
+    /// Перестраивает все процессоры под новую частоту дискретизации и размер блока:
+    /// пересчитываются коэффициенты фильтров, линии задержки, окна сдвига тона и анализа,
+    /// свертка и цепочка Neural Engine. Хвосты эффектов при этом сбрасываются.
+    ///
+    /// Выделяет память, поэтому вызывается не из аудио потока (поток держит конвейер
+    /// через `try_lock` и на время перестройки выдает тишину).
+    pub fn reconfigure(&mut self, sample_rate: f32, buffer_size: usize) -> Result<(), String> {
+        if !(MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&sample_rate) {
+            return Err(format!("Частота дискретизации {} Гц вне диапазона {}-{} Гц", sample_rate, MIN_SAMPLE_RATE, MAX_SAMPLE_RATE));
+        }
+        if buffer_size == 0 {
+            return Err("Размер блока должен быть больше нуля".to_string());
+        }
+        
+        // Neural Engine и отклик могут не перестроиться - проверяем их до изменения конвейера
+        if let Some(ref mut neural) = self.neural_processor {
+            neural.reconfigure(sample_rate, buffer_size)?;
+        }
+        let impulse = match self.impulse_response {
+            Some(ref impulse) => Some(impulse.resampled(sample_rate).map_err(|e| e.to_string())?),
+            None => None,
+        };
+        
+        self.parameters.sample_rate.store(sample_rate, Ordering::Relaxed);
+        self.parameters.buffer_size.store(buffer_size as u32, Ordering::Relaxed);
+        
+        let max_delay_samples = (sample_rate * MAX_DELAY_SECONDS) as usize;
+        let channels = self.channels();
+        self.dsp_processor = DspProcessor::with_channels(sample_rate, max_delay_samples, channels);
+        self.effect_switcher.reset(
+            (0..RETIRING_EFFECTS).map(|_| DspProcessor::with_channels(sample_rate, max_delay_samples, channels)).collect(),
+            sample_rate,
+        );
+        self.set_smoothing(self.smoothing);
+        self.ai_processor.reconfigure(sample_rate, buffer_size);
+        self.pitch_detector = PitchDetector::new(sample_rate);
+        self.pitch_monitor.publish(None);
+        for filters in &mut self.cut_filters {
+            filters.iter_mut().for_each(BiquadFilter::reset);
+        }
+        self.cut_frequencies = [f32::NAN; 2];
+        for stage in [EqStage::Pre, EqStage::Post] {
+            let bands = self.eq(stage).bands().to_vec();
+            *self.eq_mut(stage) = ParametricEq::new(sample_rate, channels);
+            self.set_eq_bands(stage, &bands)?;
+        }
+        self.gate = Gate::new(sample_rate);
+        self.compressor = Compressor::new(sample_rate);
+        self.limiter = Limiter::new(sample_rate, channels);
+        self.dynamics_meter.publish(GainReduction::default());
+        self.input_buffer = HeapRb::new(buffer_size * 4);
+        self.output_buffer = HeapRb::new(buffer_size * 4);
+        
+        self.impulse_response = None;
+        if let Some(impulse) = impulse {
+            self.set_impulse_response(&impulse);
+        }
+        if let Some(device_rate) = self.rate_converter.as_ref().map(|c| c.device_rate()) {
+            self.set_device_sample_rate(device_rate)?;
+        }
+        
+        println!("🔄 Конвейер перенастроен: {:.0} Гц, блок {} сэмплов", sample_rate, buffer_size);
+        Ok(())
+    }
+    
+    /// Текущая частота дискретизации
+    pub fn sample_rate(&self) -> f32 {
+        self.dsp_processor.sample_rate
+    }
+    
+    /// Задает частоту устройства, если она отличается от частоты обработки
+    /// (например, 48 кГц интерфейс и модель на 16 кГц). Вход и выход конвейера остаются
+    /// на частоте устройства, а внутри сигнал передискретизируется.
+    pub fn set_device_sample_rate(&mut self, device_rate: f32) -> Result<(), String> {
+        if !(MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&device_rate) {
+            return Err(format!("Частота устройства {} Гц вне диапазона {}-{} Гц", device_rate, MIN_SAMPLE_RATE, MAX_SAMPLE_RATE));
+        }
+        let processing_rate = self.sample_rate();
+        self.rate_converter = if device_rate == processing_rate {
+            None
+        } else {
+            let converter = RateConverter::new(device_rate, processing_rate, self.channels());
+            println!("🔁 Передискретизация {:.0} -> {:.0} Гц, задержка {} сэмплов",
+                device_rate, processing_rate, converter.latency());
+            Some(converter)
+        };
+        self.sidechain_converter = self.rate_converter.as_ref()
+            .map(|_| RateConverter::new(device_rate, processing_rate, self.channels()));
+        Ok(())
+    }
+    
+    /// Частота дискретизации на входе и выходе конвейера
+    pub fn device_sample_rate(&self) -> f32 {
+        self.rate_converter.as_ref().map_or(self.sample_rate(), |c| c.device_rate())
+    }
+    
+    /// Задержка передискретизации на границе в сэмплах устройства
+    pub fn rate_conversion_latency(&self) -> usize {
+        self.rate_converter.as_ref().map_or(0, |c| c.latency())
+    }
+    
+    /// Устанавливает размер помещения и затухание реверберации для Cave/Cathedral (0.0 - 1.0)
+    pub fn set_reverb(&mut self, size: f32, damping: f32) {
+        self.parameters.set_reverb(size, damping);
+    }
+    
+    /// Высота голоса эффекта Robot в Гц (40 - 1000)
+    pub fn set_robot_pitch(&mut self, frequency: f32) {
+        self.parameters.set_robot_pitch(frequency);
+    }
+    
+    /// Включает или выключает отслеживание основного тона входа
+    pub fn set_pitch_tracking(&mut self, enabled: bool) {
+        self.parameters.pitch_tracking.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.pitch_detector.reset();
+            self.pitch_monitor.publish(None);
+        }
+    }
+    
+    /// Текущий основной тон входа (None - тишина, шум или отслеживание выключено)
+    pub fn get_pitch(&self) -> Option<PitchEstimate> {
+        self.pitch_monitor.load()
+    }
+    
+    /// Монитор тона для чтения из UI потока без блокировки конвейера
+    pub fn pitch_monitor(&self) -> Arc<PitchMonitor> {
+        self.pitch_monitor.clone()
+    }
+    
+    /// Параметры для UI потока: их сеттеры не блокируют конвейер, поэтому ручки во время
+    /// работы аудио потока не приводят к пропущенным блокам. Блокировка нужна только для
+    /// перестройки (эквалайзер, цепочка эффектов, пресеты, частота дискретизации).
+    pub fn parameters(&self) -> Arc<AudioParameters> {
+        self.parameters.clone()
+    }
+    
+    /// Задержка голосовых эффектов, текущего DSP эффекта и лимитера в сэмплах
+    pub fn latency_samples(&self) -> usize {
+        let effect = EffectType::from_u32(self.parameters.current_effect.load(Ordering::Relaxed));
+        let limiter = if self.parameters.limiter_enabled.load(Ordering::Relaxed) { self.limiter.latency() } else { 0 };
+        let voice = self.neural_processor.as_ref().map_or(0, |neural| neural.latency_samples());
+        voice + self.dsp_processor.latency(effect) + limiter
+    }
+    
+    /// Срез низа и верха перед эффектом, Гц (20 Гц и 20 кГц - срез выключен)
+    pub fn set_filters(&mut self, highpass_freq: f32, lowpass_freq: f32) {
+        self.parameters.set_filters(highpass_freq, lowpass_freq);
+    }
+    
+    /// Полосы эквалайзера до (`Pre`) или после (`Post`) эффекта
+    pub fn eq_bands(&self, stage: EqStage) -> &[EqBand] {
+        self.eq(stage).bands()
+    }
+    
+    /// Заменяет все полосы ступени эквалайзера (не больше `MAX_EQ_BANDS`)
+    pub fn set_eq_bands(&mut self, stage: EqStage, bands: &[EqBand]) -> Result<(), String> {
+        self.eq_mut(stage).set_bands(bands)
+    }
+    
+    /// Меняет одну полосу ступени; индекс, равный числу полос, добавляет новую
+    pub fn set_eq_band(&mut self, stage: EqStage, index: usize, band: EqBand) -> Result<(), String> {
+        self.eq_mut(stage).set_band(index, band)
+    }
+    
+    /// Суммарная АЧХ среза низа/верха и обеих ступеней эквалайзера в дБ на частотах `frequencies`
+    /// (для графика; частоты ступеней берутся без сглаживания)
+    pub fn eq_magnitude_response(&self, frequencies: &[f32]) -> Vec<f32> {
+        let sample_rate = self.sample_rate();
+        let max_cut = MAX_CUT_FREQUENCY.min(sample_rate * 0.49);
+        let highpass = self.parameters.highpass_freq.load(Ordering::Relaxed);
+        let lowpass = self.parameters.lowpass_freq.load(Ordering::Relaxed);
+        let mut cut = [BiquadFilter::new(), BiquadFilter::new()];
+        if highpass > MIN_CUT_FREQUENCY {
+            cut[0].highpass(highpass.min(max_cut), sample_rate, std::f32::consts::FRAC_1_SQRT_2);
+        }
+        if lowpass < max_cut {
+            cut[1].lowpass(lowpass.max(MIN_CUT_FREQUENCY), sample_rate, std::f32::consts::FRAC_1_SQRT_2);
+        }
+        frequencies
+            .iter()
+            .map(|&frequency| {
+                let cut_gain = cut[0].magnitude(frequency, sample_rate) * cut[1].magnitude(frequency, sample_rate);
+                20.0 * cut_gain.max(1e-6).log10() + self.pre_eq.response(frequency) + self.post_eq.response(frequency)
+            })
+            .collect()
+    }
+    
+    fn eq(&self, stage: EqStage) -> &ParametricEq {
+        match stage {
+            EqStage::Pre => &self.pre_eq,
+            EqStage::Post => &self.post_eq,
+        }
+    }
+    
+    fn eq_mut(&mut self, stage: EqStage) -> &mut ParametricEq {
+        match stage {
+            EqStage::Pre => &mut self.pre_eq,
+            EqStage::Post => &mut self.post_eq,
+        }
+    }
+    
+    /// Гейт / экспандер на входе эффекта: порог в дБ, ratio (2 - экспандер, от 10 - гейт)
+    /// и наибольшее ослабление в дБ
+    pub fn set_gate(&mut self, enabled: bool, threshold: f32, ratio: f32, range: f32) {
+        self.parameters.set_gate(enabled, threshold, ratio, range);
+    }
+    
+    /// Время открытия и закрытия гейта в секундах
+    pub fn set_gate_times(&mut self, attack: f32, release: f32) {
+        self.parameters.set_gate_times(attack, release);
+    }
+    
+    /// Компрессор на входе эффекта: порог в дБ, ratio, колено и компенсация уровня в дБ
+    pub fn set_compressor(&mut self, enabled: bool, threshold: f32, ratio: f32, knee: f32, makeup: f32) {
+        self.parameters.set_compressor(enabled, threshold, ratio, knee, makeup);
+    }
+    
+    /// Время атаки и восстановления компрессора в секундах
+    pub fn set_compressor_times(&mut self, attack: f32, release: f32) {
+        self.parameters.set_compressor_times(attack, release);
+    }
+    
+    /// Лимитер на выходе: потолок в дБTP и время восстановления в секундах.
+    /// Включенный лимитер задерживает выход на `dynamics::LIMITER_LOOKAHEAD`.
+    pub fn set_limiter(&mut self, enabled: bool, ceiling: f32, release: f32) {
+        self.parameters.set_limiter(enabled, ceiling, release);
+    }
+    
+    /// Ослабление гейта, компрессора и лимитера в дБ за последний блок
+    pub fn get_gain_reduction(&self) -> GainReduction {
+        self.dynamics_meter.load()
+    }
+    
+    /// Индикаторы динамики для чтения из UI потока без блокировки конвейера
+    pub fn dynamics_meter(&self) -> Arc<DynamicsMeter> {
+        self.dynamics_meter.clone()
+    }
+    
+    pub fn set_noise(&mut self, noise_type: NoiseType, level: f32) {
+        self.parameters.set_noise(noise_type, level);
+    }
+    
+    pub fn start_processing(&mut self) {
+        self.is_processing.store(true, Ordering::Relaxed);
+    }
+    
+    pub fn stop_processing(&mut self) {
+        self.is_processing.store(false, Ordering::Relaxed);
+    }
+    
+    /// Получает информацию о платформе
+    pub fn platform_info(&self) -> String {
+        if let Some(ref platform) = self.platform_audio {
+            platform.platform_info()
+        } else {
+            "Базовая реализация (без платформо-специфичных оптимизаций)".to_string()
+        }
+    }
+    
+    /// Проверяет поддержку низкой задержки
+    pub fn supports_low_latency(&self) -> bool {
+        self.platform_audio
+            .as_ref()
+            .map(|p| p.supports_low_latency())
+            .unwrap_or(false)
+    }
+    
+    /// Проверяет, поддерживается ли NPU (только для macOS Apple Silicon)
+    #[cfg(target_os = "macos")]
+    pub fn supports_neural_engine(&self) -> bool {
+        if let Some(ref platform_audio) = self.platform_audio {
+            platform_audio.supports_neural_engine()
+        } else {
+            self.ai_processor.supports_npu()
+        }
+    }
+    
+    /// Заглушка для не-macOS платформ
+    #[cfg(not(target_os = "macos"))]
+    pub fn supports_neural_engine(&self) -> bool {
+        self.ai_processor.supports_npu()
+    }
+    
+    /// Получает статистику производительности
+    pub fn get_performance_stats(&self) -> PerformanceStats {
+        let mut stats = self.performance_stats.clone();
+        stats.npu_usage = self.ai_processor.get_average_npu_load();
+        stats.ai_processing_time = self.ai_processor.get_average_latency();
+        stats
+    }
+    
+    /// Получает детальную информацию о системе
+    pub fn get_system_info(&self) -> String {
+        let neural_info = if self.neural_processor.is_some() {
+            format!("\n🧠 Neural Engine: {}\n💫 Neural задержка: {:.0} нс\n🎛️ Neural нагрузка: {:.1}%",
+                self.neural_engine_info(),
+                self.get_neural_latency_ns(),
                 self.get_neural_load())
         } else {
             String::new()
@@ -710,6 +1966,126 @@ impl AudioPipeline {
         )
     }
     
+    // === Пресеты ===
+    
+    /// Снимок текущего состояния конвейера
+    pub fn preset(&self, name: &str) -> Preset {
+        let p = &self.parameters;
+        let (voice_effects, quality_preset) = match self.neural_processor {
+            Some(ref neural) => (neural.effects().to_vec(), neural.config().quality_preset),
+            None => (Vec::new(), Default::default()),
+        };
+        Preset {
+            name: name.to_string(),
+            input_gain: p.input_gain.load(Ordering::Relaxed),
+            output_gain: p.output_gain.load(Ordering::Relaxed),
+            effect: EffectType::from_u32(p.current_effect.load(Ordering::Relaxed)),
+            effect_mix: p.effect_mix.load(Ordering::Relaxed),
+            effect_bypass: p.effect_bypass.load(Ordering::Relaxed),
+            noise_type: NoiseType::from_u32(p.noise_type.load(Ordering::Relaxed)),
+            noise_level: p.noise_level.load(Ordering::Relaxed),
+            pitch_shift: p.pitch_shift.load(Ordering::Relaxed),
+            reverb_size: p.reverb_size.load(Ordering::Relaxed),
+            reverb_damping: p.reverb_damping.load(Ordering::Relaxed),
+            delay_time: p.delay_time.load(Ordering::Relaxed),
+            delay_feedback: p.delay_feedback.load(Ordering::Relaxed),
+            lowpass_freq: p.lowpass_freq.load(Ordering::Relaxed),
+            highpass_freq: p.highpass_freq.load(Ordering::Relaxed),
+            bandpass_center: p.bandpass_center.load(Ordering::Relaxed),
+            bandpass_q: p.bandpass_q.load(Ordering::Relaxed),
+            pre_eq: self.pre_eq.bands().to_vec(),
+            post_eq: self.post_eq.bands().to_vec(),
+            robot_pitch: p.robot_pitch.load(Ordering::Relaxed),
+            gate_enabled: p.gate_enabled.load(Ordering::Relaxed),
+            gate_threshold: p.gate_threshold.load(Ordering::Relaxed),
+            gate_ratio: p.gate_ratio.load(Ordering::Relaxed),
+            gate_range: p.gate_range.load(Ordering::Relaxed),
+            gate_attack: p.gate_attack.load(Ordering::Relaxed),
+            gate_release: p.gate_release.load(Ordering::Relaxed),
+            compressor_enabled: p.compressor_enabled.load(Ordering::Relaxed),
+            compressor_threshold: p.compressor_threshold.load(Ordering::Relaxed),
+            compressor_ratio: p.compressor_ratio.load(Ordering::Relaxed),
+            compressor_knee: p.compressor_knee.load(Ordering::Relaxed),
+            compressor_attack: p.compressor_attack.load(Ordering::Relaxed),
+            compressor_release: p.compressor_release.load(Ordering::Relaxed),
+            compressor_makeup: p.compressor_makeup.load(Ordering::Relaxed),
+            limiter_enabled: p.limiter_enabled.load(Ordering::Relaxed),
+            limiter_ceiling: p.limiter_ceiling.load(Ordering::Relaxed),
+            limiter_release: p.limiter_release.load(Ordering::Relaxed),
+            voice_effects,
+            quality_preset,
+            ..Preset::default()
+        }
+    }
+    
+    /// Применяет пресет. Цепочка голосовых эффектов меняется целиком или не меняется совсем;
+    /// без Neural Engine она пропускается с предупреждением.
+    pub fn apply_preset(&mut self, preset: &Preset) -> Result<(), PresetError> {
+        if preset.pre_eq.len().max(preset.post_eq.len()) > MAX_EQ_BANDS {
+            return Err(PresetError::Apply(format!("Больше {} полос эквалайзера", MAX_EQ_BANDS)));
+        }
+        if let Some(ref mut neural) = self.neural_processor {
+            neural.set_effects(preset.voice_effects.clone()).map_err(PresetError::Apply)?;
+            neural.set_quality_preset(preset.quality_preset);
+        } else if !preset.voice_effects.is_empty() {
+            println!("⚠️ Neural Engine недоступен: голосовые эффекты пресета \"{}\" пропущены", preset.name);
+        }
+        
+        let p = &self.parameters;
+        p.input_gain.store(preset.input_gain.max(0.0), Ordering::Relaxed);
+        p.output_gain.store(preset.output_gain.max(0.0), Ordering::Relaxed);
+        p.current_effect.store(preset.effect as u32, Ordering::Relaxed);
+        p.effect_mix.store(preset.effect_mix.clamp(0.0, 1.0), Ordering::Relaxed);
+        p.effect_bypass.store(preset.effect_bypass, Ordering::Relaxed);
+        p.noise_type.store(preset.noise_type as u32, Ordering::Relaxed);
+        p.noise_level.store(preset.noise_level.clamp(0.0, 1.0), Ordering::Relaxed);
+        p.pitch_shift.store(preset.pitch_shift.clamp(0.5, 2.0), Ordering::Relaxed);
+        p.reverb_size.store(preset.reverb_size.clamp(0.0, 1.0), Ordering::Relaxed);
+        p.reverb_damping.store(preset.reverb_damping.clamp(0.0, 1.0), Ordering::Relaxed);
+        p.delay_time.store(preset.delay_time.clamp(0.0, 1.0), Ordering::Relaxed);
+        p.delay_feedback.store(preset.delay_feedback.clamp(0.0, 0.95), Ordering::Relaxed);
+        p.lowpass_freq.store(preset.lowpass_freq.clamp(20.0, 20000.0), Ordering::Relaxed);
+        p.highpass_freq.store(preset.highpass_freq.clamp(20.0, 20000.0), Ordering::Relaxed);
+        p.bandpass_center.store(preset.bandpass_center.clamp(100.0, 8000.0), Ordering::Relaxed);
+        p.bandpass_q.store(preset.bandpass_q.clamp(0.1, 10.0), Ordering::Relaxed);
+        p.robot_pitch.store(preset.robot_pitch.clamp(40.0, 1000.0), Ordering::Relaxed);
+        self.set_eq_bands(EqStage::Pre, &preset.pre_eq).map_err(PresetError::Apply)?;
+        self.set_eq_bands(EqStage::Post, &preset.post_eq).map_err(PresetError::Apply)?;
+        self.set_gate(preset.gate_enabled, preset.gate_threshold, preset.gate_ratio, preset.gate_range);
+        self.set_gate_times(preset.gate_attack, preset.gate_release);
+        self.set_compressor(
+            preset.compressor_enabled,
+            preset.compressor_threshold,
+            preset.compressor_ratio,
+            preset.compressor_knee,
+            preset.compressor_makeup,
+        );
+        self.set_compressor_times(preset.compressor_attack, preset.compressor_release);
+        self.set_limiter(preset.limiter_enabled, preset.limiter_ceiling, preset.limiter_release);
+        
+        println!("🎚️ Применен пресет \"{}\"", preset.name);
+        Ok(())
+    }
+    
+    /// Применяет встроенный пресет из библиотеки (`preset::list_presets`)
+    pub fn apply_preset_by_name(&mut self, name: &str) -> Result<(), PresetError> {
+        let preset = preset::builtin_preset(name)?;
+        self.apply_preset(&preset)
+    }
+    
+    /// Сохраняет текущее состояние в JSON файл (имя пресета - имя файла)
+    pub fn save_preset<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), PresetError> {
+        let path = path.as_ref();
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        self.preset(&name).save(path)
+    }
+    
+    /// Загружает и применяет пресет из JSON файла
+    pub fn load_preset<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), PresetError> {
+        let preset = Preset::load(path)?;
+        self.apply_preset(&preset)
+    }
+    
     // === Neural Engine методы ===
     
     /// Добавляет голосовой эффект в Neural Engine
@@ -735,6 +2111,11 @@ impl AudioPipeline {
         }
     }
     
+    /// Возвращает найденную и целевую ноту автотюна (None, если автотюн не добавлен)
+    pub fn get_autotune_status(&self) -> Option<AutoTuneStatus> {
+        self.neural_processor.as_ref().and_then(|neural| neural.autotune_status())
+    }
+    
     /// Возвращает информацию о Neural Engine
     pub fn neural_engine_info(&self) -> String {
         if let Some(ref neural) = self.neural_processor {
@@ -771,6 +2152,26 @@ pub extern "C" fn create_pipeline() -> *mut c_void {
     Box::into_raw(pipeline) as *mut c_void
 }
 
+/// Создает аудиоконвейер под частоту и размер блока устройства (например, 48000/256).
+/// Возвращает null, если частота вне диапазона 8-384 кГц или размер блока нулевой.
+#[no_mangle]
+pub extern "C" fn create_pipeline_with_config(sample_rate: f32, buffer_size: u32, channels: u32) -> *mut c_void {
+    println!("Rust: create_pipeline_with_config({}, {}, {}) вызван.", sample_rate, buffer_size, channels);
+    if !(MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&sample_rate) || buffer_size == 0 {
+        return std::ptr::null_mut();
+    }
+    let pipeline = Box::new(AudioPipeline::with_channels(sample_rate, buffer_size as usize, channels as usize));
+    Box::into_raw(pipeline) as *mut c_void
+}
+
+/// Создает многоканальный аудиоконвейер (1 - 8 каналов, 2 - стерео).
+#[no_mangle]
+pub extern "C" fn create_pipeline_with_channels(channels: u32) -> *mut c_void {
+    println!("Rust: create_pipeline_with_channels({}) вызван.", channels);
+    let pipeline = Box::new(AudioPipeline::with_channels(44100.0, 512, channels as usize));
+    Box::into_raw(pipeline) as *mut c_void
+}
+
 /// Создает экземпляр аудиоконвейера с платформо-специфичной инициализацией.
 #[no_mangle]
 pub extern "C" fn create_pipeline_with_platform() -> *mut c_void {
@@ -811,422 +2212,2599 @@ pub unsafe extern "C" fn process_audio(
     pipeline.process_block(input_slice, output_slice);
 }
 
-/// Устанавливает эффект
+/// Обрабатывает моно блок с сайдчейном - внешней несущей эффекта Vocoder (12).
+/// Без сайдчейна (`sidechain` равен null) работает как `process_audio`.
+///
+/// # Safety
+/// `input`, `sidechain` и `output` должны указывать на `len` сэмплов.
 #[no_mangle]
-pub unsafe extern "C" fn set_effect(pipeline_ptr: *mut c_void, effect_type: u32) {
-    if pipeline_ptr.is_null() { return; }
+pub unsafe extern "C" fn process_audio_with_sidechain(
+    pipeline_ptr: *mut c_void,
+    input: *const f32,
+    sidechain: *const f32,
+    output: *mut f32,
+    len: usize,
+) {
+    if pipeline_ptr.is_null() || input.is_null() || output.is_null() {
+        return;
+    }
+    
     let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-    pipeline.parameters.current_effect.store(effect_type, Ordering::Relaxed);
+    let input_slice = std::slice::from_raw_parts(input, len);
+    let output_slice = std::slice::from_raw_parts_mut(output, len);
+    
+    if sidechain.is_null() {
+        pipeline.process_block(input_slice, output_slice);
+    } else {
+        let sidechain_slice = std::slice::from_raw_parts(sidechain, len);
+        pipeline.process_block_with_sidechain(input_slice, sidechain_slice, output_slice);
+    }
 }
 
-/// Устанавливает параметры шума
+/// Обрабатывает блок чередующихся кадров (L R L R ...); `frames` - количество кадров.
+/// Количество каналов входа и выхода может отличаться (например, моно микрофон в стерео выход).
+///
+/// # Safety
+/// `input` должен указывать на `frames * input_channels` сэмплов, `output` - на `frames * output_channels`.
 #[no_mangle]
-pub unsafe extern "C" fn set_noise(pipeline_ptr: *mut c_void, noise_type: u32, level: f32) {
-    if pipeline_ptr.is_null() { return; }
+pub unsafe extern "C" fn process_audio_interleaved(
+    pipeline_ptr: *mut c_void,
+    input: *const f32,
+    input_channels: u32,
+    output: *mut f32,
+    output_channels: u32,
+    frames: usize,
+) {
+    if pipeline_ptr.is_null() || input.is_null() || output.is_null() {
+        return;
+    }
+    
     let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-    pipeline.parameters.noise_type.store(noise_type, Ordering::Relaxed);
-    pipeline.parameters.noise_level.store(level.clamp(0.0, 1.0), Ordering::Relaxed);
+    let input_slice = std::slice::from_raw_parts(input, frames * input_channels as usize);
+    let output_slice = std::slice::from_raw_parts_mut(output, frames * output_channels as usize);
+    
+    pipeline.process_interleaved(input_slice, input_channels as usize, output_slice, output_channels as usize);
 }
 
-/// Запускает обработку
+/// Обрабатывает блок планарных каналов: `inputs` и `outputs` - массивы указателей на буферы каналов
+/// по `frames` сэмплов. Каналы сверх 8 игнорируются.
+///
+/// # Safety
+/// Массивы указателей должны содержать `input_channels` и `output_channels` валидных буферов.
 #[no_mangle]
-pub unsafe extern "C" fn start_processing(pipeline_ptr: *mut c_void) {
-    if pipeline_ptr.is_null() { return; }
+pub unsafe extern "C" fn process_audio_planar(
+    pipeline_ptr: *mut c_void,
+    inputs: *const *const f32,
+    input_channels: u32,
+    outputs: *const *mut f32,
+    output_channels: u32,
+    frames: usize,
+) {
+    if pipeline_ptr.is_null() || inputs.is_null() || outputs.is_null() {
+        return;
+    }
+    
     let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-    pipeline.start_processing();
+    let input_channels = (input_channels as usize).min(MAX_CHANNELS);
+    let output_channels = (output_channels as usize).min(MAX_CHANNELS);
+    
+    // Срезы каналов собираем на стеке, чтобы не выделять память в аудио потоке
+    let mut input_slices: [&[f32]; MAX_CHANNELS] = [&[]; MAX_CHANNELS];
+    let mut output_slices: [&mut [f32]; MAX_CHANNELS] = std::array::from_fn(|_| <&mut [f32]>::default());
+    for (channel, slice) in input_slices.iter_mut().enumerate().take(input_channels) {
+        *slice = std::slice::from_raw_parts(*inputs.add(channel), frames);
+    }
+    for (channel, slice) in output_slices.iter_mut().enumerate().take(output_channels) {
+        *slice = std::slice::from_raw_parts_mut(*outputs.add(channel), frames);
+    }
+    
+    pipeline.process_planar(&input_slices[..input_channels], &mut output_slices[..output_channels]);
 }
 
-/// Останавливает обработку
+/// Меняет частоту дискретизации (размер блока сохраняется).
+/// Возвращает 0 при успехе, -1 при ошибке.
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
 #[no_mangle]
-pub unsafe extern "C" fn stop_processing(pipeline_ptr: *mut c_void) {
-    if pipeline_ptr.is_null() { return; }
+pub unsafe extern "C" fn set_sample_rate(pipeline_ptr: *mut c_void, sample_rate: f32) -> i32 {
+    if pipeline_ptr.is_null() { return -1; }
     let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-    pipeline.stop_processing();
+    match pipeline.set_sample_rate(sample_rate) {
+        Ok(()) => 0,
+        Err(e) => {
+            println!("⚠️ {}", e);
+            -1
+        }
+    }
 }
 
-/// Получает загрузку NPU (возвращает процент 0.0-100.0)
+/// Задает частоту устройства, отличную от частоты обработки (передискретизация на границе).
+/// Возвращает 0 при успехе, -1 при ошибке.
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
 #[no_mangle]
-pub unsafe extern "C" fn get_npu_load(pipeline_ptr: *mut c_void) -> f32 {
-    if pipeline_ptr.is_null() { return 0.0; }
-    let pipeline = &*(pipeline_ptr as *mut AudioPipeline);
-    pipeline.ai_processor.get_average_npu_load()
+pub unsafe extern "C" fn set_device_sample_rate(pipeline_ptr: *mut c_void, device_rate: f32) -> i32 {
+    if pipeline_ptr.is_null() { return -1; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    match pipeline.set_device_sample_rate(device_rate) {
+        Ok(()) => 0,
+        Err(e) => {
+            println!("⚠️ {}", e);
+            -1
+        }
+    }
 }
 
-/// Получает задержку AI обработки в миллисекундах
+/// Меняет частоту дискретизации и размер блока.
+/// Возвращает 0 при успехе, -1 при ошибке.
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
 #[no_mangle]
-pub unsafe extern "C" fn get_ai_latency(pipeline_ptr: *mut c_void) -> f32 {
-    if pipeline_ptr.is_null() { return 0.0; }
-    let pipeline = &*(pipeline_ptr as *mut AudioPipeline);
-    pipeline.ai_processor.get_average_latency()
+pub unsafe extern "C" fn reconfigure_pipeline(pipeline_ptr: *mut c_void, sample_rate: f32, buffer_size: u32) -> i32 {
+    if pipeline_ptr.is_null() { return -1; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    match pipeline.reconfigure(sample_rate, buffer_size as usize) {
+        Ok(()) => 0,
+        Err(e) => {
+            println!("⚠️ {}", e);
+            -1
+        }
+    }
 }
 
-/// Проверяет поддержку NPU
+/// Устанавливает эффект
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
 #[no_mangle]
-pub unsafe extern "C" fn supports_npu(pipeline_ptr: *mut c_void) -> bool {
-    if pipeline_ptr.is_null() { return false; }
-    let pipeline = &*(pipeline_ptr as *mut AudioPipeline);
-    pipeline.supports_neural_engine()
+pub unsafe extern "C" fn set_effect(pipeline_ptr: *mut c_void, effect_type: u32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.set_effect(EffectType::from_u32(effect_type));
 }
 
-/// Освобождает память, выделенную под аудиоконвейер.
+/// Устанавливает сдвиг высоты тона для Monster/HighPitch (отношение частот 0.5 - 2.0)
 ///
 /// # Safety
-/// Эта функция небезопасна, так как работает с сырыми указателями из C.
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
 #[no_mangle]
-pub unsafe extern "C" fn destroy_pipeline(pipeline_ptr: *mut c_void) {
-    if !pipeline_ptr.is_null() {
-        println!("Rust: destroy_pipeline() вызван.");
-        drop(Box::from_raw(pipeline_ptr as *mut AudioPipeline));
-    }
+pub unsafe extern "C" fn set_pitch_shift(pipeline_ptr: *mut c_void, ratio: f32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.set_pitch_shift(ratio);
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_audio_pipeline_creation() {
-        let pipeline = AudioPipeline::new(44100.0, 512);
-        assert_eq!(pipeline.samples_processed, 0);
-        assert!(!pipeline.is_processing.load(Ordering::Relaxed));
-    }
+/// Загружает импульсный отклик из WAV файла для эффекта свертки (11).
+/// Возвращает 0 при успехе, -1 при ошибке.
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден,
+/// `path` должен указывать на строку UTF-8 с нулем на конце.
+#[no_mangle]
+pub unsafe extern "C" fn load_impulse_response(pipeline_ptr: *mut c_void, path: *const c_char) -> i32 {
+    if pipeline_ptr.is_null() || path.is_null() { return -1; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    let Ok(path) = CStr::from_ptr(path).to_str() else { return -1; };
     
-    #[test]
-    fn test_platform_initialization() {
-        // Тестируем создание конвейера с платформой
-        let result = AudioPipeline::new_with_platform();
-        assert!(result.is_ok(), "Не удалось создать конвейер с платформой");
-        
-        let pipeline = result.unwrap();
-        println!("Платформа: {}", pipeline.platform_info());
-        println!("Поддержка низкой задержки: {}", pipeline.supports_low_latency());
-        println!("Поддержка Neural Engine: {}", pipeline.supports_neural_engine());
-    }
-
-    #[test]
-    fn test_effects() {
-        let mut pipeline = AudioPipeline::new(44100.0, 512);
-        pipeline.start_processing();
-        
-        // Тестируем различные эффекты
-        let effects = [
-            EffectType::None,
-            EffectType::Monster,
-            EffectType::HighPitch,
-            EffectType::Cave,
-            EffectType::Radio,
-            EffectType::Cathedral,
-            EffectType::Underwater,
-            EffectType::Robot,
-            EffectType::Demon,
-            EffectType::Alien,
-        ];
-        
-        // Создаем тестовый сигнал
-        let input = vec![0.5f32; 100];
-        let mut output = vec![0.0f32; 100];
-        
-        for effect in effects.iter() {
-            pipeline.set_effect(*effect);
-            pipeline.process_block(&input, &mut output);
-            
-            // Проверяем, что выходной сигнал был изменен
-            assert!(output.iter().any(|&x| x != 0.0));
-        }
-    }
-
-    #[test]
-    fn test_noise_generators() {
-        let mut pipeline = AudioPipeline::new(44100.0, 512);
-        pipeline.start_processing();
-        
-        let noise_types = [NoiseType::White, NoiseType::Pink, NoiseType::Brown];
-        let input = vec![0.0f32; 100]; // Тишина на входе
-        let mut output = vec![0.0f32; 100];
-        
-        for noise_type in noise_types.iter() {
-            pipeline.set_noise(*noise_type, 0.1);
-            pipeline.process_block(&input, &mut output);
-            
-            // Проверяем, что был добавлен шум
-            let rms = calculate_rms(&output);
-            assert!(rms > 0.0, "Шум не был добавлен для {:?}", noise_type);
+    match pipeline.load_impulse_response(path) {
+        Ok(()) => 0,
+        Err(e) => {
+            println!("⚠️ {}", e);
+            -1
         }
     }
+}
 
-    #[test]
-    fn test_filters() {
-        let mut filter = BiquadFilter::new();
-        
-        // Тест низкочастотного фильтра
-        filter.lowpass(1000.0, 44100.0, 1.0);
-        let output = filter.process(1.0);
-        assert!(output.is_finite());
-        
-        // Тест высокочастотного фильтра
-        filter.highpass(1000.0, 44100.0, 1.0);
-        let output = filter.process(1.0);
-        assert!(output.is_finite());
-        
-        // Тест полосового фильтра
-        filter.bandpass(1000.0, 44100.0, 1.0);
-        let output = filter.process(1.0);
-        assert!(output.is_finite());
-    }
-
-    #[test]
-    fn test_delay_effect() {
-        let mut delay = DelayEffect::new(4410); // 100 мс при 44100 Гц
-        delay.set_delay_time(0.1, 44100.0);
-        delay.set_feedback(0.5);
-        delay.set_mix(0.3);
-        
-        let output = delay.process(1.0);
-        assert!(output.is_finite());
-    }
-
-    #[test]
-    fn test_noise_generator() {
-        let mut generator = NoiseGenerator::new();
-        
-        // Тест белого шума
-        generator.noise_type = NoiseType::White;
-        generator.level = 1.0;
-        let white_noise = generator.generate_sample();
-        assert!(white_noise.is_finite());
-        
-        // Тест розового шума
-        generator.noise_type = NoiseType::Pink;
-        let pink_noise = generator.generate_sample();
-        assert!(pink_noise.is_finite());
-        
-        // Тест коричневого шума
-        generator.noise_type = NoiseType::Brown;
-        let brown_noise = generator.generate_sample();
-        assert!(brown_noise.is_finite());
-    }
-
-    #[test]
-    fn test_c_ffi_interface() {
-        unsafe {
-            // Создаем конвейер через C интерфейс
-            let pipeline_ptr = create_pipeline();
-            assert!(!pipeline_ptr.is_null());
-            
-            // Тестируем установку эффекта
-            set_effect(pipeline_ptr, EffectType::Monster as u32);
-            
-            // Тестируем установку шума
-            set_noise(pipeline_ptr, NoiseType::White as u32, 0.1);
-            
-            // Тестируем запуск обработки
-            start_processing(pipeline_ptr);
-            
-            // Тестируем обработку аудио
-            let input = vec![0.5f32; 10];
-            let mut output = vec![0.0f32; 10];
-            process_audio(pipeline_ptr, input.as_ptr(), output.as_mut_ptr(), 10);
-            
-            // Тестируем остановку обработки
-            stop_processing(pipeline_ptr);
-            
-            // Освобождаем память
-            destroy_pipeline(pipeline_ptr);
+/// Применяет пресет из JSON строки. Возвращает 0 при успехе, -1 при ошибке.
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден,
+/// `json` должен указывать на строку UTF-8 с нулем на конце.
+#[no_mangle]
+pub unsafe extern "C" fn apply_preset_json(pipeline_ptr: *mut c_void, json: *const c_char) -> i32 {
+    if pipeline_ptr.is_null() || json.is_null() { return -1; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    let Ok(json) = CStr::from_ptr(json).to_str() else { return -1; };
+    
+    match Preset::from_json(json).and_then(|preset| pipeline.apply_preset(&preset)) {
+        Ok(()) => 0,
+        Err(e) => {
+            println!("⚠️ {}", e);
+            -1
         }
     }
+}
 
-    /// Вычисляет RMS (среднеквадратичное значение) сигнала
-    fn calculate_rms(samples: &[f32]) -> f32 {
-        if samples.is_empty() {
-            return 0.0;
-        }
-        
-        let sum_squares: f32 = samples.iter().map(|&x| x * x).sum();
-        (sum_squares / samples.len() as f32).sqrt()
-    }
+/// Записывает текущее состояние как JSON пресет (с нулем на конце) в `buffer`.
+/// Возвращает длину JSON без нуля; если она не меньше `buffer_len`, буфер не тронут
+/// и вызов нужно повторить с буфером большего размера.
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден,
+/// `buffer` должен указывать на `buffer_len` байт (или быть null при запросе длины).
+#[no_mangle]
+pub unsafe extern "C" fn get_preset_json(pipeline_ptr: *const c_void, buffer: *mut u8, buffer_len: usize) -> usize {
+    if pipeline_ptr.is_null() { return 0; }
+    let pipeline = &*(pipeline_ptr as *const AudioPipeline);
+    let Ok(json) = pipeline.preset("").to_json() else { return 0; };
+    copy_c_string(&json, buffer, buffer_len)
 }
 
-// === Neural Engine C API ===
+/// Записывает имена встроенных пресетов через '\n' (с нулем на конце) в `buffer`.
+/// Возвращаемое значение - как у `get_preset_json`.
+///
+/// # Safety
+/// `buffer` должен указывать на `buffer_len` байт (или быть null при запросе длины).
+#[no_mangle]
+pub unsafe extern "C" fn list_presets(buffer: *mut u8, buffer_len: usize) -> usize {
+    copy_c_string(&preset::list_presets().join("\n"), buffer, buffer_len)
+}
 
-/// Добавляет эффект изменения высоты тона
+/// Записывает JSON встроенного пресета (с описанием для интерфейса) в `buffer`.
+/// Возвращаемое значение - как у `get_preset_json`; 0, если пресета нет.
+///
+/// # Safety
+/// `name` должен указывать на строку UTF-8 с нулем на конце,
+/// `buffer` - на `buffer_len` байт (или быть null при запросе длины).
 #[no_mangle]
-pub extern "C" fn add_pitch_shift_effect(pipeline_ptr: *mut c_void, semitones: f32) -> i32 {
-    if pipeline_ptr.is_null() {
-        return -1;
-    }
-    
-    unsafe {
-        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-        match pipeline.add_voice_effect(VoiceEffect::PitchShift(semitones)) {
-            Ok(_) => 0,
-            Err(_) => -1,
-        }
-    }
+pub unsafe extern "C" fn get_builtin_preset_json(name: *const c_char, buffer: *mut u8, buffer_len: usize) -> usize {
+    if name.is_null() { return 0; }
+    let Ok(name) = CStr::from_ptr(name).to_str() else { return 0; };
+    let Ok(json) = preset::builtin_preset(name).and_then(|preset| preset.to_json()) else { return 0; };
+    copy_c_string(&json, buffer, buffer_len)
 }
 
-/// Добавляет эффект изменения формант
+/// Применяет встроенный пресет по имени. Возвращает 0 при успехе, -1 при ошибке.
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден,
+/// `name` должен указывать на строку UTF-8 с нулем на конце.
 #[no_mangle]
-pub extern "C" fn add_formant_shift_effect(pipeline_ptr: *mut c_void, shift: f32) -> i32 {
-    if pipeline_ptr.is_null() {
-        return -1;
-    }
+pub unsafe extern "C" fn apply_preset_by_name(pipeline_ptr: *mut c_void, name: *const c_char) -> i32 {
+    if pipeline_ptr.is_null() || name.is_null() { return -1; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    let Ok(name) = CStr::from_ptr(name).to_str() else { return -1; };
     
-    unsafe {
-        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-        match pipeline.add_voice_effect(VoiceEffect::FormantShift(shift)) {
-            Ok(_) => 0,
-            Err(_) => -1,
+    match pipeline.apply_preset_by_name(name) {
+        Ok(()) => 0,
+        Err(e) => {
+            println!("⚠️ {}", e);
+            -1
         }
     }
 }
 
-/// Добавляет эффект изменения голоса
-#[no_mangle]
-pub extern "C" fn add_voice_changer_effect(pipeline_ptr: *mut c_void, gender: f32, age: f32, roughness: f32) -> i32 {
-    if pipeline_ptr.is_null() {
-        return -1;
-    }
-    
-    unsafe {
-        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-        match pipeline.add_voice_effect(VoiceEffect::VoiceChanger { gender, age, roughness }) {
-            Ok(_) => 0,
-            Err(_) => -1,
-        }
+/// Копирует строку с нулем на конце, если она помещается в буфер; возвращает ее длину
+unsafe fn copy_c_string(text: &str, buffer: *mut u8, buffer_len: usize) -> usize {
+    if !buffer.is_null() && text.len() < buffer_len {
+        let buffer = std::slice::from_raw_parts_mut(buffer, text.len() + 1);
+        buffer[..text.len()].copy_from_slice(text.as_bytes());
+        buffer[text.len()] = 0;
     }
+    text.len()
 }
 
-/// Добавляет гармонические эффекты
+/// Сохраняет текущее состояние в JSON файл. Возвращает 0 при успехе, -1 при ошибке.
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден,
+/// `path` должен указывать на строку UTF-8 с нулем на конце.
 #[no_mangle]
-pub extern "C" fn add_harmonics_effect(pipeline_ptr: *mut c_void, overtones: f32, undertones: f32, distortion: f32) -> i32 {
-    if pipeline_ptr.is_null() {
-        return -1;
-    }
+pub unsafe extern "C" fn save_preset(pipeline_ptr: *const c_void, path: *const c_char) -> i32 {
+    if pipeline_ptr.is_null() || path.is_null() { return -1; }
+    let pipeline = &*(pipeline_ptr as *const AudioPipeline);
+    let Ok(path) = CStr::from_ptr(path).to_str() else { return -1; };
     
-    unsafe {
-        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-        match pipeline.add_voice_effect(VoiceEffect::Harmonics { overtones, undertones, distortion }) {
-            Ok(_) => 0,
-            Err(_) => -1,
+    match pipeline.save_preset(path) {
+        Ok(()) => 0,
+        Err(e) => {
+            println!("⚠️ {}", e);
+            -1
         }
     }
 }
 
-/// Добавляет модуляционные эффекты
+/// Загружает и применяет пресет из JSON файла. Возвращает 0 при успехе, -1 при ошибке.
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден,
+/// `path` должен указывать на строку UTF-8 с нулем на конце.
 #[no_mangle]
-pub extern "C" fn add_modulation_effect(pipeline_ptr: *mut c_void, vibrato_rate: f32, vibrato_depth: f32, tremolo_rate: f32, tremolo_depth: f32) -> i32 {
-    if pipeline_ptr.is_null() {
-        return -1;
-    }
+pub unsafe extern "C" fn load_preset(pipeline_ptr: *mut c_void, path: *const c_char) -> i32 {
+    if pipeline_ptr.is_null() || path.is_null() { return -1; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    let Ok(path) = CStr::from_ptr(path).to_str() else { return -1; };
     
-    unsafe {
-        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-        match pipeline.add_voice_effect(VoiceEffect::Modulation { vibrato_rate, vibrato_depth, tremolo_rate, tremolo_depth }) {
-            Ok(_) => 0,
-            Err(_) => -1,
+    match pipeline.load_preset(path) {
+        Ok(()) => 0,
+        Err(e) => {
+            println!("⚠️ {}", e);
+            -1
         }
     }
 }
 
-/// Добавляет эффект реверберации
+/// Устанавливает размер помещения и затухание реверберации (0.0 - 1.0)
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
 #[no_mangle]
-pub extern "C" fn add_reverb_effect(pipeline_ptr: *mut c_void, room_size: f32, damping: f32, wet_level: f32) -> i32 {
-    if pipeline_ptr.is_null() {
-        return -1;
-    }
+pub unsafe extern "C" fn set_reverb(pipeline_ptr: *mut c_void, size: f32, damping: f32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.set_reverb(size, damping);
+}
+
+/// Устанавливает высоту голоса эффекта Robot (7) в Гц
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn set_robot_pitch(pipeline_ptr: *mut c_void, frequency: f32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.set_robot_pitch(frequency);
+}
+
+/// Задает срез низа и верха перед эффектом в Гц (20 и 20000 - срез выключен)
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn set_filters(pipeline_ptr: *mut c_void, highpass_freq: f32, lowpass_freq: f32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.set_filters(highpass_freq, lowpass_freq);
+}
+
+/// Меняет или добавляет полосу эквалайзера. `stage`: 0 - до эффекта, 1 - после;
+/// `band_type`: 0 - колокол, 1/2 - нижняя/верхняя полка, 3/4 - ФНЧ/ФВЧ, 5 - режекция, 6 - фазовый.
+/// Индекс, равный числу полос, добавляет новую. Возвращает 0 при успехе, -1 при ошибке.
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn set_eq_band(
+    pipeline_ptr: *mut c_void,
+    stage: u32,
+    index: u32,
+    enabled: bool,
+    band_type: u32,
+    frequency: f32,
+    gain: f32,
+    q: f32,
+) -> i32 {
+    if pipeline_ptr.is_null() { return -1; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    let band = EqBand { enabled, band_type: EqBandType::from_u32(band_type), frequency, gain, q };
+    match pipeline.set_eq_band(EqStage::from_u32(stage), index as usize, band) {
+        Ok(()) => 0,
+        Err(e) => {
+            println!("Rust: Ошибка эквалайзера: {}", e);
+            -1
+        }
+    }
+}
+
+/// Удаляет все полосы ступени эквалайзера (`stage`: 0 - до эффекта, 1 - после)
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn clear_eq(pipeline_ptr: *mut c_void, stage: u32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    let _ = pipeline.set_eq_bands(EqStage::from_u32(stage), &[]);
+}
+
+/// Записывает в `response` суммарную АЧХ среза и эквалайзера (дБ) на `count` частотах из `frequencies`
+///
+/// # Safety
+/// `frequencies` и `response` должны указывать на `count` значений.
+#[no_mangle]
+pub unsafe extern "C" fn get_eq_response(pipeline_ptr: *const c_void, frequencies: *const f32, response: *mut f32, count: usize) {
+    if pipeline_ptr.is_null() || frequencies.is_null() || response.is_null() { return; }
+    let pipeline = &*(pipeline_ptr as *const AudioPipeline);
+    let frequencies = std::slice::from_raw_parts(frequencies, count);
+    let response = std::slice::from_raw_parts_mut(response, count);
+    response.copy_from_slice(&pipeline.eq_magnitude_response(frequencies));
+}
+
+/// Настраивает гейт / экспандер: порог в дБ, ratio (1 - 100) и наибольшее ослабление в дБ
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn set_gate(pipeline_ptr: *mut c_void, enabled: bool, threshold: f32, ratio: f32, range: f32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.set_gate(enabled, threshold, ratio, range);
+}
+
+/// Задает время открытия и закрытия гейта в секундах
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn set_gate_times(pipeline_ptr: *mut c_void, attack: f32, release: f32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.set_gate_times(attack, release);
+}
+
+/// Настраивает компрессор: порог в дБ, ratio (1 - 20), колено и компенсация уровня в дБ
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn set_compressor(pipeline_ptr: *mut c_void, enabled: bool, threshold: f32, ratio: f32, knee: f32, makeup: f32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.set_compressor(enabled, threshold, ratio, knee, makeup);
+}
+
+/// Задает время атаки и восстановления компрессора в секундах
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn set_compressor_times(pipeline_ptr: *mut c_void, attack: f32, release: f32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.set_compressor_times(attack, release);
+}
+
+/// Настраивает выходной лимитер: потолок в дБTP и время восстановления в секундах
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn set_limiter(pipeline_ptr: *mut c_void, enabled: bool, ceiling: f32, release: f32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.set_limiter(enabled, ceiling, release);
+}
+
+/// Возвращает ослабление гейта, компрессора и лимитера в дБ за последний блок
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn get_gain_reduction(pipeline_ptr: *const c_void) -> GainReduction {
+    if pipeline_ptr.is_null() { return GainReduction::default(); }
+    let pipeline = &*(pipeline_ptr as *const AudioPipeline);
+    pipeline.get_gain_reduction()
+}
+
+/// Устанавливает параметры шума
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn set_noise(pipeline_ptr: *mut c_void, noise_type: u32, level: f32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.parameters.noise_type.store(noise_type, Ordering::Relaxed);
+    pipeline.parameters.noise_level.store(level.clamp(0.0, 1.0), Ordering::Relaxed);
+}
+
+/// Задает длительность перехода при смене эффекта в секундах
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn set_effect_crossfade(pipeline_ptr: *mut c_void, seconds: f32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.set_effect_crossfade(seconds);
+}
+
+/// Задает времена сглаживания параметров в секундах (0 - изменение скачком)
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn set_parameter_smoothing(pipeline_ptr: *mut c_void, gain_time: f32, mix_time: f32, filter_time: f32, delay_time: f32) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.set_smoothing(SmoothingConfig { gain_time, mix_time, filter_time, delay_time });
+}
+
+/// Запускает обработку
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn start_processing(pipeline_ptr: *mut c_void) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.start_processing();
+}
+
+/// Останавливает обработку
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn stop_processing(pipeline_ptr: *mut c_void) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.stop_processing();
+}
+
+/// Получает загрузку NPU (возвращает процент 0.0-100.0)
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn get_npu_load(pipeline_ptr: *mut c_void) -> f32 {
+    if pipeline_ptr.is_null() { return 0.0; }
+    let pipeline = &*(pipeline_ptr as *mut AudioPipeline);
+    pipeline.ai_processor.get_average_npu_load()
+}
+
+/// Получает задержку AI обработки в миллисекундах
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn get_ai_latency(pipeline_ptr: *mut c_void) -> f32 {
+    if pipeline_ptr.is_null() { return 0.0; }
+    let pipeline = &*(pipeline_ptr as *mut AudioPipeline);
+    pipeline.ai_processor.get_average_latency()
+}
+
+/// Проверяет поддержку NPU
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn supports_npu(pipeline_ptr: *mut c_void) -> bool {
+    if pipeline_ptr.is_null() { return false; }
+    let pipeline = &*(pipeline_ptr as *mut AudioPipeline);
+    pipeline.supports_neural_engine()
+}
+
+/// Возвращает основной тон входа: частоту, уверенность, ноту MIDI и отклонение в центах
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn get_pitch(pipeline_ptr: *const c_void) -> PitchReading {
+    if pipeline_ptr.is_null() { return PitchReading::default(); }
+    let pipeline = &*(pipeline_ptr as *const AudioPipeline);
+    PitchReading::from(pipeline.get_pitch())
+}
+
+/// Записывает название ноты входа ("A4") в `buffer` как строку с нулем на конце.
+/// Возвращаемое значение - как у `get_preset_json`; 0 - тон не найден.
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден,
+/// `buffer` должен указывать на `buffer_len` байт (или быть null при запросе длины).
+#[no_mangle]
+pub unsafe extern "C" fn get_pitch_note_name(pipeline_ptr: *const c_void, buffer: *mut u8, buffer_len: usize) -> usize {
+    if pipeline_ptr.is_null() { return 0; }
+    let pipeline = &*(pipeline_ptr as *const AudioPipeline);
+    let Some(estimate) = pipeline.get_pitch() else { return 0; };
+    copy_c_string(&estimate.note_name(), buffer, buffer_len)
+}
+
+/// Включает или выключает отслеживание основного тона входа
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn set_pitch_tracking(pipeline_ptr: *mut c_void, enabled: bool) {
+    if pipeline_ptr.is_null() { return; }
+    let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+    pipeline.set_pitch_tracking(enabled);
+}
+
+/// Освобождает память, выделенную под аудиоконвейер.
+///
+/// # Safety
+/// Эта функция небезопасна, так как работает с сырыми указателями из C.
+#[no_mangle]
+pub unsafe extern "C" fn destroy_pipeline(pipeline_ptr: *mut c_void) {
+    if !pipeline_ptr.is_null() {
+        println!("Rust: destroy_pipeline() вызван.");
+        drop(Box::from_raw(pipeline_ptr as *mut AudioPipeline));
+    }
+}
+
+// === Neural Engine C API ===
+
+/// Добавляет эффект изменения высоты тона
+#[no_mangle]
+pub extern "C" fn add_pitch_shift_effect(pipeline_ptr: *mut c_void, semitones: f32) -> i32 {
+    if pipeline_ptr.is_null() {
+        return -1;
+    }
     
     unsafe {
         let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-        match pipeline.add_voice_effect(VoiceEffect::Reverb { room_size, damping, wet_level }) {
+        match pipeline.add_voice_effect(VoiceEffect::PitchShift(semitones)) {
             Ok(_) => 0,
             Err(_) => -1,
         }
     }
 }
 
-/// Добавляет эффект хоруса
+/// Добавляет эффект изменения формант
 #[no_mangle]
-pub extern "C" fn add_chorus_effect(pipeline_ptr: *mut c_void, voices: u32, delay: f32, depth: f32, rate: f32) -> i32 {
+pub extern "C" fn add_formant_shift_effect(pipeline_ptr: *mut c_void, shift: f32) -> i32 {
     if pipeline_ptr.is_null() {
         return -1;
     }
     
     unsafe {
         let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-        match pipeline.add_voice_effect(VoiceEffect::Chorus { voices, delay, depth, rate }) {
+        match pipeline.add_voice_effect(VoiceEffect::FormantShift(shift)) {
             Ok(_) => 0,
             Err(_) => -1,
         }
     }
 }
 
-/// Добавляет эффект искажения
+/// Добавляет эффект изменения голоса
 #[no_mangle]
-pub extern "C" fn add_distortion_effect(pipeline_ptr: *mut c_void, drive: f32, tone: f32, level: f32) -> i32 {
+pub extern "C" fn add_voice_changer_effect(pipeline_ptr: *mut c_void, gender: f32, age: f32, roughness: f32) -> i32 {
     if pipeline_ptr.is_null() {
         return -1;
     }
     
     unsafe {
         let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-        match pipeline.add_voice_effect(VoiceEffect::Distortion { drive, tone, level }) {
+        match pipeline.add_voice_effect(VoiceEffect::VoiceChanger { gender, age, roughness }) {
             Ok(_) => 0,
             Err(_) => -1,
         }
     }
 }
 
-/// Добавляет эффект автотюна
+/// Добавляет гармонические эффекты
 #[no_mangle]
-pub extern "C" fn add_autotune_effect(pipeline_ptr: *mut c_void, correction: f32, speed: f32, key: i32) -> i32 {
+pub extern "C" fn add_harmonics_effect(pipeline_ptr: *mut c_void, overtones: f32, undertones: f32, distortion: f32) -> i32 {
     if pipeline_ptr.is_null() {
         return -1;
     }
     
     unsafe {
         let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-        match pipeline.add_voice_effect(VoiceEffect::AutoTune { correction, speed, key }) {
+        match pipeline.add_voice_effect(VoiceEffect::Harmonics { overtones, undertones, distortion }) {
             Ok(_) => 0,
             Err(_) => -1,
         }
     }
 }
 
-/// Очищает все голосовые эффекты
+/// Добавляет модуляционные эффекты (синусоидальный LFO, тремоло в каналах в фазе)
 #[no_mangle]
-pub extern "C" fn clear_voice_effects(pipeline_ptr: *mut c_void) {
+pub extern "C" fn add_modulation_effect(pipeline_ptr: *mut c_void, vibrato_rate: f32, vibrato_depth: f32, tremolo_rate: f32, tremolo_depth: f32) -> i32 {
+    add_modulation_effect_with_shape(pipeline_ptr, vibrato_rate, vibrato_depth, tremolo_rate, tremolo_depth, 0, 0.0)
+}
+
+/// Добавляет модуляционные эффекты с выбором формы LFO:
+/// 0 - синус, 1 - треугольник, 2 - случайная. `spread` 0.0-1.0 - сдвиг фазы тремоло между каналами
+#[no_mangle]
+pub extern "C" fn add_modulation_effect_with_shape(
+    pipeline_ptr: *mut c_void,
+    vibrato_rate: f32,
+    vibrato_depth: f32,
+    tremolo_rate: f32,
+    tremolo_depth: f32,
+    shape: u32,
+    spread: f32,
+) -> i32 {
     if pipeline_ptr.is_null() {
-        return;
+        return -1;
     }
     
+    let shape = match shape {
+        0 => LfoShape::Sine,
+        1 => LfoShape::Triangle,
+        2 => LfoShape::Random,
+        _ => return -1,
+    };
+    
     unsafe {
         let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
-        pipeline.clear_voice_effects();
+        match pipeline.add_voice_effect(VoiceEffect::Modulation { vibrato_rate, vibrato_depth, tremolo_rate, tremolo_depth, shape, spread }) {
+            Ok(_) => 0,
+            Err(_) => -1,
+        }
     }
 }
 
-/// Возвращает нагрузку на Neural Engine (0.0-100.0)
+/// Добавляет эффект реверберации
 #[no_mangle]
-pub extern "C" fn get_neural_load(pipeline_ptr: *const c_void) -> f32 {
+pub extern "C" fn add_reverb_effect(pipeline_ptr: *mut c_void, room_size: f32, damping: f32, wet_level: f32) -> i32 {
     if pipeline_ptr.is_null() {
-        return 0.0;
+        return -1;
     }
     
     unsafe {
-        let pipeline = &*(pipeline_ptr as *const AudioPipeline);
-        pipeline.get_neural_load()
+        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+        match pipeline.add_voice_effect(VoiceEffect::Reverb { room_size, damping, wet_level }) {
+            Ok(_) => 0,
+            Err(_) => -1,
+        }
     }
 }
 
-/// Возвращает задержку Neural Engine в наносекундах
+/// Добавляет эффект хоруса (без обратной связи, каналы разнесены на четверть периода LFO)
 #[no_mangle]
-pub extern "C" fn get_neural_latency_ns(pipeline_ptr: *const c_void) -> u64 {
+pub extern "C" fn add_chorus_effect(pipeline_ptr: *mut c_void, voices: u32, delay: f32, depth: f32, rate: f32) -> i32 {
+    add_chorus_effect_with_feedback(pipeline_ptr, voices, delay, depth, rate, 0.0, 0.5)
+}
+
+/// Добавляет эффект хоруса с обратной связью (0.0-0.9) и стерео разносом (0.0-1.0)
+#[no_mangle]
+pub extern "C" fn add_chorus_effect_with_feedback(
+    pipeline_ptr: *mut c_void,
+    voices: u32,
+    delay: f32,
+    depth: f32,
+    rate: f32,
+    feedback: f32,
+    spread: f32,
+) -> i32 {
     if pipeline_ptr.is_null() {
-        return 0;
+        return -1;
     }
     
     unsafe {
-        let pipeline = &*(pipeline_ptr as *const AudioPipeline);
-        pipeline.get_neural_latency_ns()
+        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+        match pipeline.add_voice_effect(VoiceEffect::Chorus { voices, delay, depth, rate, feedback, spread }) {
+            Ok(_) => 0,
+            Err(_) => -1,
+        }
+    }
+}
+
+/// Добавляет эффект флэнжера: задержка в мс, обратная связь -0.95..0.95, стерео разнос 0.0-1.0
+#[no_mangle]
+pub extern "C" fn add_flanger_effect(pipeline_ptr: *mut c_void, delay: f32, depth: f32, rate: f32, feedback: f32, spread: f32) -> i32 {
+    if pipeline_ptr.is_null() {
+        return -1;
+    }
+    
+    unsafe {
+        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+        match pipeline.add_voice_effect(VoiceEffect::Flanger { delay, depth, rate, feedback, spread }) {
+            Ok(_) => 0,
+            Err(_) => -1,
+        }
+    }
+}
+
+/// Добавляет эффект фейзера: число звеньев 2-12, обратная связь -0.95..0.95, стерео разнос 0.0-1.0
+#[no_mangle]
+pub extern "C" fn add_phaser_effect(pipeline_ptr: *mut c_void, stages: u32, depth: f32, rate: f32, feedback: f32, spread: f32) -> i32 {
+    if pipeline_ptr.is_null() {
+        return -1;
+    }
+    
+    unsafe {
+        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+        match pipeline.add_voice_effect(VoiceEffect::Phaser { stages, depth, rate, feedback, spread }) {
+            Ok(_) => 0,
+            Err(_) => -1,
+        }
+    }
+}
+
+/// Добавляет кольцевой модулятор: частота в Гц, микс 0.0-1.0
+#[no_mangle]
+pub extern "C" fn add_ring_modulator_effect(pipeline_ptr: *mut c_void, frequency: f32, mix: f32) -> i32 {
+    if pipeline_ptr.is_null() {
+        return -1;
+    }
+    
+    unsafe {
+        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+        match pipeline.add_voice_effect(VoiceEffect::RingModulator { frequency, mix }) {
+            Ok(_) => 0,
+            Err(_) => -1,
+        }
+    }
+}
+
+/// Добавляет канальный вокодер: 16-32 полосы, высота несущей в Гц,
+/// форма несущей (0 - пила, 1 - меандр, 2 - шум), микс 0.0-1.0
+#[no_mangle]
+pub extern "C" fn add_vocoder_effect(pipeline_ptr: *mut c_void, bands: u32, pitch: f32, waveform: u32, mix: f32) -> i32 {
+    if pipeline_ptr.is_null() {
+        return -1;
+    }
+    
+    let waveform = match waveform {
+        0 => CarrierWaveform::Saw,
+        1 => CarrierWaveform::Pulse,
+        2 => CarrierWaveform::Noise,
+        _ => return -1,
+    };
+    
+    unsafe {
+        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+        match pipeline.add_voice_effect(VoiceEffect::Vocoder { bands, pitch, waveform, mix }) {
+            Ok(_) => 0,
+            Err(_) => -1,
+        }
+    }
+}
+
+/// Добавляет эффект искажения
+#[no_mangle]
+pub extern "C" fn add_distortion_effect(pipeline_ptr: *mut c_void, drive: f32, tone: f32, level: f32) -> i32 {
+    if pipeline_ptr.is_null() {
+        return -1;
+    }
+    
+    unsafe {
+        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+        match pipeline.add_voice_effect(VoiceEffect::Distortion { drive, tone, level }) {
+            Ok(_) => 0,
+            Err(_) => -1,
+        }
+    }
+}
+
+/// Добавляет эффект автотюна (мажорный лад)
+#[no_mangle]
+pub extern "C" fn add_autotune_effect(pipeline_ptr: *mut c_void, correction: f32, speed: f32, key: i32) -> i32 {
+    add_autotune_effect_with_scale(pipeline_ptr, correction, speed, key, 0, 0)
+}
+
+/// Добавляет эффект автотюна с выбором лада:
+/// 0 - мажор, 1 - минор, 2 - хроматический, 3 - своя маска `custom_mask` (бит i - ступень на i полутонов выше тоники)
+#[no_mangle]
+pub extern "C" fn add_autotune_effect_with_scale(
+    pipeline_ptr: *mut c_void,
+    correction: f32,
+    speed: f32,
+    key: i32,
+    scale: u32,
+    custom_mask: u32,
+) -> i32 {
+    if pipeline_ptr.is_null() {
+        return -1;
+    }
+    
+    let scale = match scale {
+        0 => Scale::Major,
+        1 => Scale::Minor,
+        2 => Scale::Chromatic,
+        3 => Scale::Custom(custom_mask as u16),
+        _ => return -1,
+    };
+    
+    unsafe {
+        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+        match pipeline.add_voice_effect(VoiceEffect::AutoTune { correction, speed, key, scale }) {
+            Ok(_) => 0,
+            Err(_) => -1,
+        }
+    }
+}
+
+/// Возвращает ноту MIDI, найденную автотюном (-1 - тон не найден или автотюна нет)
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn get_autotune_detected_note(pipeline_ptr: *const c_void) -> i32 {
+    if pipeline_ptr.is_null() { return -1; }
+    let pipeline = &*(pipeline_ptr as *const AudioPipeline);
+    pipeline.get_autotune_status().and_then(|status| status.detected_note).unwrap_or(-1)
+}
+
+/// Возвращает ноту MIDI, к которой подстраивает автотюн (-1 - нет цели)
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn get_autotune_target_note(pipeline_ptr: *const c_void) -> i32 {
+    if pipeline_ptr.is_null() { return -1; }
+    let pipeline = &*(pipeline_ptr as *const AudioPipeline);
+    pipeline.get_autotune_status().and_then(|status| status.target_note).unwrap_or(-1)
+}
+
+/// Возвращает текущую коррекцию автотюна в полутонах
+///
+/// # Safety
+/// `pipeline_ptr` должен быть получен из `create_pipeline` и еще не освобожден.
+#[no_mangle]
+pub unsafe extern "C" fn get_autotune_correction(pipeline_ptr: *const c_void) -> f32 {
+    if pipeline_ptr.is_null() { return 0.0; }
+    let pipeline = &*(pipeline_ptr as *const AudioPipeline);
+    pipeline.get_autotune_status().map_or(0.0, |status| status.correction_semitones)
+}
+
+/// Очищает все голосовые эффекты
+#[no_mangle]
+pub extern "C" fn clear_voice_effects(pipeline_ptr: *mut c_void) {
+    if pipeline_ptr.is_null() {
+        return;
+    }
+    
+    unsafe {
+        let pipeline = &mut *(pipeline_ptr as *mut AudioPipeline);
+        pipeline.clear_voice_effects();
+    }
+}
+
+/// Возвращает нагрузку на Neural Engine (0.0-100.0)
+#[no_mangle]
+pub extern "C" fn get_neural_load(pipeline_ptr: *const c_void) -> f32 {
+    if pipeline_ptr.is_null() {
+        return 0.0;
+    }
+    
+    unsafe {
+        let pipeline = &*(pipeline_ptr as *const AudioPipeline);
+        pipeline.get_neural_load()
+    }
+}
+
+/// Возвращает задержку Neural Engine в наносекундах
+#[no_mangle]
+pub extern "C" fn get_neural_latency_ns(pipeline_ptr: *const c_void) -> u64 {
+    if pipeline_ptr.is_null() {
+        return 0;
+    }
+    
+    unsafe {
+        let pipeline = &*(pipeline_ptr as *const AudioPipeline);
+        pipeline.get_neural_latency_ns()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audio_pipeline_creation() {
+        let pipeline = AudioPipeline::new(44100.0, 512);
+        assert_eq!(pipeline.samples_processed, 0);
+        assert!(!pipeline.is_processing.load(Ordering::Relaxed));
+    }
+    
+    #[test]
+    fn test_platform_initialization() {
+        // Тестируем создание конвейера с платформой
+        let result = AudioPipeline::new_with_platform();
+        assert!(result.is_ok(), "Не удалось создать конвейер с платформой");
+        
+        let pipeline = result.unwrap();
+        println!("Платформа: {}", pipeline.platform_info());
+        println!("Поддержка низкой задержки: {}", pipeline.supports_low_latency());
+        println!("Поддержка Neural Engine: {}", pipeline.supports_neural_engine());
+    }
+
+    #[test]
+    fn test_process_callback() {
+        let pipeline = Arc::new(Mutex::new(AudioPipeline::new(44100.0, 512)));
+        pipeline.lock().unwrap().start_processing();
+        let mut callback = AudioPipeline::process_callback(&pipeline);
+        
+        let input = vec![0.5f32; 64];
+        let mut output = vec![0.0f32; 64];
+        callback(&input, &mut output);
+        assert!(output.iter().all(|&x| (x - 0.5).abs() < 1e-6));
+        assert_eq!(pipeline.lock().unwrap().samples_processed, 64);
+        
+        // Пока конвейер занят, callback не блокируется и выдает тишину
+        {
+            let _guard = pipeline.lock().unwrap();
+            callback(&input, &mut output);
+            assert!(output.iter().all(|&x| x == 0.0));
+        }
+        assert_eq!(pipeline.lock().unwrap().get_stream_stats().underruns, 1);
+
+        // Ручки UI меняют параметры без блокировки - callback не пропускает блоки
+        let parameters = pipeline.lock().unwrap().parameters();
+        parameters.set_filters(300.0, 3400.0);
+        callback(&input, &mut output);
+        assert!(output.iter().any(|&x| x != 0.0));
+        assert_eq!(pipeline.lock().unwrap().parameters.highpass_freq.load(Ordering::Relaxed), 300.0);
+        assert_eq!(pipeline.lock().unwrap().get_stream_stats().underruns, 1);
+
+        // После освобождения конвейера callback тоже выдает тишину
+        drop(pipeline);
+        output.fill(1.0);
+        callback(&input, &mut output);
+        assert!(output.iter().all(|&x| x == 0.0));
+    }
+
+    #[test]
+    fn test_file_platform() {
+        use platform::file::{FilePacing, FilePlatform};
+        use platform::PlatformAudio;
+        
+        let dir = std::env::temp_dir();
+        let input_path = dir.join(format!("dsp_core_file_in_{}.wav", std::process::id()));
+        let output_path = dir.join(format!("dsp_core_file_out_{}.wav", std::process::id()));
+        
+        // Стерео 16-bit вход, 1000 кадров - не кратно размеру блока
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 22050,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&input_path, spec).unwrap();
+        for _ in 0..1000 {
+            writer.write_sample(16384i16).unwrap();
+            writer.write_sample(16384i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        
+        let mut file_audio = FilePlatform::open(&input_path, &output_path, FilePacing::AsFastAsPossible).unwrap();
+        file_audio.set_buffer_size(256);
+        assert_eq!(file_audio.get_sample_rate(), 22050.0);
+        assert_eq!(file_audio.get_buffer_size(), 256);
+        
+        let pipeline = Arc::new(Mutex::new(AudioPipeline::new(22050.0, 256)));
+        pipeline.lock().unwrap().start_processing();
+        file_audio.set_process_callback(AudioPipeline::process_callback(&pipeline));
+        file_audio.start().unwrap();
+        file_audio.wait().unwrap();
+        assert_eq!(file_audio.progress(), 1.0);
+        
+        // 1000 кадров блоками по 256 - 4 вызова; файл не дает overrun/underrun
+        let stats = file_audio.stream_stats();
+        assert_eq!(stats.callbacks, 4);
+        assert_eq!(stats.xruns(), 0);
+        
+        let mut reader = hound::WavReader::open(&output_path).unwrap();
+        assert_eq!(reader.spec().channels, 1);
+        assert_eq!(reader.spec().sample_rate, 22050);
+        let output: Vec<f32> = reader.samples::<f32>().map(|s| s.unwrap()).collect();
+        assert_eq!(output.len(), 1000);
+        assert!(output.iter().all(|&x| (x - 0.5).abs() < 1e-4));
+        assert_eq!(pipeline.lock().unwrap().samples_processed, 1024);
+        
+        let _ = std::fs::remove_file(&input_path);
+        let _ = std::fs::remove_file(&output_path);
+    }
+
+    #[test]
+    fn test_offline_processing() {
+        use offline::{process_file, process_file_with_options, OfflineOptions, OutputFormat, ProcessingChain};
+        
+        let dir = std::env::temp_dir();
+        let input_path = dir.join(format!("dsp_core_offline_in_{}.wav", std::process::id()));
+        let output_path = dir.join(format!("dsp_core_offline_out_{}.wav", std::process::id()));
+        
+        // Стерео 24-bit, каналы с разными сигналами
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 48000,
+            bits_per_sample: 24,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&input_path, spec).unwrap();
+        for i in 0..3000 {
+            let t = i as f32 / 48000.0;
+            writer.write_sample(((t * 440.0 * std::f32::consts::TAU).sin() * 4_000_000.0) as i32).unwrap();
+            writer.write_sample(-2_000_000i32).unwrap();
+        }
+        writer.finalize().unwrap();
+        
+        // Без эффекта файл проходит без изменений и с тем же форматом
+        let report = process_file(&input_path, &output_path, &ProcessingChain::Effect(EffectType::None)).unwrap();
+        assert_eq!(report.channels, 2);
+        assert_eq!(report.output_frames, 3000);
+        let mut original = hound::WavReader::open(&input_path).unwrap();
+        let mut processed = hound::WavReader::open(&output_path).unwrap();
+        assert_eq!(processed.spec(), spec);
+        let a: Vec<i32> = original.samples::<i32>().map(|s| s.unwrap()).collect();
+        let b: Vec<i32> = processed.samples::<i32>().map(|s| s.unwrap()).collect();
+        assert_eq!(a.len(), b.len());
+        assert!(a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() <= 1));
+        
+        // Цепочка голосовых эффектов с понижением частоты и float выходом
+        let chain = ProcessingChain::VoiceEffects(vec![
+            VoiceEffect::Distortion { drive: 0.3, tone: 0.5, level: 0.8 },
+        ]);
+        let options = OfflineOptions {
+            block_size: 256,
+            output_sample_rate: Some(16000),
+            output_format: OutputFormat::Float32,
+            ..OfflineOptions::default()
+        };
+        let mut updates = Vec::new();
+        let report = process_file_with_options(&input_path, &output_path, &chain, &options, |p| updates.push(p)).unwrap();
+        assert_eq!(report.input_sample_rate, 48000);
+        assert_eq!(report.output_sample_rate, 16000);
+        assert!((report.output_frames as i64 - 1000).abs() <= 1);
+        assert_eq!(updates.first(), Some(&0.0));
+        assert_eq!(updates.last(), Some(&1.0));
+        assert!(updates.windows(2).all(|w| w[0] <= w[1]));
+        
+        let mut processed = hound::WavReader::open(&output_path).unwrap();
+        assert_eq!(processed.spec().sample_format, hound::SampleFormat::Float);
+        assert_eq!(processed.spec().sample_rate, 16000);
+        assert!(processed.samples::<f32>().all(|s| s.unwrap().is_finite()));
+        
+        let _ = std::fs::remove_file(&input_path);
+        let _ = std::fs::remove_file(&output_path);
+    }
+
+    #[test]
+    fn test_effects() {
+        let mut pipeline = AudioPipeline::new(44100.0, 512);
+        pipeline.start_processing();
+        
+        // Тестируем различные эффекты
+        let effects = [
+            EffectType::None,
+            EffectType::Monster,
+            EffectType::HighPitch,
+            EffectType::Cave,
+            EffectType::Radio,
+            EffectType::Cathedral,
+            EffectType::Underwater,
+            EffectType::Robot,
+            EffectType::Demon,
+            EffectType::Alien,
+        ];
+        
+        // Создаем тестовый сигнал
+        let input: Vec<f32> = (0..512).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        let mut output = vec![0.0f32; 512];
+        
+        for effect in effects.iter() {
+            pipeline.set_effect(*effect);
+            // Сдвиг высоты тона вносит задержку - прогоняем несколько блоков
+            for _ in 0..16 {
+                pipeline.process_block(&input, &mut output);
+            }
+            
+            // Проверяем, что выходной сигнал был изменен
+            assert!(output.iter().any(|&x| x != 0.0));
+        }
+    }
+
+    #[test]
+    fn test_pitch_effects() {
+        let sample_rate = 44100.0;
+        let input: Vec<f32> = (0..sample_rate as usize)
+            .map(|i| (std::f32::consts::TAU * 220.0 * i as f32 / sample_rate).sin() * 0.3)
+            .collect();
+        
+        for (effect, expected) in [(EffectType::HighPitch, 330.0), (EffectType::Monster, 110.0)] {
+            let mut pipeline = AudioPipeline::new(sample_rate, 512);
+            pipeline.start_processing();
+            pipeline.set_effect(effect);
+            assert_eq!(pipeline.parameters.pitch_shift.load(Ordering::Relaxed), expected / 220.0);
+            
+            let mut output = vec![0.0f32; input.len()];
+            for (block_in, block_out) in input.chunks(512).zip(output.chunks_mut(512)) {
+                pipeline.process_block(block_in, block_out);
+            }
+            
+            let settled = &output[pipeline.latency_samples() + 4096..];
+            let frequency = estimate_frequency(settled, sample_rate);
+            assert!((frequency - expected).abs() < expected * 0.05, "{:?}: {:.1} Гц", effect, frequency);
+        }
+        
+        // Параметр pitch_shift управляет сдвигом и после выбора эффекта
+        let mut pipeline = AudioPipeline::new(sample_rate, 512);
+        pipeline.start_processing();
+        pipeline.set_effect(EffectType::HighPitch);
+        pipeline.set_pitch_shift(2.0);
+        let mut output = vec![0.0f32; input.len()];
+        for (block_in, block_out) in input.chunks(512).zip(output.chunks_mut(512)) {
+            pipeline.process_block(block_in, block_out);
+        }
+        let frequency = estimate_frequency(&output[pipeline.latency_samples() + 4096..], sample_rate);
+        assert!((frequency - 440.0).abs() < 22.0, "{:.1} Гц", frequency);
+    }
+
+    #[test]
+    fn test_noise_generators() {
+        let mut pipeline = AudioPipeline::new(44100.0, 512);
+        pipeline.start_processing();
+        
+        let noise_types = [NoiseType::White, NoiseType::Pink, NoiseType::Brown];
+        let input = vec![0.0f32; 100]; // Тишина на входе
+        let mut output = vec![0.0f32; 100];
+        
+        for noise_type in noise_types.iter() {
+            pipeline.set_noise(*noise_type, 0.1);
+            pipeline.process_block(&input, &mut output);
+            
+            // Проверяем, что был добавлен шум
+            let rms = calculate_rms(&output);
+            assert!(rms > 0.0, "Шум не был добавлен для {:?}", noise_type);
+        }
+    }
+
+    #[test]
+    fn test_filters() {
+        let mut filter = BiquadFilter::new();
+        
+        // Тест низкочастотного фильтра
+        filter.lowpass(1000.0, 44100.0, 1.0);
+        let output = filter.process(1.0);
+        assert!(output.is_finite());
+        
+        // Тест высокочастотного фильтра
+        filter.highpass(1000.0, 44100.0, 1.0);
+        let output = filter.process(1.0);
+        assert!(output.is_finite());
+        
+        // Тест полосового фильтра
+        filter.bandpass(1000.0, 44100.0, 1.0);
+        let output = filter.process(1.0);
+        assert!(output.is_finite());
+    }
+
+    #[test]
+    fn test_delay_effect() {
+        let mut delay = DelayEffect::new(4410); // 100 мс при 44100 Гц
+        delay.set_delay_time(0.1, 44100.0);
+        delay.set_feedback(0.5);
+        delay.set_mix(0.3);
+        
+        let output = delay.process(1.0);
+        assert!(output.is_finite());
+
+        // Обратная связь эха Cave берется из параметров (пресет cave.json)
+        let tail = |feedback: f32| {
+            let mut dsp = DspProcessor::new(44100.0, 44100);
+            let params = AudioParameters::default();
+            params.delay_time.store(0.01, Ordering::Relaxed);
+            params.delay_feedback.store(feedback, Ordering::Relaxed);
+            (0..4410).map(|i| dsp.process_effect(if i == 0 { 1.0 } else { 0.0 }, EffectType::Cave, &params))
+                .skip(1000).map(|x| x * x).sum::<f32>()
+        };
+        assert!(tail(0.9) > tail(0.0) * 2.0);
+    }
+
+    #[test]
+    fn test_delay_line() {
+        use delay::{DelayInterpolation, DelayLine, NoteValue, StereoDelay};
+        
+        // Дробная задержка: центр импульсного отклика там, где задано, при любой интерполяции
+        for interpolation in [DelayInterpolation::Linear, DelayInterpolation::Allpass, DelayInterpolation::Cubic] {
+            let mut line = DelayLine::new(64);
+            let response: Vec<f32> = (0..40)
+                .map(|n| {
+                    let output = line.read(10.25, interpolation);
+                    line.write(if n == 0 { 1.0 } else { 0.0 });
+                    output
+                })
+                .collect();
+            let sum: f32 = response.iter().sum();
+            let centroid = response.iter().enumerate().map(|(n, y)| n as f32 * y).sum::<f32>() / sum;
+            assert!((sum - 1.0).abs() < 0.01 && (centroid - 10.25).abs() < 0.05, "{:?} {} {}", interpolation, sum, centroid);
+        }
+        
+        // Смена времени задержки на ходу без скачков
+        let mut delay = DelayEffect::new(44100);
+        delay.set_delay_time(0.01, 44100.0);
+        delay.set_feedback(0.0);
+        delay.set_mix(1.0);
+        let mut output = Vec::new();
+        for n in 0..8820 {
+            if n == 4410 {
+                delay.set_delay_time(0.02, 44100.0);
+            }
+            output.push(delay.process((n as f32 * 0.02).sin() * 0.5) - (n as f32 * 0.02).sin() * 0.5);
+        }
+        let max_step = output[1000..].windows(2).map(|w| (w[1] - w[0]).abs()).fold(0.0f32, f32::max);
+        assert!(max_step < 0.02, "{}", max_step);
+        assert!((delay.delay_time() - 0.02).abs() < 1e-6);
+        
+        // Синхронизация с темпом
+        assert!((NoteValue::DottedEighth.seconds(120.0) - 0.375).abs() < 1e-6);
+        delay.set_tempo_sync(120.0, NoteValue::Quarter);
+        (0..44100).for_each(|_| { delay.process(0.0); });
+        assert!((delay.delay_time() - 0.5).abs() < 1e-6);
+        
+        // Пинг-понг: повторы чередуются L, R, L и теряют feedback на каждом
+        let mut stereo = StereoDelay::new(1000, 1000.0);
+        stereo.set_ping_pong(true);
+        stereo.set_delay_time(0.1);
+        stereo.set_feedback(0.5);
+        stereo.set_mix(1.0);
+        let frames: Vec<(f32, f32)> = (0..400).map(|n| stereo.process(if n == 0 { 1.0 } else { 0.0 }, 0.0)).collect();
+        let echoes: Vec<(usize, f32, f32)> = frames.iter().enumerate().skip(1)
+            .filter(|(_, (l, r))| l.abs() > 1e-3 || r.abs() > 1e-3)
+            .map(|(n, &(l, r))| (n, l, r))
+            .collect();
+        assert_eq!(echoes, vec![(100, 0.5, 0.0), (200, 0.0, 0.25), (300, 0.125, 0.0)]);
+        
+        // Фильтр в обратной связи делает повторы темнее
+        let mut bright = DelayEffect::new(1000);
+        let mut dark = DelayEffect::new(1000);
+        dark.set_feedback_filter(20.0, 1000.0);
+        let high: Vec<f32> = (0..20000).map(|n| if n < 200 { (n as f32 * 2.5).sin() } else { 0.0 }).collect();
+        let energy = |delay: &mut DelayEffect| {
+            delay.set_delay_time(0.01, 44100.0);
+            delay.set_feedback(0.9);
+            high.iter().map(|&x| delay.process(x)).skip(5000).map(|y| y * y).sum::<f32>()
+        };
+        assert!(energy(&mut dark) < energy(&mut bright) * 0.01);
+    }
+
+    #[test]
+    fn test_modulation_effects() {
+        use modulation::{Chorus, Flanger, Phaser};
+        use neural_engine::{NeuralConfig, NeuralVoiceProcessor};
+        
+        let sample_rate = 44100.0;
+        let sine = |n: usize, frequency: f32| (n as f32 * std::f32::consts::TAU * frequency / sample_rate).sin() * 0.5;
+        // Отношение самого тихого и самого громкого блока по 10 мс
+        let envelope_ratio = |output: &[f32]| {
+            let levels: Vec<f32> = output.chunks(441).map(|block| block.iter().map(|x| x * x).sum::<f32>().sqrt()).collect();
+            let max = levels.iter().cloned().fold(0.0f32, f32::max);
+            levels.iter().cloned().fold(f32::MAX, f32::min) / max
+        };
+        
+        // LFO слышен: без глубины уровень ровный, с глубиной голоса бьются
+        let mut steady = Chorus::new(sample_rate);
+        steady.set_depth(0.0);
+        let mut chorus = Chorus::new(sample_rate);
+        chorus.set_voices(2);
+        chorus.set_depth(0.5);
+        chorus.set_rate(2.0);
+        let steady: Vec<f32> = (0..88200).map(|n| steady.process(sine(n, 440.0))).skip(8820).collect();
+        let modulated: Vec<f32> = (0..88200).map(|n| chorus.process(sine(n, 440.0))).skip(8820).collect();
+        assert!(envelope_ratio(&steady) > 0.95, "{}", envelope_ratio(&steady));
+        assert!(envelope_ratio(&modulated) < 0.8, "{}", envelope_ratio(&modulated));
+        
+        // Флэнжер и фейзер проводят вырезку через тон 1 кГц
+        let mut flanger = Flanger::new(sample_rate);
+        flanger.set_delay(2.0);
+        flanger.set_depth(1.0);
+        flanger.set_rate(1.0);
+        let output: Vec<f32> = (0..88200).map(|n| flanger.process(sine(n, 1000.0))).skip(4410).collect();
+        assert!(envelope_ratio(&output) < 0.2, "{}", envelope_ratio(&output));
+        let mut phaser = Phaser::new(sample_rate);
+        phaser.set_stages(4);
+        phaser.set_depth(1.0);
+        phaser.set_rate(1.0);
+        phaser.set_feedback(0.5);
+        let output: Vec<f32> = (0..88200).map(|n| phaser.process(sine(n, 1000.0))).skip(4410).collect();
+        assert!(envelope_ratio(&output) < 0.3, "{}", envelope_ratio(&output));
+        assert!(output.iter().all(|x| x.is_finite() && x.abs() < 1.0));
+        
+        // Стерео разнос: одинаковый вход, разные каналы только при spread > 0
+        let stereo_difference = |spread: f32| {
+            let config = NeuralConfig { channels: 2, ..NeuralConfig::default() };
+            let mut processor = NeuralVoiceProcessor::new(config).unwrap();
+            processor.add_effect(VoiceEffect::Flanger { delay: 2.0, depth: 0.8, rate: 0.5, feedback: -0.5, spread }).unwrap();
+            let mut difference = 0.0;
+            for block in 0..40 {
+                let input: Vec<f32> = (0..512).map(|i| sine(block * 512 + i, 700.0)).collect();
+                let mut channels = vec![input.clone(), input];
+                processor.process_planar(&mut channels).unwrap();
+                difference += channels[0].iter().zip(&channels[1]).map(|(l, r)| (l - r).abs()).sum::<f32>();
+            }
+            difference
+        };
+        assert_eq!(stereo_difference(0.0), 0.0);
+        assert!(stereo_difference(1.0) > 100.0);
+        
+        // Старые пресеты хоруса без обратной связи и разноса читаются
+        let effect: VoiceEffect = serde_json::from_str(r#"{"Chorus":{"voices":3,"delay":20.0,"depth":0.5,"rate":1.0}}"#).unwrap();
+        assert_eq!(effect, VoiceEffect::Chorus { voices: 3, delay: 20.0, depth: 0.5, rate: 1.0, feedback: 0.0, spread: 0.0 });
+    }
+
+    #[test]
+    fn test_vibrato_tremolo() {
+        use modulation::{Lfo, Vibrato};
+        use neural_engine::{NeuralConfig, NeuralVoiceProcessor};
+        
+        let sample_rate = 44100.0;
+        let sine = |n: usize| (n as f32 * std::f32::consts::TAU * 440.0 / sample_rate).sin() * 0.5;
+        
+        // Вибрато глубины 1.0 качает высоту на ±1 полутон при любой форме LFO
+        for shape in [LfoShape::Sine, LfoShape::Triangle] {
+            let mut vibrato = Vibrato::new(sample_rate);
+            vibrato.set_shape(shape);
+            vibrato.set_rate(5.0);
+            vibrato.set_depth(1.0);
+            let output: Vec<f32> = (0..44100).map(|n| vibrato.process(sine(n))).collect();
+            // Частота по расстоянию между переходами через ноль снизу вверх
+            let crossings: Vec<f32> = (4410..output.len())
+                .filter(|&n| output[n - 1] < 0.0 && output[n] >= 0.0)
+                .map(|n| n as f32 - output[n] / (output[n] - output[n - 1]))
+                .collect();
+            let frequencies: Vec<f32> = crossings.windows(2).map(|w| sample_rate / (w[1] - w[0])).collect();
+            let max = frequencies.iter().cloned().fold(0.0f32, f32::max);
+            let min = frequencies.iter().cloned().fold(f32::MAX, f32::min);
+            let semitones = 12.0 * (max / min).log2();
+            assert!((1.7..2.2).contains(&semitones), "{:?} {}", shape, semitones);
+        }
+        
+        // Случайный LFO в пределах -1..1 и без скачков
+        let mut lfo = Lfo::new(sample_rate);
+        lfo.set_shape(LfoShape::Random);
+        lfo.set_rate(10.0);
+        let values: Vec<f32> = (0..44100).map(|_| lfo.next_value()).collect();
+        assert!(values.iter().all(|v| v.abs() <= 1.0));
+        assert!(values.windows(2).all(|w| (w[1] - w[0]).abs() < 0.01));
+        assert!(values.iter().cloned().fold(0.0f32, f32::max) > 0.3);
+        
+        // Фазы сохраняются между блоками: блоки по 256 звучат как один длинный блок
+        let effect = VoiceEffect::Modulation {
+            vibrato_rate: 5.5,
+            vibrato_depth: 0.7,
+            tremolo_rate: 3.0,
+            tremolo_depth: 0.6,
+            shape: LfoShape::Sine,
+            spread: 0.0,
+        };
+        let input: Vec<f32> = (0..8192).map(sine).collect();
+        let mut whole = NeuralVoiceProcessor::new(NeuralConfig::default()).unwrap();
+        whole.add_effect(effect.clone()).unwrap();
+        let expected = whole.process(&input).unwrap().output;
+        let mut blocks = NeuralVoiceProcessor::new(NeuralConfig::default()).unwrap();
+        blocks.add_effect(effect).unwrap();
+        let output: Vec<f32> = input.chunks(256).flat_map(|block| blocks.process(block).unwrap().output).collect();
+        assert_eq!(output, expected);
+        
+        // Тремоло с разносом 1.0: каналы в противофазе, сумма громкостей постоянна
+        let config = NeuralConfig { channels: 2, ..NeuralConfig::default() };
+        let mut processor = NeuralVoiceProcessor::new(config).unwrap();
+        processor.add_effect(VoiceEffect::Modulation {
+            vibrato_rate: 5.0,
+            vibrato_depth: 0.0,
+            tremolo_rate: 4.0,
+            tremolo_depth: 0.8,
+            shape: LfoShape::Triangle,
+            spread: 1.0,
+        }).unwrap();
+        let mut channels = vec![vec![0.5f32; 22050]; 2];
+        processor.process_planar(&mut channels).unwrap();
+        assert!(channels[0][1000..].iter().zip(&channels[1][1000..]).all(|(l, r)| ((l + r) - 0.6).abs() < 1e-3));
+        assert!(channels[0].iter().cloned().fold(1.0f32, f32::min) < 0.15);
+    }
+
+    #[test]
+    fn test_robot_vocoder() {
+        use neural_engine::{NeuralConfig, NeuralVoiceProcessor};
+        use vocoder::RingModulator;
+        
+        let sample_rate = 44100.0;
+        // Амплитуда частоты `frequency` в сигнале (Гёрцель)
+        let magnitude = |signal: &[f32], frequency: f32| {
+            let omega = std::f32::consts::TAU * frequency / sample_rate;
+            let (re, im) = signal.iter().enumerate().fold((0.0f32, 0.0f32), |(re, im), (n, &x)| {
+                (re + x * (omega * n as f32).cos(), im - x * (omega * n as f32).sin())
+            });
+            (re * re + im * im).sqrt() * 2.0 / signal.len() as f32
+        };
+        // "Голос": гармоники 220 Гц
+        let voice = |n: usize| {
+            (1..8).map(|k| (n as f32 * std::f32::consts::TAU * 220.0 * k as f32 / sample_rate).sin() / k as f32).sum::<f32>() * 0.2
+        };
+        
+        // Робот теперь DSP эффект: детерминирован и поет на заданной высоте, а не на высоте голоса
+        assert!(!EffectType::Robot.is_ai());
+        let render = || {
+            let mut pipeline = AudioPipeline::new(sample_rate, 512);
+            pipeline.start_processing();
+            pipeline.set_effect(EffectType::Robot);
+            pipeline.set_robot_pitch(150.0);
+            let mut output = Vec::new();
+            let mut block = vec![0.0f32; 512];
+            for start in (0..44032).step_by(512) {
+                let input: Vec<f32> = (start..start + 512).map(voice).collect();
+                pipeline.process_block(&input, &mut block);
+                output.extend_from_slice(&block);
+            }
+            output
+        };
+        let output = render();
+        assert_eq!(output, render());
+        let tail = &output[22050..];
+        assert!(magnitude(tail, 150.0) > magnitude(tail, 220.0) * 4.0, "{} {}", magnitude(tail, 150.0), magnitude(tail, 220.0));
+        let level = |signal: &[f32]| (signal.iter().map(|x| x * x).sum::<f32>() / signal.len() as f32).sqrt();
+        let input_level = level(&(22050..44032).map(voice).collect::<Vec<f32>>());
+        assert!((0.5..2.0).contains(&(level(tail) / input_level)), "{} {}", level(tail), input_level);
+        
+        // Тишина на входе - тишина на выходе (несущая звучит только по огибающей голоса)
+        let mut vocoder = Vocoder::new(sample_rate, 20);
+        assert!((0..4410).map(|_| vocoder.process(0.0)).all(|x| x == 0.0));
+        assert_eq!(Vocoder::new(sample_rate, 8).band_count(), 16);
+        assert_eq!(Vocoder::new(sample_rate, 64).band_count(), 32);
+        
+        // Кольцевой модулятор: 1 кГц * 100 Гц = 900 Гц + 1100 Гц, исходного тона нет
+        let mut ring = RingModulator::new(sample_rate);
+        ring.set_frequency(100.0);
+        let output: Vec<f32> = (0..44100).map(|n| ring.process((n as f32 * std::f32::consts::TAU * 1000.0 / sample_rate).sin())).collect();
+        assert!(magnitude(&output, 1000.0) < 0.01);
+        assert!((magnitude(&output, 900.0) - 0.5).abs() < 0.02 && (magnitude(&output, 1100.0) - 0.5).abs() < 0.02);
+        
+        // Голосовые эффекты: форма несущей сохраняется в JSON
+        let effect = VoiceEffect::Vocoder { bands: 24, pitch: 90.0, waveform: CarrierWaveform::Pulse, mix: 1.0 };
+        let json = serde_json::to_string(&effect).unwrap();
+        assert_eq!(serde_json::from_str::<VoiceEffect>(&json).unwrap(), effect);
+        let mut processor = NeuralVoiceProcessor::new(NeuralConfig::default()).unwrap();
+        processor.add_effect(effect).unwrap();
+        processor.add_effect(VoiceEffect::RingModulator { frequency: 30.0, mix: 0.5 }).unwrap();
+        let input: Vec<f32> = (0..4096).map(voice).collect();
+        let output = processor.process(&input).unwrap().output;
+        assert!(output.iter().all(|x| x.is_finite()) && level(&output[2048..]) > 0.01);
+    }
+
+    #[test]
+    fn test_sidechain_vocoder() {
+        let sample_rate = 44100.0;
+        let magnitude = |signal: &[f32], frequency: f32, rate: f32| {
+            let omega = std::f32::consts::TAU * frequency / rate;
+            let (re, im) = signal.iter().enumerate().fold((0.0f32, 0.0f32), |(re, im), (n, &x)| {
+                (re + x * (omega * n as f32).cos(), im - x * (omega * n as f32).sin())
+            });
+            (re * re + im * im).sqrt() * 2.0 / signal.len() as f32
+        };
+        let voice = |n: usize, rate: f32| {
+            (1..8).map(|k| (n as f32 * std::f32::consts::TAU * 220.0 * k as f32 / rate).sin() / k as f32).sum::<f32>() * 0.2
+        };
+        // Несущая - "синтезатор": пила 330 Гц
+        let synth = |n: usize, rate: f32| ((n as f32 * 330.0 / rate).fract() * 2.0 - 1.0) * 0.3;
+        assert_eq!(EffectType::from_u32(12), EffectType::Vocoder);
+        
+        // Голос ложится на внешнюю несущую: на выходе ее высота, а не высота голоса
+        let mut pipeline = AudioPipeline::new(sample_rate, 512);
+        pipeline.start_processing();
+        pipeline.set_effect(EffectType::Vocoder);
+        let mut output = Vec::new();
+        let mut block = vec![0.0f32; 512];
+        for start in (0..44032).step_by(512) {
+            let input: Vec<f32> = (start..start + 512).map(|n| voice(n, sample_rate)).collect();
+            let carrier: Vec<f32> = (start..start + 512).map(|n| synth(n, sample_rate)).collect();
+            pipeline.process_block_with_sidechain(&input, &carrier, &mut block);
+            output.extend_from_slice(&block);
+        }
+        let tail = &output[22050..];
+        assert!(magnitude(tail, 330.0, sample_rate) > magnitude(tail, 220.0, sample_rate) * 4.0);
+        
+        // Несущая без голоса не проходит
+        let silence = vec![0.0f32; 512];
+        for _ in 0..20 {
+            let carrier: Vec<f32> = (0..512).map(|n| synth(n, sample_rate)).collect();
+            pipeline.process_block_with_sidechain(&silence, &carrier, &mut block);
+        }
+        assert!(block.iter().all(|x| x.abs() < 1e-3));
+        
+        // Без сайдчейна вокодер звучит на внутренней несущей высоты robot_pitch
+        pipeline.set_robot_pitch(150.0);
+        let mut output = Vec::new();
+        for start in (0..22016).step_by(512) {
+            let input: Vec<f32> = (start..start + 512).map(|n| voice(n, sample_rate)).collect();
+            pipeline.process_block(&input, &mut block);
+            output.extend_from_slice(&block);
+        }
+        assert!(magnitude(&output[11025..], 150.0, sample_rate) > magnitude(&output[11025..], 330.0, sample_rate) * 4.0);
+        
+        // Стерео конвейер на частоте 48 кГц устройства: моно несущая проходит передискретизацию вместе с голосом
+        let device_rate = 48000.0;
+        let mut pipeline = AudioPipeline::with_channels(sample_rate, 480, 2);
+        pipeline.set_device_sample_rate(device_rate).unwrap();
+        pipeline.start_processing();
+        pipeline.set_effect(EffectType::Vocoder);
+        let mut left = Vec::new();
+        let mut outputs = vec![vec![0.0f32; 480]; 2];
+        for start in (0..48000).step_by(480) {
+            let input: Vec<f32> = (start..start + 480).map(|n| voice(n, device_rate)).collect();
+            let carrier: Vec<f32> = (start..start + 480).map(|n| synth(n, device_rate)).collect();
+            pipeline.process_planar_with_sidechain(&[&input[..]], &[&carrier[..]], &mut outputs);
+            assert_eq!(outputs[0], outputs[1]);
+            left.extend_from_slice(&outputs[0]);
+        }
+        let tail = &left[24000..];
+        assert!(magnitude(tail, 330.0, device_rate) > magnitude(tail, 220.0, device_rate) * 4.0);
+        
+        // C API: без сайдчейна - то же, что process_audio
+        unsafe {
+            let first = create_pipeline();
+            let second = create_pipeline();
+            for pipeline_ptr in [first, second] {
+                start_processing(pipeline_ptr);
+                set_effect(pipeline_ptr, EffectType::Vocoder as u32);
+            }
+            let input: Vec<f32> = (0..512).map(|n| voice(n, sample_rate)).collect();
+            let (mut a, mut b) = (vec![0.0f32; 512], vec![0.0f32; 512]);
+            process_audio(first, input.as_ptr(), a.as_mut_ptr(), 512);
+            process_audio_with_sidechain(second, input.as_ptr(), std::ptr::null(), b.as_mut_ptr(), 512);
+            assert_eq!(a, b);
+            destroy_pipeline(first);
+            destroy_pipeline(second);
+        }
+    }
+
+    #[test]
+    fn test_dynamics() {
+        use dynamics::{db_to_linear, soft_clip, Limiter};
+        
+        let sample_rate = 44100.0;
+        let sine = |n: usize, frequency: f32, amplitude: f32| (n as f32 * std::f32::consts::TAU * frequency / sample_rate).sin() * amplitude;
+        let peak = |signal: &[f32]| signal.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+        
+        // Лимитер: резкий скачок из тишины не проходит выше потолка (заглядывание вперед)
+        let mut pipeline = AudioPipeline::new(sample_rate, 512);
+        pipeline.start_processing();
+        pipeline.set_limiter(true, -1.0, 0.05);
+        pipeline.parameters.input_gain.store(4.0, Ordering::Relaxed);
+        let mut output = Vec::new();
+        let mut block = vec![0.0f32; 512];
+        for start in (0..22016).step_by(512) {
+            let input: Vec<f32> = (start..start + 512).map(|n| if n < 4410 { 0.0 } else { sine(n, 440.0, 0.5) }).collect();
+            pipeline.process_block(&input, &mut block);
+            output.extend_from_slice(&block);
+        }
+        assert!(peak(&output) <= db_to_linear(-1.0) + 1e-4, "{}", peak(&output));
+        assert!(peak(&output[11025..]) > db_to_linear(-1.5));
+        assert!(pipeline.get_gain_reduction().limiter > 5.0);
+        assert_eq!(pipeline.latency_samples(), Limiter::new(sample_rate, 1).latency());
+        
+        // Межсэмпловый пик: синус fs/4 со сдвигом фазы 45° - сэмплы на 0.71 амплитуды, пик между ними
+        let mut limiter = Limiter::new(sample_rate, 1);
+        limiter.set_ceiling(0.0, 0.05);
+        let output: Vec<f32> = (0..8820)
+            .map(|n| {
+                let mut frame = [1.2 * (n as f32 * std::f32::consts::FRAC_PI_2 + std::f32::consts::FRAC_PI_4).sin()];
+                limiter.process_frame(&mut frame);
+                frame[0]
+            })
+            .collect();
+        assert!(peak(&output[4410..]) < 0.75 && peak(&output[4410..]) > 0.65, "{}", peak(&output[4410..]));
+        
+        // Компрессор: -6 дБ при пороге -18 и ratio 4 сжимается на 9 дБ
+        let mut pipeline = AudioPipeline::new(sample_rate, 512);
+        pipeline.start_processing();
+        pipeline.set_compressor(true, -18.0, 4.0, 0.0, 0.0);
+        let mut output = Vec::new();
+        for start in (0..44032).step_by(512) {
+            let input: Vec<f32> = (start..start + 512).map(|n| sine(n, 440.0, db_to_linear(-6.0))).collect();
+            pipeline.process_block(&input, &mut block);
+            output.extend_from_slice(&block);
+        }
+        let level = dynamics::linear_to_db(peak(&output[22050..]));
+        assert!((level + 15.0).abs() < 1.5, "{}", level);
+        let reduction = pipeline.get_gain_reduction();
+        assert!((reduction.compressor - 9.0).abs() < 0.5 && reduction.gate == 0.0 && reduction.limiter == 0.0, "{:?} {}", reduction, level);
+        
+        // Гейт: тихий шум под порогом глушится, голос над порогом проходит без изменений
+        let mut pipeline = AudioPipeline::new(sample_rate, 512);
+        pipeline.start_processing();
+        pipeline.set_gate(true, -50.0, 10.0, 60.0);
+        let mut quiet = Vec::new();
+        for start in (0..22016).step_by(512) {
+            let input: Vec<f32> = (start..start + 512).map(|n| sine(n, 440.0, db_to_linear(-70.0))).collect();
+            pipeline.process_block(&input, &mut block);
+            quiet.extend_from_slice(&block);
+        }
+        assert!(peak(&quiet[16538..]) < db_to_linear(-125.0));
+        assert!((pipeline.get_gain_reduction().gate - 60.0).abs() < 1.0);
+        let mut loud = Vec::new();
+        let mut input_tail = Vec::new();
+        for start in (22016..44032).step_by(512) {
+            let input: Vec<f32> = (start..start + 512).map(|n| sine(n, 440.0, 0.3)).collect();
+            pipeline.process_block(&input, &mut block);
+            loud.extend_from_slice(&block);
+            input_tail.extend_from_slice(&input);
+        }
+        assert!(loud[11025..].iter().zip(&input_tail[11025..]).all(|(y, x)| (y - x).abs() < 1e-4));
+        
+        // Мягкое ограничение: ниже 0.8 без изменений, выше - монотонно и не выше 1.0
+        assert_eq!(soft_clip(0.5), 0.5);
+        assert_eq!(soft_clip(-0.8), -0.8);
+        assert!(soft_clip(0.9) > 0.85 && soft_clip(0.9) < 0.9);
+        assert!(soft_clip(10.0) <= 1.0 && soft_clip(-10.0) >= -1.0 && soft_clip(10.0) > soft_clip(2.0));
+        
+        // Динамика входит в пресет
+        let mut pipeline = AudioPipeline::new(sample_rate, 512);
+        pipeline.apply_preset_by_name("radio_announcer").unwrap();
+        let preset = pipeline.preset("copy");
+        assert!(preset.compressor_enabled && preset.limiter_enabled && !preset.gate_enabled);
+        assert_eq!(preset.compressor_ratio, 3.0);
+    }
+
+    #[test]
+    fn test_parametric_eq() {
+        use equalizer::log_frequencies;
+        
+        let sample_rate = 44100.0;
+        let db = |gain: f32| 20.0 * gain.log10();
+        
+        // Формы biquad: колокол, полки, режекция и фазовый фильтр
+        let mut filter = BiquadFilter::new();
+        filter.peaking(1000.0, sample_rate, 1.0, 6.0);
+        assert!((db(filter.magnitude(1000.0, sample_rate)) - 6.0).abs() < 0.01);
+        assert!(db(filter.magnitude(50.0, sample_rate)).abs() < 0.1);
+        filter.low_shelf(200.0, sample_rate, 0.707, -9.0);
+        assert!((db(filter.magnitude(20.0, sample_rate)) + 9.0).abs() < 0.2);
+        assert!(db(filter.magnitude(10000.0, sample_rate)).abs() < 0.1);
+        filter.high_shelf(4000.0, sample_rate, 0.707, 12.0);
+        assert!((db(filter.magnitude(18000.0, sample_rate)) - 12.0).abs() < 0.5);
+        assert!(db(filter.magnitude(100.0, sample_rate)).abs() < 0.1);
+        filter.notch(60.0, sample_rate, 4.0);
+        assert!(db(filter.magnitude(60.0, sample_rate)) < -40.0);
+        assert!(db(filter.magnitude(1000.0, sample_rate)).abs() < 0.1);
+        filter.allpass(1000.0, sample_rate, 0.707);
+        assert!(log_frequencies(20.0, 20000.0, 32).iter().all(|&f| db(filter.magnitude(f, sample_rate)).abs() < 1e-3));
+        
+        // По умолчанию срез и эквалайзер выключены: сигнал проходит бит в бит
+        let input: Vec<f32> = (0..4096).map(|n| (n as f32 * 0.37).sin() * 0.5).collect();
+        let mut output = vec![0.0f32; 4096];
+        let mut pipeline = AudioPipeline::new(sample_rate, 4096);
+        pipeline.start_processing();
+        pipeline.process_block(&input, &mut output);
+        assert_eq!(input, output);
+        
+        // Полосы до и после эффекта меняют уровень синуса так, как показывает АЧХ
+        let level = |pipeline: &mut AudioPipeline, frequency: f32| {
+            let mut output = vec![0.0f32; 512];
+            let mut peak = 0.0f32;
+            for start in (0..22016).step_by(512) {
+                let input: Vec<f32> = (start..start + 512)
+                    .map(|n| (n as f32 * std::f32::consts::TAU * frequency / sample_rate).sin() * 0.1)
+                    .collect();
+                pipeline.process_block(&input, &mut output);
+                if start >= 11008 {
+                    peak = output.iter().fold(peak, |peak, x| peak.max(x.abs()));
+                }
+            }
+            db(peak / 0.1)
+        };
+        let boost = EqBand { gain: 6.0, ..EqBand::default() };
+        let cut = EqBand { band_type: EqBandType::HighShelf, frequency: 3000.0, gain: -12.0, ..EqBand::default() };
+        pipeline.set_eq_bands(EqStage::Pre, &[boost]).unwrap();
+        pipeline.set_eq_band(EqStage::Post, 0, cut).unwrap();
+        let response = pipeline.eq_magnitude_response(&[1000.0, 10000.0]);
+        assert!((level(&mut pipeline, 1000.0) - response[0]).abs() < 0.1, "{:?}", response);
+        assert!((level(&mut pipeline, 10000.0) - response[1]).abs() < 0.2, "{:?}", response);
+        assert!(response[1] < -11.0);
+        
+        // Срез низа и верха из AudioParameters теперь применяется
+        pipeline.set_eq_bands(EqStage::Pre, &[]).unwrap();
+        pipeline.set_eq_bands(EqStage::Post, &[]).unwrap();
+        pipeline.set_filters(300.0, 3400.0);
+        assert!(level(&mut pipeline, 1000.0).abs() < 0.5);
+        assert!(level(&mut pipeline, 60.0) < -25.0);
+        let response = pipeline.eq_magnitude_response(&[300.0, 3400.0]);
+        assert!(response.iter().all(|r| (r + 3.0).abs() < 0.3), "{:?}", response);
+
+        // Скачок частоты без сглаживания тоже пересчитывает срез
+        pipeline.set_smoothing(SmoothingConfig::disabled());
+        pipeline.set_filters(1000.0, MAX_CUT_FREQUENCY);
+        assert!(level(&mut pipeline, 8000.0).abs() < 0.5);
+        assert!(level(&mut pipeline, 60.0) < -40.0);
+        pipeline.set_smoothing(SmoothingConfig::default());
+
+        // Полосы входят в пресет, лишние полосы - ошибка без частичного применения
+        pipeline.set_eq_bands(EqStage::Post, &[cut, boost]).unwrap();
+        let preset = pipeline.preset("eq");
+        assert_eq!(preset.post_eq.len(), 2);
+        let mut restored = AudioPipeline::new(sample_rate, 512);
+        restored.apply_preset(&preset).unwrap();
+        assert_eq!(restored.eq_bands(EqStage::Post), pipeline.eq_bands(EqStage::Post));
+        let mut too_many = preset.clone();
+        too_many.pre_eq = vec![EqBand::default(); MAX_EQ_BANDS + 1];
+        too_many.output_gain = 0.5;
+        assert!(restored.apply_preset(&too_many).is_err());
+        assert_eq!(restored.preset("eq").output_gain, preset.output_gain);
+        assert!(restored.set_eq_band(EqStage::Pre, 1, boost).is_err());
+    }
+
+    #[test]
+    fn test_noise_generator() {
+        let mut generator = NoiseGenerator::new();
+        
+        // Тест белого шума
+        generator.noise_type = NoiseType::White;
+        generator.level = 1.0;
+        let white_noise = generator.generate_sample();
+        assert!(white_noise.is_finite());
+        
+        // Тест розового шума
+        generator.noise_type = NoiseType::Pink;
+        let pink_noise = generator.generate_sample();
+        assert!(pink_noise.is_finite());
+        
+        // Тест коричневого шума
+        generator.noise_type = NoiseType::Brown;
+        let brown_noise = generator.generate_sample();
+        assert!(brown_noise.is_finite());
+    }
+
+    #[test]
+    fn test_c_ffi_interface() {
+        unsafe {
+            // Создаем конвейер через C интерфейс
+            let pipeline_ptr = create_pipeline();
+            assert!(!pipeline_ptr.is_null());
+            
+            // Тестируем установку эффекта
+            set_effect(pipeline_ptr, EffectType::Monster as u32);
+            
+            // Тестируем установку шума
+            set_noise(pipeline_ptr, NoiseType::White as u32, 0.1);
+            
+            // Тестируем запуск обработки
+            start_processing(pipeline_ptr);
+            
+            // Тестируем обработку аудио
+            let input = [0.5f32; 10];
+            let mut output = vec![0.0f32; 10];
+            process_audio(pipeline_ptr, input.as_ptr(), output.as_mut_ptr(), 10);
+            
+            // Тестируем остановку обработки
+            stop_processing(pipeline_ptr);
+            
+            // Освобождаем память
+            destroy_pipeline(pipeline_ptr);
+        }
+    }
+
+    #[test]
+    fn test_pitch_shift() {
+        use neural_engine::NeuralConfig;
+        
+        let sample_rate = 44100.0;
+        let mut processor = NeuralVoiceProcessor::new(NeuralConfig::default()).unwrap();
+        processor.add_effect(VoiceEffect::PitchShift(12.0)).unwrap();
+        assert!(processor.latency_samples() > 0);
+        
+        // Секунда синуса 220 Гц блоками по 512 - состояние должно переноситься между блоками
+        let input: Vec<f32> = (0..44100)
+            .map(|i| (std::f32::consts::TAU * 220.0 * i as f32 / sample_rate).sin() * 0.5)
+            .collect();
+        let mut output = Vec::with_capacity(input.len());
+        for block in input.chunks(512) {
+            let result = processor.process(block).unwrap();
+            assert_eq!(result.output.len(), block.len()); // Длительность не меняется
+            output.extend(result.output);
+        }
+        
+        // После задержки выходит тон на октаву выше
+        let settled = &output[processor.latency_samples() + 4096..];
+        let frequency = estimate_frequency(settled, sample_rate);
+        assert!((frequency - 440.0).abs() < 15.0, "частота {:.1} Гц", frequency);
+        assert!(calculate_rms(settled) > 0.2);
+    }
+
+    #[test]
+    fn test_voice_effects_in_pipeline() {
+        let sample_rate = 44100.0;
+        let input: Vec<f32> = (0..44032)
+            .map(|i| (std::f32::consts::TAU * 220.0 * i as f32 / sample_rate).sin() * 0.5)
+            .collect();
+        let run = |pipeline: &mut AudioPipeline| {
+            let mut output = vec![0.0f32; input.len()];
+            for (block, out) in input.chunks(512).zip(output.chunks_mut(512)) {
+                pipeline.process_block(block, out);
+            }
+            output
+        };
+        
+        // Голосовой эффект слышен на любой платформе и без AI эффекта
+        let mut pipeline = AudioPipeline::new(sample_rate, 512);
+        pipeline.start_processing();
+        pipeline.add_voice_effect(VoiceEffect::PitchShift(12.0)).unwrap();
+        assert!(pipeline.latency_samples() > 0);
+        let output = run(&mut pipeline);
+        let settled = &output[pipeline.latency_samples() + 4096..];
+        let frequency = estimate_frequency(settled, sample_rate);
+        assert!((frequency - 440.0).abs() < 15.0, "частота {:.1} Гц", frequency);
+        
+        // С DSP эффектом цепочка стоит перед ним
+        let mut plain = AudioPipeline::new(sample_rate, 512);
+        plain.start_processing();
+        plain.set_effect(EffectType::Radio);
+        let mut voiced = AudioPipeline::new(sample_rate, 512);
+        voiced.start_processing();
+        voiced.set_effect(EffectType::Radio);
+        voiced.add_voice_effect(VoiceEffect::RingModulator { frequency: 30.0, mix: 1.0 }).unwrap();
+        let (plain, voiced) = (run(&mut plain), run(&mut voiced));
+        assert!(plain.iter().zip(&voiced).any(|(a, b)| (a - b).abs() > 0.05));
+        assert!(voiced.iter().all(|x| x.is_finite()));
+    }
+
+    #[test]
+    fn test_formant_shift() {
+        use formant::FormantShifter;
+        
+        let sample_rate = 44100.0;
+        // Гласная: пила 150 Гц через резонатор на 700 Гц
+        let (r, theta) = (0.98f32, std::f32::consts::TAU * 700.0 / sample_rate);
+        let (a1, a2) = (2.0 * r * theta.cos(), -r * r);
+        let (mut y1, mut y2) = (0.0f32, 0.0f32);
+        let input: Vec<f32> = (0..sample_rate as usize)
+            .map(|i| {
+                let saw = (i as f32 * 150.0 / sample_rate).fract() * 2.0 - 1.0;
+                let y = 0.02 * saw + a1 * y1 + a2 * y2;
+                y2 = y1;
+                y1 = y;
+                y
+            })
+            .collect();
+        
+        // Без сдвига - точная реконструкция с задержкой
+        let mut shifter = FormantShifter::new(sample_rate);
+        let latency = shifter.latency();
+        let mut output = vec![0.0f32; input.len()];
+        for (block_in, block_out) in input.chunks(512).zip(output.chunks_mut(512)) {
+            shifter.process(block_in, block_out);
+        }
+        for i in 4096..input.len() {
+            assert!((output[i] - input[i - latency]).abs() < 1e-4);
+        }
+        
+        // Сдвиг вверх: спектр светлеет, основной тон остается 150 Гц
+        let mut shifter = FormantShifter::new(sample_rate);
+        shifter.set_shift(1.0);
+        let mut shifted = vec![0.0f32; input.len()];
+        for (block_in, block_out) in input.chunks(512).zip(shifted.chunks_mut(512)) {
+            shifter.process(block_in, block_out);
+        }
+        let original = &input[8192..];
+        let shifted = &shifted[8192 + latency..];
+        // Яркость: отношение RMS первой разности к RMS сигнала растет с частотой резонанса
+        let brightness = |x: &[f32]| {
+            let diff: Vec<f32> = x.windows(2).map(|w| w[1] - w[0]).collect();
+            calculate_rms(&diff) / calculate_rms(x)
+        };
+        assert!(brightness(shifted) > brightness(original) * 1.2);
+        assert!((autocorrelation_pitch(shifted, sample_rate) - 150.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn test_reverb() {
+        let sample_rate = 44100.0;
+        // Энергия хвоста импульса на отрезке [from, to) секунд
+        let tail_energy = |room_size: f32, damping: f32, from: f32, to: f32| {
+            let mut reverb = Reverb::new(sample_rate);
+            reverb.set_room_size(room_size);
+            reverb.set_damping(damping);
+            reverb.set_mix(1.0, 0.0);
+            let response: Vec<f32> = (0..(sample_rate * to) as usize)
+                .map(|i| reverb.process(if i == 0 { 1.0 } else { 0.0 }))
+                .collect();
+            response[(sample_rate * from) as usize..].iter().map(|x| x * x).sum::<f32>()
+        };
+        
+        // Хвост есть, затухает, и в большом помещении он длиннее
+        assert!(tail_energy(0.5, 0.5, 0.3, 0.6) > 1e-6);
+        assert!(tail_energy(0.5, 0.5, 1.5, 2.0) < tail_energy(0.5, 0.5, 0.3, 0.8));
+        assert!(tail_energy(0.9, 0.5, 1.0, 1.5) > tail_energy(0.2, 0.5, 1.0, 1.5) * 10.0);
+        // Затухание высоких частот укорачивает хвост
+        assert!(tail_energy(0.7, 0.9, 1.0, 1.5) < tail_energy(0.7, 0.0, 1.0, 1.5));
+        
+        // Предзадержка: до нее мокрый сигнал молчит
+        let mut reverb = Reverb::new(sample_rate);
+        reverb.set_pre_delay(0.05);
+        reverb.set_mix(1.0, 0.0);
+        let response: Vec<f32> = (0..4410).map(|i| reverb.process(if i == 0 { 1.0 } else { 0.0 })).collect();
+        assert!(response[..2205].iter().all(|&x| x == 0.0));
+        assert!(response[2205..].iter().any(|&x| x != 0.0));
+        
+        // Ширина 0 дает одинаковые каналы
+        let mut reverb = Reverb::new(sample_rate);
+        reverb.set_width(0.0);
+        let (left, right) = (0..3000).fold((0.0, 0.0), |_, i| reverb.process_stereo(if i < 100 { 0.5 } else { 0.0 }));
+        assert!((left - right).abs() < 1e-6);
+        
+        // Cathedral звучит после окончания входа, размер берется из параметров
+        let tail_after_burst = |size: f32| {
+            let mut pipeline = AudioPipeline::new(sample_rate, 512);
+            pipeline.start_processing();
+            pipeline.set_effect(EffectType::Cathedral);
+            pipeline.set_reverb(size, 0.3);
+            let mut output = vec![0.0f32; 512];
+            let mut energy = 0.0;
+            for block in 0..120 {
+                let input: Vec<f32> = (0..512)
+                    .map(|i| if block < 4 { ((block * 512 + i) as f32 * 0.05).sin() * 0.5 } else { 0.0 })
+                    .collect();
+                pipeline.process_block(&input, &mut output);
+                if block >= 60 {
+                    energy += output.iter().map(|x| x * x).sum::<f32>();
+                }
+            }
+            energy
+        };
+        assert!(tail_after_burst(0.0) > 1e-4);
+        assert!(tail_after_burst(1.0) > tail_after_burst(0.0));
+    }
+
+    #[test]
+    fn test_convolution() {
+        use neural_engine::{NeuralConfig, NeuralVoiceProcessor};
+        
+        // Отклик: затухающий шум длиннее нескольких блоков
+        let mut state = 1u32;
+        let mut noise = || {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            (state >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0
+        };
+        let impulse: Vec<f32> = (0..3000).map(|i| noise() * (-(i as f32) / 600.0).exp()).collect();
+        let impulse = ImpulseResponse::new(impulse, 44100.0).unwrap();
+        let input: Vec<f32> = (0..6000).map(|_| noise() * 0.5).collect();
+        
+        // Блочная свертка совпадает с прямой (с задержкой в один блок)
+        let block_size = 256;
+        let mut convolver = Convolver::new(&impulse, block_size);
+        assert_eq!(convolver.partition_count(), 12);
+        assert_eq!(convolver.latency(), block_size);
+        let mut output = vec![0.0f32; input.len()];
+        for (block_in, block_out) in input.chunks(100).zip(output.chunks_mut(100)) {
+            convolver.process(block_in, block_out);
+        }
+        let h = impulse.samples();
+        for i in (block_size..input.len()).step_by(37) {
+            let n = i - block_size;
+            let expected: f32 = (0..h.len().min(n + 1)).map(|k| h[k] * input[n - k]).sum();
+            assert!((output[i] - expected).abs() < 1e-3, "{}: {} != {}", i, output[i], expected);
+        }
+        
+        // Сухой сигнал задерживается вместе со сверткой
+        let mut convolver = Convolver::new(&impulse, block_size);
+        convolver.set_mix(0.0, 1.0);
+        let mut dry = vec![0.0f32; input.len()];
+        convolver.process(&input, &mut dry);
+        assert_eq!(&dry[block_size..], &input[..input.len() - block_size]);
+        
+        assert!(matches!(ImpulseResponse::new(vec![0.0; 100], 44100.0), Err(ConvolutionError::EmptyImpulse)));
+        
+        // Отклик из WAV: стерео 22.05 кГц приводится к моно 44.1 кГц
+        let path = std::env::temp_dir().join(format!("dsp_core_ir_{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 22050,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..1500 {
+            let sample = if i % 300 == 0 { 0.5 } else { 0.0 };
+            writer.write_sample(sample).unwrap();
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+        let loaded = ImpulseResponse::from_wav(&path, 44100.0).unwrap();
+        // После последнего импульса остается звон фильтра передискретизации (32 сэмпла на 22.05 кГц)
+        assert!((loaded.duration() - 1200.0 / 22050.0).abs() < 0.002, "{}", loaded.duration());
+        
+        // Эффект конвейера: задержка равна размеру блока
+        let mut pipeline = AudioPipeline::new(44100.0, 512);
+        pipeline.start_processing();
+        pipeline.set_effect(EffectType::Convolution);
+        assert_eq!(pipeline.latency_samples(), 0);
+        pipeline.load_impulse_response(&path).unwrap();
+        assert_eq!(pipeline.latency_samples(), 512);
+        let mut impulse_in = vec![0.0f32; 4096];
+        impulse_in[0] = 1.0;
+        let mut response = vec![0.0f32; 4096];
+        for (block_in, block_out) in impulse_in.chunks(512).zip(response.chunks_mut(512)) {
+            pipeline.process_block(block_in, block_out);
+        }
+        let peaks: Vec<usize> = (0..response.len()).filter(|&i| response[i].abs() > 0.2).collect();
+        assert_eq!(peaks.first(), Some(&512));
+        assert!(peaks.contains(&(512 + 600)));
+        
+        // Голосовой эффект загружает отклик при добавлении
+        let mut processor = NeuralVoiceProcessor::new(NeuralConfig::default()).unwrap();
+        processor.add_effect(VoiceEffect::Convolution { impulse_path: path.display().to_string(), wet_level: 1.0 }).unwrap();
+        assert_eq!(processor.latency_samples(), NeuralConfig::default().buffer_size);
+        assert!(processor
+            .add_effect(VoiceEffect::Convolution { impulse_path: "/nonexistent.wav".to_string(), wet_level: 1.0 })
+            .is_err());
+        
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_multichannel() {
+        use neural_engine::{NeuralConfig, NeuralVoiceProcessor};
+        
+        // Раскладка: моно разводится во все каналы, в моно сводится средним
+        let mut stereo = vec![vec![0.0f32; 4]; 2];
+        channels::remix(&[[1.0f32, 2.0, 3.0, 4.0]], &mut stereo, 4);
+        assert_eq!(stereo[0], stereo[1]);
+        let mut mono = [vec![0.0f32; 2]];
+        channels::remix(&[[1.0f32, 3.0], [3.0, 5.0]], &mut mono, 2);
+        assert_eq!(mono[0], vec![2.0, 4.0]);
+        let mut interleaved = vec![0.0f32; 8];
+        channels::interleave(&stereo, &mut interleaved);
+        assert_eq!(&interleaved[..4], &[1.0, 1.0, 2.0, 2.0]);
+        let mut planar = vec![vec![0.0f32; 4]; 2];
+        channels::deinterleave(&interleaved, &mut planar);
+        assert_eq!(planar, stereo);
+        
+        // Моно микрофон в стерео выход: хвост Cathedral расходится по каналам
+        let mut pipeline = AudioPipeline::with_channels(44100.0, 512, 2);
+        assert_eq!(pipeline.channels(), 2);
+        pipeline.start_processing();
+        pipeline.set_effect(EffectType::Cathedral);
+        let mut output = vec![0.0f32; 1024];
+        let mut difference = 0.0;
+        for block in 0..20 {
+            let input: Vec<f32> = (0..512)
+                .map(|i| if block < 2 { ((block * 512 + i) as f32 * 0.05).sin() * 0.5 } else { 0.0 })
+                .collect();
+            pipeline.process_interleaved(&input, 1, &mut output, 2);
+            difference += output.chunks(2).map(|frame| (frame[0] - frame[1]).abs()).sum::<f32>();
+        }
+        assert!(difference > 0.1);
+        
+        // Эффект без стерео части оставляет одинаковые каналы одинаковыми
+        // (новый конвейер: хвост Cathedral доигрывал бы после переключения)
+        let mut pipeline = AudioPipeline::with_channels(44100.0, 512, 2);
+        pipeline.start_processing();
+        pipeline.set_effect(EffectType::Radio);
+        let input: Vec<f32> = (0..1024).map(|i| ((i / 2) as f32 * 0.03).sin() * 0.5).collect();
+        pipeline.process_interleaved(&input, 2, &mut output, 2);
+        assert!(output.chunks(2).all(|frame| frame[0] == frame[1]));
+        
+        // Моно конвейер сводит стерео вход: противофазные каналы дают тишину
+        let mut pipeline = AudioPipeline::new(44100.0, 512);
+        pipeline.start_processing();
+        let left: Vec<f32> = (0..512).map(|i| (i as f32 * 0.03).sin()).collect();
+        let right: Vec<f32> = left.iter().map(|x| -x).collect();
+        let mut out = vec![1.0f32; 512];
+        pipeline.process_planar(&[&left, &right], &mut [&mut out]);
+        assert!(calculate_rms(&out) < 1e-6);
+        
+        // Голосовая реверберация в стерео дает разные каналы, лишние каналы - ошибка
+        let config = NeuralConfig { channels: 2, ..NeuralConfig::default() };
+        let mut processor = NeuralVoiceProcessor::new(config).unwrap();
+        processor.add_effect(VoiceEffect::Reverb { room_size: 0.7, damping: 0.3, wet_level: 0.5 }).unwrap();
+        let burst: Vec<f32> = (0..4096).map(|i| if i < 256 { (i as f32 * 0.1).sin() } else { 0.0 }).collect();
+        let mut voice = vec![burst.clone(), burst];
+        processor.process_planar(&mut voice).unwrap();
+        assert!(voice[0].iter().zip(&voice[1]).any(|(l, r)| (l - r).abs() > 1e-4));
+        assert!(processor.process_planar(&mut vec![vec![0.0; 16]; 3]).is_err());
+    }
+
+    #[test]
+    fn test_reconfigure() {
+        use neural_engine::{NeuralConfig, NeuralVoiceProcessor};
+        
+        let mut pipeline = AudioPipeline::new(44100.0, 512);
+        pipeline.set_effect(EffectType::Monster);
+        let latency_44k = pipeline.latency_samples();
+        
+        // Окна и линии задержки пересчитываются под новую частоту
+        pipeline.reconfigure(96000.0, 256).unwrap();
+        assert_eq!(pipeline.sample_rate(), 96000.0);
+        assert_eq!(pipeline.parameters.sample_rate.load(Ordering::Relaxed), 96000.0);
+        assert_eq!(pipeline.parameters.buffer_size.load(Ordering::Relaxed), 256);
+        let ratio = pipeline.latency_samples() as f32 / latency_44k as f32;
+        assert!((ratio - 96000.0 / 44100.0).abs() < 0.1, "{}", ratio);
+        
+        // Фильтр Underwater режет одну и ту же частоту в герцах
+        pipeline.start_processing();
+        pipeline.set_effect(EffectType::Underwater);
+        let sine: Vec<f32> = (0..9600).map(|i| (2.0 * std::f32::consts::PI * 5000.0 * i as f32 / 96000.0).sin()).collect();
+        let mut output = vec![0.0f32; 9600];
+        for (block_in, block_out) in sine.chunks(256).zip(output.chunks_mut(256)) {
+            pipeline.process_block(block_in, block_out);
+        }
+        assert!(calculate_rms(&output[4800..]) < calculate_rms(&sine) * 0.1);
+        
+        // Неверные параметры отклоняются без изменений
+        assert!(pipeline.set_sample_rate(1000.0).is_err());
+        assert!(pipeline.reconfigure(48000.0, 0).is_err());
+        assert_eq!(pipeline.sample_rate(), 96000.0);
+        
+        // Импульсный отклик переживает смену частоты, свертка делится по новому блоку
+        let impulse = ImpulseResponse::new(vec![1.0, 0.5, 0.25], 96000.0).unwrap();
+        pipeline.set_impulse_response(&impulse);
+        pipeline.set_effect(EffectType::Convolution);
+        pipeline.set_sample_rate(48000.0).unwrap();
+        assert_eq!(pipeline.latency_samples(), 256);
+        
+        // Цепочка голосовых эффектов перестраивается вместе с частотой
+        let mut processor = NeuralVoiceProcessor::new(NeuralConfig::default()).unwrap();
+        processor.add_effect(VoiceEffect::PitchShift(3.0)).unwrap();
+        let latency = processor.latency_samples();
+        processor.reconfigure(88200.0, 1024).unwrap();
+        assert_eq!(processor.config().sample_rate, 88200.0);
+        assert!(processor.latency_samples() > latency * 3 / 2);
+        assert_eq!(processor.process(&[0.0; 1024]).unwrap().output.len(), 1024);
+    }
+
+    #[test]
+    fn test_presets() {
+        use autotune::Scale;
+        use neural_engine::QualityPreset;
+        
+        let preset = Preset {
+            name: "Робот в пещере".to_string(),
+            effect: EffectType::Robot,
+            effect_mix: 0.7,
+            noise_type: NoiseType::Pink,
+            noise_level: 0.1,
+            reverb_size: 0.9,
+            voice_effects: vec![
+                VoiceEffect::PitchShift(-3.0),
+                VoiceEffect::AutoTune { correction: 0.8, speed: 2.0, key: 9, scale: Scale::Custom(0b1001_0010_1001) },
+            ],
+            quality_preset: QualityPreset::High,
+            ..Preset::default()
+        };
+        assert_eq!(Preset::from_json(&preset.to_json().unwrap()).unwrap(), preset);
+        
+        // Отсутствующие поля берутся по умолчанию, неизвестные пропускаются, новая схема отклоняется
+        let partial = Preset::from_json(r#"{"name": "x", "effect": "Monster", "future_field": 42}"#).unwrap();
+        assert_eq!(partial.effect, EffectType::Monster);
+        assert_eq!(partial.version, preset::PRESET_VERSION);
+        assert_eq!(partial.output_gain, 1.0);
+        let newer = format!(r#"{{"version": {}}}"#, preset::PRESET_VERSION + 1);
+        assert!(matches!(Preset::from_json(&newer), Err(PresetError::UnsupportedVersion(_))));
+        assert!(matches!(Preset::from_json("{"), Err(PresetError::Json(_))));
+        
+        // Снимок конвейера совпадает с примененным пресетом (включая цепочку эффектов)
+        let mut pipeline = AudioPipeline::new(44100.0, 512);
+        pipeline.apply_preset(&preset).unwrap();
+        assert_eq!(pipeline.preset(&preset.name), preset);
+        
+        // Слишком длинная цепочка отклоняется целиком
+        let too_long = Preset { voice_effects: vec![VoiceEffect::PitchShift(1.0); 64], ..Preset::default() };
+        assert!(matches!(pipeline.apply_preset(&too_long), Err(PresetError::Apply(_))));
+        assert_eq!(pipeline.preset(&preset.name), preset);
+        
+        // Файл: имя пресета - имя файла
+        let path = std::env::temp_dir().join(format!("dsp_core_preset_{}.json", std::process::id()));
+        pipeline.save_preset(&path).unwrap();
+        let mut restored = AudioPipeline::new(44100.0, 512);
+        restored.load_preset(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(restored.preset(&preset.name), preset);
+        assert!(restored.load_preset(&path).is_err());
+    }
+
+    #[test]
+    fn test_builtin_presets() {
+        let names = preset::list_presets();
+        assert!(names.len() >= 6);
+        let sample_rate = 44100.0;
+        let input: Vec<f32> = (0..66048)
+            .map(|i| (std::f32::consts::TAU * 220.0 * i as f32 / sample_rate).sin() * 0.3)
+            .collect();
+        
+        // Каждый пресет разбирается, называется как в библиотеке, применяется целиком
+        // и звучит так, как обещает: синус 220 Гц выходит с нужной высотой и уровнем
+        for name in &names {
+            let builtin = preset::builtin_preset(name).unwrap();
+            assert_eq!(builtin.name, *name);
+            assert!(!builtin.description.is_empty());
+            // Высота задается либо DSP эффектом, либо цепочкой - не дважды
+            assert!(builtin.voice_effects.is_empty() || builtin.effect == EffectType::None || builtin.effect.is_ai(), "{}", name);
+            
+            let mut pipeline = AudioPipeline::new(sample_rate, 512);
+            pipeline.start_processing();
+            pipeline.apply_preset_by_name(name).unwrap();
+            let applied = pipeline.preset(name);
+            assert_eq!(applied.effect, builtin.effect);
+            assert_eq!(applied.voice_effects, builtin.voice_effects);
+            let mut output = vec![0.0f32; input.len()];
+            for (block, out) in input.chunks(512).zip(output.chunks_mut(512)) {
+                pipeline.process_block(block, out);
+            }
+            assert!(output.iter().all(|x| x.is_finite()), "{}", name);
+            
+            let settled = &output[44100..];
+            let level = calculate_rms(settled) / calculate_rms(&input);
+            let expected_pitch = match *name {
+                "robot" => Some(100.0),                                   // Несущая вокодера
+                "giant" => Some(220.0 * 2f32.powf(-8.0 / 12.0)),
+                "chipmunk" => Some(220.0 * 1.9),
+                "radio_announcer" => Some(220.0 * 2f32.powf(-2.0 / 12.0)),
+                "ghost" => Some(220.0 * 2f32.powf(2.0 / 12.0)),
+                "monster" => Some(110.0),
+                "cave" | "underwater" => Some(220.0),
+                _ => None,
+            };
+            if let Some(expected) = expected_pitch {
+                let pitch = autocorrelation_pitch(settled, sample_rate);
+                assert!((pitch / expected - 1.0).abs() < 0.04, "{}: {:.1} Гц вместо {:.1}", name, pitch, expected);
+            }
+            if *name == "telephone" {
+                assert!(level < 0.5, "{}: уровень {:.2}", name, level); // 220 Гц ниже полосы 300-3400
+            } else {
+                assert!(level > 0.2 && level < 3.0, "{}: уровень {:.2}", name, level);
+            }
+        }
+        
+        assert_eq!(preset::builtin_preset(" Robot ").unwrap().effect, EffectType::Robot);
+        assert!(matches!(AudioPipeline::new(sample_rate, 512).apply_preset_by_name("nope"), Err(PresetError::NotFound(_))));
+        
+        // FFI: запрос длины, затем чтение списка
+        let len = unsafe { list_presets(std::ptr::null_mut(), 0) };
+        let mut buffer = vec![0u8; len + 1];
+        assert_eq!(unsafe { list_presets(buffer.as_mut_ptr(), buffer.len()) }, len);
+        assert_eq!(std::str::from_utf8(&buffer[..len]).unwrap(), names.join("\n"));
+    }
+
+    #[test]
+    fn test_parameter_smoothing() {
+        // Линейный переход приходит к цели ровно за заданное время
+        let mut linear = SmoothedValue::new(SmoothingMode::Linear, 0.01, 1000.0);
+        linear.set_target(0.0);
+        linear.set_target(1.0);
+        let ramp: Vec<f32> = (0..10).map(|_| linear.next_value()).collect();
+        assert!((ramp[0] - 0.1).abs() < 1e-6 && ramp[9] == 1.0 && !linear.is_smoothing());
+        
+        // Однополюсный: 63% пути за постоянную времени, затем доходит до цели
+        let mut one_pole = SmoothedValue::new(SmoothingMode::OnePole, 0.01, 1000.0);
+        one_pole.set_target(100.0);
+        one_pole.set_target(200.0);
+        let value = (0..10).map(|_| one_pole.next_value()).last().unwrap();
+        assert!((value - 163.2).abs() < 0.5, "{}", value);
+        (0..200).for_each(|_| { one_pole.next_value(); });
+        assert_eq!(one_pole.value(), 200.0);
+        
+        // Скачок выходного усиления на постоянном сигнале превращается в плавный переход
+        let mut pipeline = AudioPipeline::new(44100.0, 512);
+        pipeline.start_processing();
+        let input = vec![0.5f32; 512];
+        let mut output = vec![0.0f32; 512];
+        pipeline.process_block(&input, &mut output);
+        assert!((output[0] - 0.5).abs() < 1e-6);
+        
+        pipeline.parameters.output_gain.store(0.0, Ordering::Relaxed);
+        let mut faded = Vec::new();
+        for _ in 0..3 {
+            pipeline.process_block(&input, &mut output);
+            faded.extend_from_slice(&output);
+        }
+        let max_step = faded.windows(2).map(|w| (w[1] - w[0]).abs()).fold(0.0f32, f32::max);
+        assert!(max_step < 0.5 / 800.0, "{}", max_step);
+        assert_eq!(*faded.last().unwrap(), 0.0);
+        
+        // Без сглаживания - прежнее поведение скачком
+        pipeline.set_smoothing(SmoothingConfig::disabled());
+        pipeline.parameters.output_gain.store(1.0, Ordering::Relaxed);
+        pipeline.process_block(&input, &mut output);
+        assert!((output[0] - 0.5).abs() < 1e-6);
+        
+        // Частота полосового фильтра Radio скользит, а не прыгает
+        let mut dsp = DspProcessor::new(44100.0, 44100);
+        let params = AudioParameters::default();
+        dsp.process_effect(0.0, EffectType::Radio, &params);
+        params.bandpass_center.store(3000.0, Ordering::Relaxed);
+        dsp.process_effect(0.0, EffectType::Radio, &params);
+        let center = dsp.smoothed_bandpass_center.value();
+        assert!(center > 1000.0 && center < 1100.0, "{}", center);
+    }
+
+    #[test]
+    fn test_effect_crossfade() {
+        let mut pipeline = AudioPipeline::new(44100.0, 512);
+        pipeline.start_processing();
+        let silence = vec![0.0f32; 512];
+        let mut output = vec![0.0f32; 512];
+        
+        // Эхо пещеры доигрывает после выключения эффекта
+        pipeline.set_effect(EffectType::Cave);
+        pipeline.process_block(&silence, &mut output);
+        let mut impulse = vec![0.0f32; 512];
+        impulse[0] = 1.0;
+        pipeline.process_block(&impulse, &mut output);
+        pipeline.set_effect(EffectType::None);
+        let mut tail = Vec::new();
+        for _ in 0..40 {
+            pipeline.process_block(&silence, &mut output);
+            tail.extend_from_slice(&output);
+        }
+        let echo = (0.3 * 44100.0) as usize - 512;
+        assert!(tail[echo - 100..echo + 100].iter().any(|x| x.abs() > 0.05));
+        
+        // Переход между сдвигом тона и чистым сигналом без скачков (новый конвейер - без эха пещеры)
+        let mut pipeline = AudioPipeline::new(44100.0, 512);
+        pipeline.start_processing();
+        let sine: Vec<f32> = (0..512 * 20).map(|i| (i as f32 * 0.03).sin() * 0.5).collect();
+        let mut faded = vec![0.0f32; sine.len()];
+        pipeline.set_effect(EffectType::HighPitch);
+        for (block, (block_in, block_out)) in sine.chunks(512).zip(faded.chunks_mut(512)).enumerate() {
+            if block == 10 {
+                pipeline.set_effect(EffectType::None);
+            }
+            pipeline.process_block(block_in, block_out);
+        }
+        let max_step = faded[512 * 9..].windows(2).map(|w| (w[1] - w[0]).abs()).fold(0.0f32, f32::max);
+        assert!(max_step < 0.1, "{}", max_step);
+        
+        // Частые переключения: запас процессоров не кончается, хвосты в итоге освобождаются
+        for effect in [EffectType::Cathedral, EffectType::Cave, EffectType::Radio, EffectType::Robot, EffectType::None] {
+            pipeline.set_effect(effect);
+            pipeline.process_block(&sine[..512], &mut output);
+            assert!(output.iter().all(|x| x.is_finite()));
+        }
+        assert!(pipeline.effect_switcher.is_transitioning());
+        for _ in 0..(crossfade::MAX_TAIL_SECONDS * 44100.0 / 512.0) as usize + 10 {
+            pipeline.process_block(&silence, &mut output);
+        }
+        assert!(!pipeline.effect_switcher.is_transitioning());
+        assert_eq!(pipeline.effect_switcher.active(), EffectType::None);
+    }
+
+    #[test]
+    fn test_resampler() {
+        use resample::{Resampler, ResamplerQuality};
+        
+        let tone = |frequency: f32, rate: f32, len: usize| -> Vec<f32> {
+            (0..len).map(|i| (std::f32::consts::TAU * frequency * i as f32 / rate).sin() * 0.5).collect()
+        };
+        
+        // 44.1 -> 48 кГц: длина по отношению частот, синус совпадает с идеальным (без сдвига по времени)
+        let input = tone(1000.0, 44100.0, 4410);
+        let mut resampler = Resampler::new(44100.0, 48000.0);
+        let mut output = Vec::new();
+        for chunk in input.chunks(97) {
+            resampler.process(chunk, &mut output);
+        }
+        resampler.flush(&mut output);
+        assert!((output.len() as i64 - 4800).abs() <= 1, "{}", output.len());
+        let expected = tone(1000.0, 48000.0, 4800);
+        let error = output[100..4700].iter().zip(&expected[100..4700]).map(|(a, b)| (a - b).abs()).fold(0.0f32, f32::max);
+        assert!(error < 1e-3, "{}", error);
+        
+        // Блоками и целиком - одно и то же
+        let mut whole = Vec::new();
+        let mut resampler = Resampler::new(44100.0, 48000.0);
+        resampler.process(&input, &mut whole);
+        resampler.flush(&mut whole);
+        assert_eq!(whole, output);
+        
+        // 48 -> 16 кГц: тон выше новой частоты Найквиста подавляется, а не заворачивается
+        let mut resampler = Resampler::with_quality(48000.0, 16000.0, ResamplerQuality::High);
+        let mut output = Vec::new();
+        resampler.process(&tone(10000.0, 48000.0, 9600), &mut output);
+        assert!(calculate_rms(&output[200..]) < 0.005, "{}", calculate_rms(&output[200..]));
+        
+        // Устройство 48 кГц, обработка 16 кГц: вход возвращается с задержкой перехода
+        let mut pipeline = AudioPipeline::new(16000.0, 160);
+        pipeline.set_device_sample_rate(48000.0).unwrap();
+        assert_eq!(pipeline.device_sample_rate(), 48000.0);
+        pipeline.start_processing();
+        let input = tone(440.0, 48000.0, 9600);
+        let mut output = vec![0.0f32; 9600];
+        for (block_in, block_out) in input.chunks(480).zip(output.chunks_mut(480)) {
+            pipeline.process_block(block_in, block_out);
+        }
+        let latency = pipeline.rate_conversion_latency();
+        assert!(latency > 0 && latency < 480);
+        let error = output[latency + 500..].iter().zip(&input[500..]).map(|(a, b)| (a - b).abs()).fold(0.0f32, f32::max);
+        assert!(error < 0.01, "{}", error);
+    }
+
+    #[test]
+    fn test_autotune() {
+        use autotune::AutoTuner;
+        use pitch_detection::PitchDetector;
+        
+        // Лады: 61 (C#4) не входит в до мажор, ближайшая нота - C4 или D4
+        assert_eq!(Scale::Major.nearest_note(61.4, 0), Some(62));
+        assert_eq!(Scale::Minor.nearest_note(63.8, 0), Some(63));
+        assert_eq!(Scale::Chromatic.nearest_note(61.4, 0), Some(61));
+        assert_eq!(Scale::Custom(0b1001_0001).nearest_note(66.0, 0), Some(67)); // C E G
+        assert_eq!(Scale::Custom(0).nearest_note(60.0, 0), None);
+        
+        let sample_rate = 44100.0;
+        let sine = |frequency: f32| -> Vec<f32> {
+            (0..sample_rate as usize)
+                .map(|i| 0.5 * (std::f32::consts::TAU * frequency * i as f32 / sample_rate).sin())
+                .collect()
+        };
+        
+        // YIN находит тон синуса
+        let mut detector = PitchDetector::new(sample_rate);
+        let estimate = detector.process(&sine(220.0)).unwrap();
+        assert!((estimate.frequency - 220.0).abs() < 1.0);
+        assert!(estimate.confidence > 0.9);
+        
+        // 455 Гц (между A4 и A#4): в до мажоре тянется к A4, в хроматике - к A#4
+        let input = sine(455.0);
+        for (scale, target_note, target_frequency) in [(Scale::Major, 69, 440.0), (Scale::Chromatic, 70, 466.16)] {
+            let mut tuner = AutoTuner::new(sample_rate);
+            tuner.configure(1.0, 10.0, 0, scale);
+            let mut output = vec![0.0f32; input.len()];
+            for (block_in, block_out) in input.chunks(512).zip(output.chunks_mut(512)) {
+                tuner.process(block_in, block_out);
+            }
+            
+            let status = tuner.status();
+            assert_eq!(status.detected_note, Some(70));
+            assert_eq!(status.target_note, Some(target_note));
+            let frequency = estimate_frequency(&output[tuner.latency() + 8192..], sample_rate);
+            assert!((frequency - target_frequency).abs() < 4.0, "{:?}: {} Гц", scale, frequency);
+        }
+    }
+
+    #[test]
+    fn test_pitch_tracking() {
+        let sample_rate = 44100.0;
+        let mut pipeline = AudioPipeline::new(sample_rate, 512);
+        pipeline.start_processing();
+        pipeline.process_block(&[0.3; 512], &mut [0.0; 512]);
+        assert_eq!(pipeline.get_pitch(), None); // Анализ выключен, пока его не включит тюнер
+        pipeline.set_pitch_tracking(true);
+        let monitor = pipeline.pitch_monitor();
+        
+        // A3 чуть выше строя: 222 Гц = +15.6 цента
+        let input: Vec<f32> = (0..sample_rate as usize / 4)
+            .map(|i| (std::f32::consts::TAU * 222.0 * i as f32 / sample_rate).sin() * 0.3)
+            .collect();
+        let mut output = vec![0.0f32; input.len()];
+        for (block_in, block_out) in input.chunks(512).zip(output.chunks_mut(512)) {
+            pipeline.process_block(block_in, block_out);
+        }
+        
+        let pitch = pipeline.get_pitch().unwrap();
+        assert!((pitch.frequency - 222.0).abs() < 1.0);
+        assert!(pitch.confidence > 0.9);
+        assert_eq!(pitch.note(), 57);
+        assert!((pitch.cents() - 15.6).abs() < 8.0);
+        assert_eq!(monitor.load(), Some(pitch));
+        
+        let pipeline_ptr = &mut pipeline as *mut AudioPipeline as *mut c_void;
+        let reading = unsafe { get_pitch(pipeline_ptr) };
+        assert_eq!(reading.note, 57);
+        let mut name = [0u8; 8];
+        let len = unsafe { get_pitch_note_name(pipeline_ptr, name.as_mut_ptr(), name.len()) };
+        assert_eq!(&name[..=len], b"A3\0");
+        let mut short = [0xffu8; 2];
+        assert_eq!(unsafe { get_pitch_note_name(pipeline_ptr, short.as_mut_ptr(), short.len()) }, 2);
+        assert_eq!(short, [0xff; 2]); // Не поместилось - буфер не тронут, как у остальных строк C API
+        
+        // Тишина сбрасывает тон, выключение останавливает анализ
+        let silence = vec![0.0f32; 4096];
+        for block in silence.chunks(512) {
+            pipeline.process_block(block, &mut output[..block.len()]);
+        }
+        assert_eq!(pipeline.get_pitch(), None);
+        pipeline.set_pitch_tracking(false);
+        pipeline.process_block(&input[..512], &mut output[..512]);
+        assert_eq!(pipeline.get_pitch(), None);
+    }
+
+    /// Оценивает основной тон по максимуму автокорреляции (60-500 Гц)
+    fn autocorrelation_pitch(samples: &[f32], sample_rate: f32) -> f32 {
+        let frame = &samples[..4096];
+        let min_lag = (sample_rate / 500.0) as usize;
+        let max_lag = (sample_rate / 60.0) as usize;
+        let best_lag = (min_lag..=max_lag)
+            .max_by(|&a, &b| {
+                let corr = |lag: usize| frame.iter().zip(&frame[lag..]).map(|(x, y)| x * y).sum::<f32>();
+                corr(a).total_cmp(&corr(b))
+            })
+            .unwrap();
+        sample_rate / best_lag as f32
+    }
+
+    /// Оценивает частоту сигнала по переходам через ноль
+    fn estimate_frequency(samples: &[f32], sample_rate: f32) -> f32 {
+        let crossings = samples
+            .windows(2)
+            .filter(|w| w[0] <= 0.0 && w[1] > 0.0)
+            .count();
+        crossings as f32 * sample_rate / samples.len() as f32
+    }
+
+    /// Вычисляет RMS (среднеквадратичное значение) сигнала
+    fn calculate_rms(samples: &[f32]) -> f32 {
+        if samples.is_empty() {
+            return 0.0;
+        }
+        
+        let sum_squares: f32 = samples.iter().map(|&x| x * x).sum();
+        (sum_squares / samples.len() as f32).sqrt()
     }
 }
@@ -0,0 +1,341 @@
+// CLI утилита для офлайн обработки WAV файлов
+use dsp_core::autotune::Scale;
+use dsp_core::modulation::LfoShape;
+use dsp_core::vocoder::CarrierWaveform;
+use dsp_core::neural_engine::VoiceEffect;
+use dsp_core::offline::{process_file_with_options, OfflineOptions, OutputFormat, ProcessingChain};
+use dsp_core::EffectType;
+
+use std::io::Write;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Использование: dsp_core <вход.wav> <выход.wav> [параметры]
+
+Параметры:
+  -e, --effect <имя>     Эффект конвейера: none, monster, high-pitch, cave, radio,
+                         cathedral, underwater, robot, demon, alien, voice-changer,
+                         convolution (нужен --ir), vocoder (несущая из --carrier)
+  -i, --ir <файл.wav>    Импульсный отклик для эффекта convolution
+  -c, --carrier <файл.wav>
+                         Несущая для эффекта vocoder: голос накладывается на музыку или синтезатор
+  -v, --voice <эффект>   Голосовой эффект (можно повторять, применяются по порядку):
+                           pitch:<полутоны>
+                           formant:<сдвиг>
+                           voice:<пол>,<возраст>,<грубость>
+                           harmonics:<обертоны>,<субгармоники>,<искажение>
+                           modulation:<частота вибрато>,<глубина>,<частота тремоло>,<глубина>[,<форма>[,<разнос>]]
+                             форма: sine, triangle, random
+                           reverb:<размер>,<затухание>,<уровень>
+                           chorus:<голоса>,<задержка мс>,<глубина>,<частота>[,<обр. связь>,<разнос>]
+                           flanger:<задержка мс>,<глубина>,<частота>,<обр. связь>,<разнос>
+                           phaser:<звенья>,<глубина>,<частота>,<обр. связь>,<разнос>
+                           ring:<частота>,<микс>
+                           vocoder:<полосы 16-32>,<высота Гц>,<микс>[,<форма>]
+                             форма: saw, pulse, noise
+                           distortion:<драйв>,<тон>,<уровень>
+                           autotune:<коррекция>,<скорость>,<тональность 0-11>[,<лад>]
+                             лад: major, minor, chromatic или маска ступеней (101011010101)
+                           convolution:<отклик.wav>[,<уровень>]
+  -r, --rate <Гц>        Частота дискретизации выходного файла (по умолчанию как у входного)
+  -f, --format <формат>  Формат выхода: same, i16, i24, i32, f32 (по умолчанию same)
+  -b, --block <сэмплы>   Размер блока обработки (по умолчанию 1024)
+  -q, --quiet            Не выводить прогресс
+  -h, --help             Показать эту справку";
+
+struct CliArgs {
+    input: String,
+    output: String,
+    chain: ProcessingChain,
+    options: OfflineOptions,
+    quiet: bool,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let cli = match parse_args(&args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("❌ {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let quiet = cli.quiet;
+    let mut last_percent = u32::MAX;
+    let progress = |fraction: f32| {
+        let percent = (fraction * 100.0) as u32;
+        if !quiet && percent != last_percent {
+            last_percent = percent;
+            eprint!("\r⏳ Обработка: {:3}%", percent);
+            let _ = std::io::stderr().flush();
+        }
+    };
+
+    match process_file_with_options(&cli.input, &cli.output, &cli.chain, &cli.options, progress) {
+        Ok(report) => {
+            if !quiet {
+                eprintln!();
+                println!(
+                    "✅ {} → {}: {} кан., {} → {} Гц, {} → {} кадров за {:.1} мс",
+                    cli.input,
+                    cli.output,
+                    report.channels,
+                    report.input_sample_rate,
+                    report.output_sample_rate,
+                    report.input_frames,
+                    report.output_frames,
+                    report.processing_time_ms
+                );
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("\n❌ {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<CliArgs, String> {
+    let mut positional = Vec::new();
+    let mut effect = None;
+    let mut voice_effects = Vec::new();
+    let mut options = OfflineOptions::default();
+    let mut quiet = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().cloned().ok_or(format!("Для {} нужно значение", name));
+        match arg.as_str() {
+            "-e" | "--effect" => effect = Some(parse_effect(&value(arg)?)?),
+            "-v" | "--voice" => voice_effects.push(parse_voice_effect(&value(arg)?)?),
+            "-r" | "--rate" => {
+                let rate = value(arg)?;
+                let rate = rate.parse::<u32>().ok().filter(|&r| r > 0)
+                    .ok_or(format!("Некорректная частота: {}", rate))?;
+                options.output_sample_rate = Some(rate);
+            }
+            "-f" | "--format" => options.output_format = parse_format(&value(arg)?)?,
+            "-b" | "--block" => {
+                let block = value(arg)?;
+                options.block_size = block.parse::<usize>().ok().filter(|&b| b > 0)
+                    .ok_or(format!("Некорректный размер блока: {}", block))?;
+            }
+            "-i" | "--ir" => options.impulse_response = Some(value(arg)?.into()),
+            "-c" | "--carrier" => options.carrier = Some(value(arg)?.into()),
+            "-q" | "--quiet" => quiet = true,
+            _ if arg.starts_with('-') && arg.len() > 1 => return Err(format!("Неизвестный параметр: {}", arg)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let [input, output] = <[String; 2]>::try_from(positional)
+        .map_err(|_| "Нужно указать входной и выходной файлы".to_string())?;
+
+    let chain = match (effect, voice_effects.is_empty()) {
+        (Some(_), false) => return Err("Нельзя одновременно использовать --effect и --voice".to_string()),
+        (Some(effect), true) => ProcessingChain::Effect(effect),
+        (None, false) => ProcessingChain::VoiceEffects(voice_effects),
+        (None, true) => ProcessingChain::Effect(EffectType::None),
+    };
+
+    Ok(CliArgs { input, output, chain, options, quiet })
+}
+
+fn parse_effect(name: &str) -> Result<EffectType, String> {
+    let effect = match name.to_lowercase().replace('_', "-").as_str() {
+        "none" => EffectType::None,
+        "monster" => EffectType::Monster,
+        "high-pitch" | "highpitch" => EffectType::HighPitch,
+        "cave" => EffectType::Cave,
+        "radio" => EffectType::Radio,
+        "cathedral" => EffectType::Cathedral,
+        "underwater" => EffectType::Underwater,
+        "robot" => EffectType::Robot,
+        "demon" => EffectType::Demon,
+        "alien" => EffectType::Alien,
+        "voice-changer" | "voicechanger" => EffectType::VoiceChanger,
+        "convolution" => EffectType::Convolution,
+        "vocoder" => EffectType::Vocoder,
+        _ => return Err(format!("Неизвестный эффект: {}", name)),
+    };
+    Ok(effect)
+}
+
+fn parse_voice_effect(spec: &str) -> Result<VoiceEffect, String> {
+    let (name, params) = spec.split_once(':').unwrap_or((spec, ""));
+    // Путь к отклику - не число, разбираем свертку отдельно
+    if name == "convolution" {
+        let (path, wet_level) = match params.rsplit_once(',') {
+            Some((path, level)) => {
+                let level = level.trim().parse::<f32>().map_err(|_| format!("Некорректное число '{}' в {}", level, spec))?;
+                (path, level)
+            }
+            None => (params, 1.0),
+        };
+        if path.is_empty() {
+            return Err(format!("Эффект {} ожидает путь к импульсному отклику", name));
+        }
+        return Ok(VoiceEffect::Convolution { impulse_path: path.to_string(), wet_level });
+    }
+    // Нечисловой параметр (лад автотюна, форма LFO, форма несущей) отделяем заранее по позиции
+    let mut params: Vec<&str> = params.split(',').collect();
+    let word_position = match name {
+        "autotune" => 3,
+        "vocoder" => 3,
+        "modulation" => 4,
+        _ => usize::MAX,
+    };
+    let word = (params.len() > word_position).then(|| params.remove(word_position).trim());
+    let values = params
+        .iter()
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| p.trim().parse::<f32>().map_err(|_| format!("Некорректное число '{}' в {}", p, spec)))
+        .collect::<Result<Vec<f32>, String>>()?;
+
+    let expect = |count: usize| {
+        if values.len() == count {
+            Ok(())
+        } else {
+            Err(format!("Эффект {} ожидает {} параметр(ов), получено {}", name, count, values.len()))
+        }
+    };
+
+    let effect = match name {
+        "pitch" => {
+            expect(1)?;
+            VoiceEffect::PitchShift(values[0])
+        }
+        "formant" => {
+            expect(1)?;
+            VoiceEffect::FormantShift(values[0])
+        }
+        "voice" => {
+            expect(3)?;
+            VoiceEffect::VoiceChanger { gender: values[0], age: values[1], roughness: values[2] }
+        }
+        "harmonics" => {
+            expect(3)?;
+            VoiceEffect::Harmonics { overtones: values[0], undertones: values[1], distortion: values[2] }
+        }
+        "modulation" => {
+            if values.len() != 5 {
+                expect(4)?;
+            }
+            VoiceEffect::Modulation {
+                vibrato_rate: values[0],
+                vibrato_depth: values[1],
+                tremolo_rate: values[2],
+                tremolo_depth: values[3],
+                shape: word.map(parse_lfo_shape).transpose()?.unwrap_or_default(),
+                spread: values.get(4).copied().unwrap_or(0.0),
+            }
+        }
+        "reverb" => {
+            expect(3)?;
+            VoiceEffect::Reverb { room_size: values[0], damping: values[1], wet_level: values[2] }
+        }
+        "chorus" => {
+            if values.len() != 6 {
+                expect(4)?;
+            }
+            VoiceEffect::Chorus {
+                voices: values[0] as u32,
+                delay: values[1],
+                depth: values[2],
+                rate: values[3],
+                feedback: values.get(4).copied().unwrap_or(0.0),
+                spread: values.get(5).copied().unwrap_or(0.5),
+            }
+        }
+        "flanger" => {
+            expect(5)?;
+            VoiceEffect::Flanger { delay: values[0], depth: values[1], rate: values[2], feedback: values[3], spread: values[4] }
+        }
+        "phaser" => {
+            expect(5)?;
+            VoiceEffect::Phaser { stages: values[0] as u32, depth: values[1], rate: values[2], feedback: values[3], spread: values[4] }
+        }
+        "ring" => {
+            expect(2)?;
+            VoiceEffect::RingModulator { frequency: values[0], mix: values[1] }
+        }
+        "vocoder" => {
+            expect(3)?;
+            VoiceEffect::Vocoder {
+                bands: values[0] as u32,
+                pitch: values[1],
+                mix: values[2],
+                waveform: word.map(parse_waveform).transpose()?.unwrap_or_default(),
+            }
+        }
+        "distortion" => {
+            expect(3)?;
+            VoiceEffect::Distortion { drive: values[0], tone: values[1], level: values[2] }
+        }
+        "autotune" => {
+            expect(3)?;
+            VoiceEffect::AutoTune {
+                correction: values[0],
+                speed: values[1],
+                key: values[2] as i32,
+                scale: word.map(parse_scale).transpose()?.unwrap_or_default(),
+            }
+        }
+        _ => return Err(format!("Неизвестный голосовой эффект: {}", name)),
+    };
+    Ok(effect)
+}
+
+fn parse_scale(name: &str) -> Result<Scale, String> {
+    let scale = match name {
+        "major" => Scale::Major,
+        "minor" => Scale::Minor,
+        "chromatic" => Scale::Chromatic,
+        mask if mask.len() == 12 && mask.chars().all(|c| c == '0' || c == '1') => {
+            // Первая цифра - тоника, дальше по полутонам вверх
+            let bits = mask.chars().enumerate().filter(|&(_, c)| c == '1').fold(0u16, |acc, (i, _)| acc | (1 << i));
+            Scale::Custom(bits)
+        }
+        _ => return Err(format!("Неизвестный лад: {}", name)),
+    };
+    Ok(scale)
+}
+
+fn parse_lfo_shape(name: &str) -> Result<LfoShape, String> {
+    let shape = match name {
+        "sine" => LfoShape::Sine,
+        "triangle" => LfoShape::Triangle,
+        "random" => LfoShape::Random,
+        _ => return Err(format!("Неизвестная форма LFO: {}", name)),
+    };
+    Ok(shape)
+}
+
+fn parse_waveform(name: &str) -> Result<CarrierWaveform, String> {
+    let waveform = match name {
+        "saw" => CarrierWaveform::Saw,
+        "pulse" => CarrierWaveform::Pulse,
+        "noise" => CarrierWaveform::Noise,
+        _ => return Err(format!("Неизвестная форма несущей: {}", name)),
+    };
+    Ok(waveform)
+}
+
+fn parse_format(name: &str) -> Result<OutputFormat, String> {
+    let format = match name {
+        "same" => OutputFormat::SameAsInput,
+        "i16" => OutputFormat::Int16,
+        "i24" => OutputFormat::Int24,
+        "i32" => OutputFormat::Int32,
+        "f32" => OutputFormat::Float32,
+        _ => return Err(format!("Неизвестный формат: {}", name)),
+    };
+    Ok(format)
+}
@@ -0,0 +1,507 @@
+// Модуляционные эффекты: хорус, флэнжер и вибрато на линиях задержки с дробным чтением,
+// фейзер на цепочке allpass, тремоло
+use std::f32::consts::{FRAC_1_SQRT_2, PI, TAU};
+
+use serde::{Deserialize, Serialize};
+
+use crate::delay::{DelayInterpolation, DelayLine};
+use crate::smoothing::{SmoothedValue, SmoothingMode};
+
+/// Максимальное число голосов хоруса
+pub const MAX_CHORUS_VOICES: usize = 8;
+/// Максимальная центральная задержка хоруса и флэнжера, секунды
+const MAX_CHORUS_DELAY: f32 = 0.1;
+const MAX_FLANGER_DELAY: f32 = 0.02;
+/// Максимальное число allpass звеньев фейзера (два звена - одна вырезка в спектре)
+pub const MAX_PHASER_STAGES: usize = 12;
+/// Фейзер качает частоту звеньев вокруг центра в PHASER_RANGE раз вверх и вниз (200 - 3200 Гц)
+const PHASER_CENTER: f32 = 800.0;
+const PHASER_RANGE: f32 = 4.0;
+/// Время сглаживания задержки и глубины при смене параметров, секунды
+const PARAMETER_GLIDE: f32 = 0.05;
+/// Вибрато с глубиной 1.0 отклоняет высоту на ±1 полутон
+const VIBRATO_SEMITONES: f32 = 1.0;
+/// Максимальный размах задержки вибрато, секунды (на очень медленном LFO ограничивает глубину)
+const MAX_VIBRATO_SWING: f32 = 0.05;
+
+/// Форма LFO
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LfoShape {
+    #[default]
+    Sine,
+    Triangle,
+    /// Новое случайное значение на каждый период с плавным переходом к нему
+    Random,
+}
+
+/// Низкочастотный генератор со значениями -1.0 - 1.0. Фаза продолжается между блоками.
+pub struct Lfo {
+    shape: LfoShape,
+    phase: f32,     // 0.0 - 1.0
+    increment: f32,
+    sample_rate: f32,
+    random_state: u32,
+    random_from: f32,
+    random_to: f32,
+}
+
+impl Lfo {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut lfo = Self {
+            shape: LfoShape::default(),
+            phase: 0.0,
+            increment: 0.0,
+            sample_rate,
+            random_state: 12345,
+            random_from: 0.0,
+            random_to: 0.0,
+        };
+        lfo.random_to = lfo.next_random();
+        lfo
+    }
+
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+    }
+
+    pub fn set_rate(&mut self, rate_hz: f32) {
+        self.increment = rate_hz.clamp(0.0, self.sample_rate * 0.5) / self.sample_rate;
+    }
+
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase.rem_euclid(1.0);
+    }
+
+    /// Наибольшая скорость изменения значения за сэмпл (для пересчета глубины вибрато)
+    pub fn max_slope(&self) -> f32 {
+        let per_period = match self.shape {
+            LfoShape::Sine => TAU,
+            LfoShape::Triangle => 4.0,
+            LfoShape::Random => PI, // Полуволна косинуса от -1 до 1
+        };
+        per_period * self.increment
+    }
+
+    #[inline]
+    pub fn next_value(&mut self) -> f32 {
+        let value = match self.shape {
+            LfoShape::Sine => (self.phase * TAU).sin(),
+            LfoShape::Triangle => 1.0 - 4.0 * ((self.phase + 0.25).fract() - 0.5).abs(),
+            LfoShape::Random => {
+                let blend = 0.5 - 0.5 * (self.phase * PI).cos();
+                self.random_from + (self.random_to - self.random_from) * blend
+            },
+        };
+        self.phase += self.increment;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+            self.random_from = self.random_to;
+            self.random_to = self.next_random();
+        }
+        value
+    }
+
+    fn next_random(&mut self) -> f32 {
+        self.random_state = self.random_state.wrapping_mul(1103515245).wrapping_add(12345);
+        (self.random_state as f32 / u32::MAX as f32) * 2.0 - 1.0
+    }
+}
+
+/// Начальная фаза LFO канала (0.0 - 1.0): `spread` 1.0 ставит соседние каналы в противофазу
+pub fn channel_phase(channel: usize, spread: f32) -> f32 {
+    (channel as f32 * spread.clamp(0.0, 1.0) * 0.5).fract()
+}
+
+/// Линия задержки, время которой качает синусоидальный LFO.
+/// Задержка = центр * (1 + глубина * LFO), центр и глубина меняются плавно.
+struct ModulatedDelay {
+    line: DelayLine,
+    sample_rate: f32,
+    center: SmoothedValue, // Сэмплы
+    depth: SmoothedValue,  // Доля центральной задержки
+    phase: f32,            // 0.0 - 1.0
+    increment: f32,
+    primed: bool,          // До первого сэмпла параметры применяются сразу
+}
+
+impl ModulatedDelay {
+    fn new(max_delay_seconds: f32, sample_rate: f32) -> Self {
+        // Вдвое больше центральной задержки - запас на полную глубину модуляции
+        let max_samples = (max_delay_seconds * sample_rate * 2.0) as usize + 2;
+        Self {
+            line: DelayLine::new(max_samples),
+            sample_rate,
+            center: SmoothedValue::new(SmoothingMode::Linear, PARAMETER_GLIDE, sample_rate),
+            depth: SmoothedValue::new(SmoothingMode::Linear, PARAMETER_GLIDE, sample_rate),
+            phase: 0.0,
+            increment: 0.0,
+            primed: false,
+        }
+    }
+
+    fn set_delay(&mut self, seconds: f32) {
+        let samples = (seconds * self.sample_rate).clamp(1.0, self.line.max_delay() * 0.5);
+        set_parameter(&mut self.center, samples, self.primed);
+    }
+
+    fn set_depth(&mut self, depth: f32) {
+        set_parameter(&mut self.depth, depth.clamp(0.0, 1.0), self.primed);
+    }
+
+    fn set_rate(&mut self, rate_hz: f32) {
+        self.increment = rate_hz.clamp(0.0, self.sample_rate * 0.5) / self.sample_rate;
+    }
+
+    /// Центр и размах задержки на текущем сэмпле
+    #[inline]
+    fn next_sweep(&mut self) -> (f32, f32) {
+        self.primed = true;
+        let center = self.center.next_value();
+        (center, center * self.depth.next_value())
+    }
+
+    /// Чтение с LFO, сдвинутым на `phase_offset` периода (голоса хоруса)
+    #[inline]
+    fn read(&mut self, center: f32, sweep: f32, phase_offset: f32) -> f32 {
+        let lfo = ((self.phase + phase_offset) * TAU).sin();
+        self.line.read(center + sweep * lfo, DelayInterpolation::Cubic)
+    }
+
+    #[inline]
+    fn write(&mut self, sample: f32) {
+        self.line.write(sample);
+        self.phase = (self.phase + self.increment).fract();
+    }
+
+    fn clear(&mut self) {
+        self.line.clear();
+    }
+}
+
+/// Хорус: несколько копий голоса с задержкой 10-100 мс, которую качают LFO с равномерно разнесенными фазами
+pub struct Chorus {
+    delay: ModulatedDelay,
+    voices: usize,
+    feedback: f32,
+}
+
+impl Chorus {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut chorus = Self {
+            delay: ModulatedDelay::new(MAX_CHORUS_DELAY, sample_rate),
+            voices: 3,
+            feedback: 0.0,
+        };
+        chorus.set_delay(20.0);
+        chorus.set_depth(0.3);
+        chorus.set_rate(1.0);
+        chorus
+    }
+
+    /// Число голосов 1 - 8
+    pub fn set_voices(&mut self, voices: u32) {
+        self.voices = (voices as usize).clamp(1, MAX_CHORUS_VOICES);
+    }
+
+    /// Центральная задержка в миллисекундах
+    pub fn set_delay(&mut self, delay_ms: f32) {
+        self.delay.set_delay(delay_ms / 1000.0);
+    }
+
+    /// Глубина 0.0 - 1.0: задержка гуляет на ±половину центральной
+    pub fn set_depth(&mut self, depth: f32) {
+        self.delay.set_depth(depth * 0.5);
+    }
+
+    /// Частота LFO в Гц
+    pub fn set_rate(&mut self, rate_hz: f32) {
+        self.delay.set_rate(rate_hz);
+    }
+
+    /// Обратная связь 0.0 - 0.9
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback.clamp(0.0, 0.9);
+    }
+
+    /// Начальная фаза LFO 0.0 - 1.0 (см. `channel_phase`)
+    pub fn set_phase(&mut self, phase: f32) {
+        self.delay.phase = phase.rem_euclid(1.0);
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let (center, sweep) = self.delay.next_sweep();
+        let voices = self.voices as f32;
+        let mut wet = 0.0;
+        for voice in 0..self.voices {
+            wet += self.delay.read(center, sweep, voice as f32 / voices);
+        }
+        wet /= voices;
+        self.delay.write(input + wet * self.feedback);
+        (input + wet) * FRAC_1_SQRT_2
+    }
+
+    pub fn clear(&mut self) {
+        self.delay.clear();
+    }
+}
+
+/// Флэнжер: одна копия с короткой задержкой (0.5-10 мс) и обратной связью - "реактивный" гребенчатый свип.
+/// Отрицательная обратная связь дает более полый звук.
+pub struct Flanger {
+    delay: ModulatedDelay,
+    feedback: f32,
+}
+
+impl Flanger {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut flanger = Self {
+            delay: ModulatedDelay::new(MAX_FLANGER_DELAY, sample_rate),
+            feedback: 0.0,
+        };
+        flanger.set_delay(3.0);
+        flanger.set_depth(0.8);
+        flanger.set_rate(0.3);
+        flanger
+    }
+
+    /// Центральная задержка в миллисекундах
+    pub fn set_delay(&mut self, delay_ms: f32) {
+        self.delay.set_delay(delay_ms / 1000.0);
+    }
+
+    /// Глубина 0.0 - 1.0: при 1.0 задержка проходит от нуля до двойной центральной
+    pub fn set_depth(&mut self, depth: f32) {
+        self.delay.set_depth(depth);
+    }
+
+    pub fn set_rate(&mut self, rate_hz: f32) {
+        self.delay.set_rate(rate_hz);
+    }
+
+    /// Обратная связь -0.95 - 0.95
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback.clamp(-0.95, 0.95);
+    }
+
+    pub fn set_phase(&mut self, phase: f32) {
+        self.delay.phase = phase.rem_euclid(1.0);
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let (center, sweep) = self.delay.next_sweep();
+        let wet = self.delay.read(center, sweep, 0.0);
+        self.delay.write(input + wet * self.feedback);
+        (input + wet) * 0.5
+    }
+
+    pub fn clear(&mut self) {
+        self.delay.clear();
+    }
+}
+
+/// Фейзер: цепочка allpass фильтров первого порядка, частоту которых качает LFO.
+/// Смесь с исходным сигналом дает вырезки в спектре, которые плывут по частоте.
+pub struct Phaser {
+    sample_rate: f32,
+    stages: usize,
+    states: [(f32, f32); MAX_PHASER_STAGES], // (x[n-1], y[n-1]) каждого звена
+    depth: SmoothedValue,
+    feedback: f32,
+    last_wet: f32,
+    phase: f32,
+    increment: f32,
+    primed: bool,
+}
+
+impl Phaser {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut depth = SmoothedValue::new(SmoothingMode::Linear, PARAMETER_GLIDE, sample_rate);
+        depth.set_target(0.8);
+        let mut phaser = Self {
+            sample_rate,
+            stages: 4,
+            states: [(0.0, 0.0); MAX_PHASER_STAGES],
+            depth,
+            feedback: 0.0,
+            last_wet: 0.0,
+            phase: 0.0,
+            increment: 0.0,
+            primed: false,
+        };
+        phaser.set_rate(0.5);
+        phaser
+    }
+
+    /// Число звеньев: четное, 2 - 12
+    pub fn set_stages(&mut self, stages: u32) {
+        let stages = (stages as usize).clamp(2, MAX_PHASER_STAGES) & !1;
+        if stages != self.stages {
+            self.stages = stages;
+            self.states = [(0.0, 0.0); MAX_PHASER_STAGES];
+        }
+    }
+
+    /// Глубина 0.0 - 1.0: при 1.0 частота звеньев проходит 200 - 3200 Гц
+    pub fn set_depth(&mut self, depth: f32) {
+        set_parameter(&mut self.depth, depth.clamp(0.0, 1.0), self.primed);
+    }
+
+    pub fn set_rate(&mut self, rate_hz: f32) {
+        self.increment = rate_hz.clamp(0.0, self.sample_rate * 0.5) / self.sample_rate;
+    }
+
+    /// Обратная связь -0.95 - 0.95 (делает вырезки острее)
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback.clamp(-0.95, 0.95);
+    }
+
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase.rem_euclid(1.0);
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.primed = true;
+        let lfo = (self.phase * TAU).sin();
+        self.phase = (self.phase + self.increment).fract();
+
+        let frequency = (PHASER_CENTER * PHASER_RANGE.powf(self.depth.next_value() * lfo)).min(self.sample_rate * 0.45);
+        let coefficient = allpass_coefficient(frequency, self.sample_rate);
+
+        let mut signal = input + self.last_wet * self.feedback;
+        for (x1, y1) in self.states.iter_mut().take(self.stages) {
+            let output = coefficient * signal + *x1 - coefficient * *y1;
+            *x1 = signal;
+            *y1 = output;
+            signal = output;
+        }
+        self.last_wet = signal;
+        (input + signal) * 0.5
+    }
+
+    pub fn clear(&mut self) {
+        self.states = [(0.0, 0.0); MAX_PHASER_STAGES];
+        self.last_wet = 0.0;
+    }
+}
+
+/// Вибрато: задержку качает LFO, и скорость чтения линии, а с ней высота тона, колеблется.
+/// Глубина задается отклонением высоты, поэтому на любой частоте LFO звучит одинаково сильно.
+pub struct Vibrato {
+    line: DelayLine,
+    lfo: Lfo,
+    sample_rate: f32,
+    depth: f32,
+    swing: SmoothedValue, // Размах задержки, сэмплы
+    primed: bool,
+}
+
+impl Vibrato {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            line: DelayLine::new((MAX_VIBRATO_SWING * sample_rate * 2.0) as usize + 2),
+            lfo: Lfo::new(sample_rate),
+            sample_rate,
+            depth: 0.0,
+            swing: SmoothedValue::new(SmoothingMode::Linear, PARAMETER_GLIDE, sample_rate),
+            primed: false,
+        }
+    }
+
+    pub fn set_rate(&mut self, rate_hz: f32) {
+        self.lfo.set_rate(rate_hz);
+        self.update_swing();
+    }
+
+    /// Глубина 0.0 - 1.0 (1.0 - ±1 полутон)
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth = depth.clamp(0.0, 1.0);
+        self.update_swing();
+    }
+
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.lfo.set_shape(shape);
+        self.update_swing();
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.primed = true;
+        let swing = self.swing.next_value();
+        // Задержка от 1 до 1 + 2 * размах: в среднем вибрато задерживает на размах
+        let delay = 1.0 + swing * (1.0 + self.lfo.next_value());
+        let output = self.line.read(delay, DelayInterpolation::Cubic);
+        self.line.write(input);
+        output
+    }
+
+    pub fn clear(&mut self) {
+        self.line.clear();
+    }
+
+    /// Размах задержки, при котором скорость чтения отклоняется на нужное число полутонов
+    fn update_swing(&mut self) {
+        let deviation = 2.0f32.powf(self.depth * VIBRATO_SEMITONES / 12.0) - 1.0;
+        let slope = self.lfo.max_slope();
+        let swing = if slope > 0.0 { deviation / slope } else { 0.0 };
+        set_parameter(&mut self.swing, swing.min(MAX_VIBRATO_SWING * self.sample_rate), self.primed);
+    }
+}
+
+/// Тремоло: громкость качается от полной до (1 - глубина)
+pub struct Tremolo {
+    lfo: Lfo,
+    depth: SmoothedValue,
+    primed: bool,
+}
+
+impl Tremolo {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            lfo: Lfo::new(sample_rate),
+            depth: SmoothedValue::new(SmoothingMode::Linear, PARAMETER_GLIDE, sample_rate),
+            primed: false,
+        }
+    }
+
+    pub fn set_rate(&mut self, rate_hz: f32) {
+        self.lfo.set_rate(rate_hz);
+    }
+
+    pub fn set_depth(&mut self, depth: f32) {
+        set_parameter(&mut self.depth, depth.clamp(0.0, 1.0), self.primed);
+    }
+
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.lfo.set_shape(shape);
+    }
+
+    /// Начальная фаза LFO 0.0 - 1.0 (см. `channel_phase`)
+    pub fn set_phase(&mut self, phase: f32) {
+        self.lfo.set_phase(phase);
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.primed = true;
+        let depth = self.depth.next_value();
+        input * (1.0 - depth * 0.5 * (1.0 - self.lfo.next_value()))
+    }
+}
+
+// Helper функции
+
+/// Новое значение параметра: плавно, если звук уже идет, иначе сразу
+#[inline]
+fn set_parameter(value: &mut SmoothedValue, target: f32, primed: bool) {
+    if primed {
+        value.set_target(target);
+    } else {
+        value.reset(target);
+    }
+}
+
+/// Коэффициент allpass первого порядка со сдвигом фазы 90° на частоте `frequency`
+#[inline]
+fn allpass_coefficient(frequency: f32, sample_rate: f32) -> f32 {
+    let t = (PI * frequency / sample_rate).tan();
+    (t - 1.0) / (t + 1.0)
+}
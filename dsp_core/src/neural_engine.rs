@@ -3,14 +3,29 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::collections::VecDeque;
 use std::time::Instant;
 
+use serde::{Deserialize, Serialize};
+
+use crate::autotune::{AutoTuneStatus, AutoTuner, Scale};
+use crate::channels::MAX_CHANNELS;
+use crate::convolution::{Convolver, ImpulseResponse};
+use crate::dynamics::soft_clip;
+use crate::formant::FormantShifter;
+use crate::modulation::{channel_phase, Chorus, Flanger, LfoShape, Phaser, Tremolo, Vibrato};
+use crate::pitch::PitchShifter;
+use crate::reverb::Reverb;
+use crate::vocoder::{CarrierWaveform, RingModulator, Vocoder};
+
 #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
 use metal::*;
 
+/// Сколько последних измерений времени обработки хранится для статистики
+const PERFORMANCE_HISTORY: usize = 100;
+
 /// Типы голосовых эффектов для Neural Engine
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum VoiceEffect {
-    PitchShift(f32),      // Сдвиг высоты тона (-12.0 до +12.0 полутонов)
-    FormantShift(f32),    // Сдвиг формант (-2.0 до +2.0)
+    PitchShift(f32),      // Сдвиг высоты тона (-24.0 до +24.0 полутонов)
+    FormantShift(f32),    // Сдвиг формант (-2.0 до +2.0, ±1 октава огибающей)
     VoiceChanger {        // Изменение голоса
         gender: f32,      // -1.0 (мужской) до +1.0 (женский)
         age: f32,         // -1.0 (молодой) до +1.0 (старый)
@@ -23,9 +38,13 @@ pub enum VoiceEffect {
     },
     Modulation {          // Модуляционные эффекты
         vibrato_rate: f32,    // 0.1 до 20.0 Гц
-        vibrato_depth: f32,   // 0.0 до 1.0
+        vibrato_depth: f32,   // 0.0 до 1.0 (1.0 - ±1 полутон)
         tremolo_rate: f32,    // 0.1 до 20.0 Гц
         tremolo_depth: f32,   // 0.0 до 1.0
+        #[serde(default)]
+        shape: LfoShape,      // Форма LFO: синус, треугольник, случайная
+        #[serde(default)]
+        spread: f32,          // 0.0 до 1.0 - сдвиг фазы тремоло между каналами
     },
     Reverb {              // Пространственные эффекты
         room_size: f32,   // 0.0 до 1.0
@@ -37,6 +56,34 @@ pub enum VoiceEffect {
         delay: f32,       // 10.0 до 100.0 мс
         depth: f32,       // 0.0 до 1.0
         rate: f32,        // 0.1 до 5.0 Гц
+        #[serde(default)]
+        feedback: f32,    // 0.0 до 0.9
+        #[serde(default)]
+        spread: f32,      // 0.0 до 1.0 (1.0 - соседние каналы в противофазе)
+    },
+    Flanger {             // Флэнжер
+        delay: f32,       // 0.5 до 10.0 мс
+        depth: f32,       // 0.0 до 1.0
+        rate: f32,        // 0.05 до 5.0 Гц
+        feedback: f32,    // -0.95 до 0.95
+        spread: f32,      // 0.0 до 1.0
+    },
+    Phaser {              // Фейзер
+        stages: u32,      // 2 до 12 (четное)
+        depth: f32,       // 0.0 до 1.0
+        rate: f32,        // 0.05 до 5.0 Гц
+        feedback: f32,    // -0.95 до 0.95
+        spread: f32,      // 0.0 до 1.0
+    },
+    RingModulator {       // Кольцевой модулятор
+        frequency: f32,   // 10.0 до 2000.0 Гц
+        mix: f32,         // 0.0 до 1.0
+    },
+    Vocoder {             // Канальный вокодер с внутренней несущей
+        bands: u32,       // 16 до 32
+        pitch: f32,       // 40.0 до 1000.0 Гц
+        waveform: CarrierWaveform,
+        mix: f32,         // 0.0 до 1.0
     },
     Distortion {          // Искажения
         drive: f32,       // 0.0 до 1.0
@@ -47,6 +94,11 @@ pub enum VoiceEffect {
         correction: f32,  // 0.0 до 1.0
         speed: f32,       // 0.1 до 10.0
         key: i32,         // 0-11 (C, C#, D, ...)
+        scale: Scale,     // Лад: мажор, минор, хроматический или своя маска
+    },
+    Convolution {         // Свертка с импульсным откликом из WAV файла
+        impulse_path: String,
+        wet_level: f32,   // 0.0 до 1.0
     },
 }
 
@@ -68,12 +120,14 @@ pub struct NeuralConfig {
     pub max_effects: usize,
     pub quality_preset: QualityPreset,
     pub enable_real_time: bool,
+    pub channels: usize,          // Количество каналов (состояние эффектов у каждого свое)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub enum QualityPreset {
     UltraLow,     // Минимальная задержка для живых выступлений
     Low,          // Сбалансированный для стриминга  
+    #[default]
     Medium,       // Хорошее качество для записи
     High,         // Высокое качество студийной записи
     Ultra,        // Максимальное качество для мастеринга
@@ -84,10 +138,8 @@ pub struct NeuralVoiceProcessor {
     config: NeuralConfig,
     is_processing: AtomicBool,
     effects_chain: Vec<VoiceEffect>,
-    
-    // Буферы для обработки
-    input_buffer: VecDeque<f32>,
-    output_buffer: VecDeque<f32>,
+    effect_states: Vec<Vec<EffectState>>, // Состояние каждого эффекта цепочки: [эффект][канал]
+    scratch: Vec<f32>,                    // Копия входа канала для эффектов с отдельным выходом
     
     // Статистика производительности
     processing_times: VecDeque<u64>,
@@ -100,25 +152,22 @@ pub struct NeuralVoiceProcessor {
     metal_queue: Option<CommandQueue>,
     #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
     neural_kernels: Option<NeuralKernels>,
-    
-    // Обработчики эффектов
-    pitch_processor: PitchProcessor,
-    formant_processor: FormantProcessor,
-    modulation_processor: ModulationProcessor,
-    spatial_processor: SpatialProcessor,
 }
 
 impl NeuralVoiceProcessor {
     pub fn new(config: NeuralConfig) -> Result<Self, String> {
         println!("🧠 Инициализация Neural Engine процессора голоса...");
         
+        let mut config = config;
+        config.channels = config.channels.clamp(1, MAX_CHANNELS);
+        
         let processor = Self {
             config: config.clone(),
             is_processing: AtomicBool::new(false),
             effects_chain: Vec::new(),
-            input_buffer: VecDeque::new(),
-            output_buffer: VecDeque::new(),
-            processing_times: VecDeque::new(),
+            effect_states: Vec::new(),
+            scratch: vec![0.0; config.buffer_size],
+            processing_times: VecDeque::with_capacity(PERFORMANCE_HISTORY),
             neural_loads: VecDeque::new(),
             
             #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
@@ -127,11 +176,6 @@ impl NeuralVoiceProcessor {
             metal_queue: None,
             #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
             neural_kernels: None,
-            
-            pitch_processor: PitchProcessor::new(&config)?,
-            formant_processor: FormantProcessor::new(&config)?,
-            modulation_processor: ModulationProcessor::new(&config)?,
-            spatial_processor: SpatialProcessor::new(&config)?,
         };
         
         // Инициализируем Metal для Neural Engine
@@ -167,211 +211,305 @@ impl NeuralVoiceProcessor {
         Ok(())
     }
     
+    /// Перестраивает состояние всех эффектов под новую частоту дискретизации и размер блока.
+    /// Хвосты эффектов сбрасываются; при ошибке процессор остается в прежней конфигурации.
+    pub fn reconfigure(&mut self, sample_rate: f32, buffer_size: usize) -> Result<(), String> {
+        let config = NeuralConfig { sample_rate, buffer_size, ..self.config.clone() };
+        
+        let effect_states = self.effects_chain
+            .iter()
+            .map(|effect| EffectState::for_channels(effect, &config))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        self.effect_states = effect_states;
+        self.scratch = vec![0.0; buffer_size];
+        self.config = config;
+        println!("🔄 Neural Engine перенастроен: {:.0} Гц, блок {}", sample_rate, buffer_size);
+        Ok(())
+    }
+    
+    pub fn config(&self) -> &NeuralConfig {
+        &self.config
+    }
+    
     /// Добавляет эффект в цепочку обработки
     pub fn add_effect(&mut self, effect: VoiceEffect) -> Result<(), String> {
         if self.effects_chain.len() >= self.config.max_effects {
             return Err(format!("Превышено максимальное количество эффектов ({})", self.config.max_effects));
         }
         
+        // Состояние создаем здесь, а не в аудио потоке
+        self.effect_states.push(EffectState::for_channels(&effect, &self.config)?);
         self.effects_chain.push(effect.clone());
         println!("🎛️ Добавлен эффект: {:?}", effect);
         Ok(())
     }
     
+    /// Текущая цепочка эффектов
+    pub fn effects(&self) -> &[VoiceEffect] {
+        &self.effects_chain
+    }
+    
+    /// Заменяет всю цепочку. Состояния создаются заранее: если какой-то эффект
+    /// не удалось создать, прежняя цепочка остается без изменений.
+    pub fn set_effects(&mut self, effects: Vec<VoiceEffect>) -> Result<(), String> {
+        if effects.len() > self.config.max_effects {
+            return Err(format!("Превышено максимальное количество эффектов ({})", self.config.max_effects));
+        }
+        let effect_states = effects
+            .iter()
+            .map(|effect| EffectState::for_channels(effect, &self.config))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        self.effects_chain = effects;
+        self.effect_states = effect_states;
+        println!("🎛️ Цепочка эффектов заменена ({} эффектов)", self.effects_chain.len());
+        Ok(())
+    }
+    
+    /// Режим качества (влияет на оценку нагрузки)
+    pub fn set_quality_preset(&mut self, quality_preset: QualityPreset) {
+        self.config.quality_preset = quality_preset;
+    }
+    
     /// Удаляет эффект из цепочки
     pub fn remove_effect(&mut self, effect: &VoiceEffect) {
-        self.effects_chain.retain(|e| e != effect);
+        for index in (0..self.effects_chain.len()).rev() {
+            if &self.effects_chain[index] == effect {
+                self.effects_chain.remove(index);
+                self.effect_states.remove(index);
+            }
+        }
         println!("🗑️ Удален эффект: {:?}", effect);
     }
     
     /// Очищает все эффекты
     pub fn clear_effects(&mut self) {
         self.effects_chain.clear();
+        self.effect_states.clear();
         println!("🧹 Все эффекты удалены");
     }
     
-    /// Обрабатывает аудио через Neural Engine
+    /// Обрабатывает моно аудио через Neural Engine и собирает статистику блока
+    /// (выделяет память под результат, поэтому не для аудио потока)
     pub fn process(&mut self, input: &[f32]) -> Result<NeuralProcessingResult, String> {
+        let mut channels = [input.to_vec()];
+        self.process_planar(&mut channels)?;
+        let [output] = channels;
+        
+        let effects_applied = self.effects_chain.clone();
+        Ok(NeuralProcessingResult {
+            latency_ns: self.processing_times.back().copied().unwrap_or(0),
+            neural_engine_load: self.calculate_neural_load(&effects_applied),
+            quality_score: self.calculate_quality_score(&output, &effects_applied),
+            output,
+            effects_applied,
+        })
+    }
+    
+    /// Обрабатывает планарные каналы на месте (буферы одной длины, не больше `config.channels`).
+    /// Память не выделяет (кроме первого блока длиннее `config.buffer_size`), поэтому
+    /// вызывается из аудио потока.
+    pub fn process_planar(&mut self, channels: &mut [Vec<f32>]) -> Result<(), String> {
         let start_time = Instant::now();
         
+        if channels.len() > self.config.channels {
+            return Err(format!("Процессор настроен на {} каналов, передано {}", self.config.channels, channels.len()));
+        }
+        
         if !self.is_processing.load(Ordering::Relaxed) {
             self.is_processing.store(true, Ordering::Relaxed);
         }
         
-        // Добавляем входные данные в буфер
-        self.input_buffer.extend(input.iter().copied());
-        
-        let mut output = input.to_vec();
-        let mut applied_effects = Vec::new();
+        let frames = channels.iter().map(|samples| samples.len()).max().unwrap_or(0);
+        if self.scratch.len() < frames {
+            self.scratch.resize(frames, 0.0);
+        }
         
         // Применяем каждый эффект последовательно
-        let effects_chain = self.effects_chain.clone();
-        for effect in &effects_chain {
-            output = self.apply_effect(&output, effect)?;
-            applied_effects.push(effect.clone());
+        let sample_rate = self.config.sample_rate;
+        for (effect, states) in self.effects_chain.iter().zip(self.effect_states.iter_mut()) {
+            match effect {
+                // Реверберация в стерео одна на все каналы - иначе хвосты одинаковые и ширины нет
+                VoiceEffect::Reverb { room_size, damping, wet_level } if channels.len() > 1 => {
+                    Self::apply_shared_reverb(channels, states, *room_size, *damping, *wet_level)?;
+                }
+                _ => {
+                    for (samples, state) in channels.iter_mut().zip(states.iter_mut()) {
+                        Self::apply_effect(samples, &mut self.scratch, effect, state, sample_rate)?;
+                    }
+                }
+            }
         }
         
         // Измеряем время обработки
         let processing_time = start_time.elapsed().as_nanos() as u64;
         self.update_performance_stats(processing_time);
-        
-        // Расчитываем нагрузку на Neural Engine
-        let neural_load = self.calculate_neural_load(&applied_effects);
-        
-        // Оценка качества обработки
-        let quality_score = self.calculate_quality_score(&output, &applied_effects);
-        
-        Ok(NeuralProcessingResult {
-            output,
-            latency_ns: processing_time,
-            neural_engine_load: neural_load,
-            effects_applied: applied_effects,
-            quality_score,
+        Ok(())
+    }
+    
+    /// Задержка цепочки эффектов в сэмплах (ее вносят сдвиги высоты тона и формант)
+    pub fn latency_samples(&self) -> usize {
+        self.effect_states.iter().filter_map(|states| states.first()).map(|state| state.latency()).sum()
+    }
+    
+    /// Состояние первого автотюна в цепочке (найденная и целевая нота для UI)
+    pub fn autotune_status(&self) -> Option<AutoTuneStatus> {
+        self.effect_states.iter().filter_map(|states| states.first()).find_map(|state| match state {
+            EffectState::AutoTune(tuner) => Some(tuner.status()),
+            _ => None,
         })
     }
     
-    /// Применяет конкретный эффект к аудио
-    fn apply_effect(&mut self, input: &[f32], effect: &VoiceEffect) -> Result<Vec<f32>, String> {
+    /// Применяет конкретный эффект к одному каналу на месте.
+    /// `state` - состояние эффекта этого канала, `scratch` - буфер не короче `samples`
+    /// для эффектов с отдельными входом и выходом.
+    fn apply_effect(samples: &mut [f32], scratch: &mut [f32], effect: &VoiceEffect, state: &mut EffectState, sample_rate: f32) -> Result<(), String> {
         match effect {
             VoiceEffect::PitchShift(semitones) => {
-                self.pitch_processor.shift_pitch(input, *semitones)
+                let EffectState::Pitch(shifter) = state else {
+                    return Err("Нет состояния для сдвига высоты тона".to_string());
+                };
+                shifter.set_semitones(*semitones);
+                
+                // Одинаковая длина входа и выхода - меняется только высота, не темп
+                shifter.process(copy_input(scratch, samples), samples);
             }
             VoiceEffect::FormantShift(shift) => {
-                self.formant_processor.shift_formants(input, *shift)
+                let EffectState::Formant(shifter) = state else {
+                    return Err("Нет состояния для сдвига формант".to_string());
+                };
+                shifter.set_shift(*shift);
+                
+                shifter.process(copy_input(scratch, samples), samples);
             }
             VoiceEffect::VoiceChanger { gender, age, roughness } => {
-                self.apply_voice_transformation(input, *gender, *age, *roughness)
+                apply_voice_transformation(samples, sample_rate, *gender, *age, *roughness);
             }
             VoiceEffect::Harmonics { overtones, undertones, distortion } => {
-                self.apply_harmonic_enhancement(input, *overtones, *undertones, *distortion)
+                apply_harmonic_enhancement(samples, sample_rate, *overtones, *undertones, *distortion);
             }
-            VoiceEffect::Modulation { vibrato_rate, vibrato_depth, tremolo_rate, tremolo_depth } => {
-                self.modulation_processor.apply_modulation(input, *vibrato_rate, *vibrato_depth, *tremolo_rate, *tremolo_depth)
+            VoiceEffect::Modulation { vibrato_rate, vibrato_depth, tremolo_rate, tremolo_depth, shape, .. } => {
+                let EffectState::Modulation(vibrato, tremolo) = state else {
+                    return Err("Нет состояния для модуляции".to_string());
+                };
+                vibrato.set_shape(*shape);
+                vibrato.set_rate(*vibrato_rate);
+                vibrato.set_depth(*vibrato_depth);
+                tremolo.set_shape(*shape);
+                tremolo.set_rate(*tremolo_rate);
+                tremolo.set_depth(*tremolo_depth);
+                
+                samples.iter_mut().for_each(|sample| *sample = tremolo.process(vibrato.process(*sample)));
             }
             VoiceEffect::Reverb { room_size, damping, wet_level } => {
-                self.spatial_processor.apply_reverb(input, *room_size, *damping, *wet_level)
+                let EffectState::Reverb(reverb) = state else {
+                    return Err("Нет состояния для реверберации".to_string());
+                };
+                reverb.set_room_size(*room_size);
+                reverb.set_damping(*damping);
+                reverb.set_mix(*wet_level, 1.0 - *wet_level);
+                
+                samples.iter_mut().for_each(|sample| *sample = reverb.process(*sample));
+            }
+            VoiceEffect::Chorus { voices, delay, depth, rate, feedback, .. } => {
+                let EffectState::Chorus(chorus) = state else {
+                    return Err("Нет состояния для хоруса".to_string());
+                };
+                chorus.set_voices(*voices);
+                chorus.set_delay(*delay);
+                chorus.set_depth(*depth);
+                chorus.set_rate(*rate);
+                chorus.set_feedback(*feedback);
+                
+                samples.iter_mut().for_each(|sample| *sample = chorus.process(*sample));
+            }
+            VoiceEffect::Flanger { delay, depth, rate, feedback, .. } => {
+                let EffectState::Flanger(flanger) = state else {
+                    return Err("Нет состояния для флэнжера".to_string());
+                };
+                flanger.set_delay(*delay);
+                flanger.set_depth(*depth);
+                flanger.set_rate(*rate);
+                flanger.set_feedback(*feedback);
+                
+                samples.iter_mut().for_each(|sample| *sample = flanger.process(*sample));
+            }
+            VoiceEffect::Phaser { stages, depth, rate, feedback, .. } => {
+                let EffectState::Phaser(phaser) = state else {
+                    return Err("Нет состояния для фейзера".to_string());
+                };
+                phaser.set_stages(*stages);
+                phaser.set_depth(*depth);
+                phaser.set_rate(*rate);
+                phaser.set_feedback(*feedback);
+                
+                samples.iter_mut().for_each(|sample| *sample = phaser.process(*sample));
             }
-            VoiceEffect::Chorus { voices, delay, depth, rate } => {
-                self.spatial_processor.apply_chorus(input, *voices, *delay, *depth, *rate)
+            VoiceEffect::RingModulator { frequency, mix } => {
+                let EffectState::RingModulator(modulator) = state else {
+                    return Err("Нет состояния для кольцевого модулятора".to_string());
+                };
+                modulator.set_frequency(*frequency);
+                modulator.set_mix(*mix);
+                
+                samples.iter_mut().for_each(|sample| *sample = modulator.process(*sample));
+            }
+            VoiceEffect::Vocoder { bands, pitch, waveform, mix } => {
+                let EffectState::Vocoder(vocoder) = state else {
+                    return Err("Нет состояния для вокодера".to_string());
+                };
+                vocoder.set_bands(*bands as usize);
+                vocoder.set_pitch(*pitch);
+                vocoder.set_waveform(*waveform);
+                vocoder.set_mix(*mix);
+                
+                samples.iter_mut().for_each(|sample| *sample = vocoder.process(*sample));
             }
             VoiceEffect::Distortion { drive, tone, level } => {
-                self.apply_distortion(input, *drive, *tone, *level)
+                apply_distortion(samples, *drive, *tone, *level);
             }
-            VoiceEffect::AutoTune { correction, speed, key } => {
-                self.apply_autotune(input, *correction, *speed, *key)
+            VoiceEffect::Convolution { wet_level, .. } => {
+                let EffectState::Convolution(convolver) = state else {
+                    return Err("Нет состояния для свертки".to_string());
+                };
+                
+                convolver.set_mix(*wet_level, 1.0 - *wet_level);
+                convolver.process(copy_input(scratch, samples), samples);
+            }
+            VoiceEffect::AutoTune { correction, speed, key, scale } => {
+                let EffectState::AutoTune(tuner) = state else {
+                    return Err("Нет состояния для автотюна".to_string());
+                };
+                tuner.configure(*correction, *speed, *key, *scale);
+                
+                tuner.process(copy_input(scratch, samples), samples);
             }
         }
+        Ok(())
     }
     
-    /// Применяет изменение голоса (пол, возраст, грубость)
-    fn apply_voice_transformation(&self, input: &[f32], gender: f32, age: f32, roughness: f32) -> Result<Vec<f32>, String> {
-        let mut output = Vec::with_capacity(input.len());
-        
-        for (i, &sample) in input.iter().enumerate() {
-            let t = i as f32 / self.config.sample_rate;
-            
-            // Изменение пола через формантное смещение
-            let gender_mod = 1.0 + gender * 0.3;
-            
-            // Изменение возраста через высокочастотное ослабление
-            let age_filter = 1.0 - age.abs() * 0.2;
-            
-            // Добавление грубости через нелинейные искажения
-            let roughness_factor = 1.0 + roughness * (t * 100.0).sin() * 0.1;
-            
-            let processed = sample * gender_mod * age_filter * roughness_factor;
-            let clamped = processed.max(-1.0).min(1.0);
-            
-            output.push(clamped);
-        }
-        
-        Ok(output)
-    }
-    
-    /// Применяет гармонические эффекты
-    fn apply_harmonic_enhancement(&self, input: &[f32], overtones: f32, undertones: f32, distortion: f32) -> Result<Vec<f32>, String> {
-        let mut output = Vec::with_capacity(input.len());
-        
-        for (i, &sample) in input.iter().enumerate() {
-            let phase = i as f32 * 2.0 * std::f32::consts::PI / self.config.sample_rate;
-            
-            // Добавляем обертоны (высшие гармоники)
-            let overtone_1 = overtones * 0.3 * (phase * 2.0).sin();
-            let overtone_2 = overtones * 0.2 * (phase * 3.0).sin();
-            let overtone_3 = overtones * 0.1 * (phase * 4.0).sin();
-            
-            // Добавляем субгармоники (низшие частоты)
-            let undertone_1 = undertones * 0.2 * (phase * 0.5).sin();
-            let undertone_2 = undertones * 0.1 * (phase * 0.25).sin();
-            
-            // Нелинейные искажения
-            let distorted = if distortion > 0.0 {
-                sample.signum() * (sample.abs().powf(1.0 - distortion * 0.5))
-            } else {
-                sample
-            };
-            
-            let enhanced = distorted + overtone_1 + overtone_2 + overtone_3 + undertone_1 + undertone_2;
-            let normalized = enhanced * 0.7; // Нормализация
-            
-            output.push(normalized.max(-1.0).min(1.0));
-        }
-        
-        Ok(output)
-    }
-    
-    /// Применяет искажения
-    fn apply_distortion(&self, input: &[f32], drive: f32, tone: f32, level: f32) -> Result<Vec<f32>, String> {
-        let mut output = Vec::with_capacity(input.len());
-        
-        for &sample in input {
-            // Усиление сигнала
-            let driven = sample * (1.0 + drive * 10.0);
-            
-            // Нелинейные искажения
-            let distorted = if driven > 0.0 {
-                1.0 - (-driven).exp()
-            } else {
-                -1.0 + driven.exp()
-            };
-            
-            // Тональная коррекция (простой фильтр)
-            let toned = distorted * (1.0 + tone * 0.5);
-            
-            // Финальный уровень
-            let final_sample = toned * level;
-            
-            output.push(final_sample.max(-1.0).min(1.0));
-        }
-        
-        Ok(output)
-    }
-    
-    /// Применяет автотюн
-    fn apply_autotune(&self, input: &[f32], correction: f32, speed: f32, key: i32) -> Result<Vec<f32>, String> {
-        // Упрощенная реализация автотюна
-        let mut output = Vec::with_capacity(input.len());
-        
-        // Определяем ноты в хроматической гамме (центы от C)
-        let note_frequencies = [261.63, 277.18, 293.66, 311.13, 329.63, 349.23, 369.99, 392.00, 415.30, 440.00, 466.16, 493.88];
-        let target_freq = note_frequencies[key as usize % 12];
-        
-        for (i, &sample) in input.iter().enumerate() {
-            // Простая питч-коррекция (в реальности нужен сложный анализ)
-            let correction_factor = 1.0 + correction * 0.1 * (i as f32 * target_freq / self.config.sample_rate).sin();
-            let corrected = sample * correction_factor;
-            
-            // Сглаживание с предыдущими значениями для скорости коррекции
-            let smoothed = if i > 0 {
-                corrected * speed + output[i-1] * (1.0 - speed)
-            } else {
-                corrected
-            };
-            
-            output.push(smoothed.max(-1.0).min(1.0));
+    /// Общая реверберация для многоканального сигнала: в нее идет моно сумма,
+    /// левый выход добавляется к четным каналам, правый - к нечетным
+    fn apply_shared_reverb(channels: &mut [Vec<f32>], states: &mut [EffectState], room_size: f32, damping: f32, wet_level: f32) -> Result<(), String> {
+        let Some(EffectState::Reverb(reverb)) = states.first_mut() else {
+            return Err("Нет состояния для реверберации".to_string());
+        };
+        reverb.set_room_size(room_size);
+        reverb.set_damping(damping);
+        reverb.set_mix(wet_level, 1.0 - wet_level);
+        let dry = reverb.dry();
+        
+        let frames = channels.iter().map(|samples| samples.len()).min().unwrap_or(0);
+        let count = channels.len() as f32;
+        for i in 0..frames {
+            let mono = channels.iter().map(|samples| samples[i]).sum::<f32>() / count;
+            let (left, right) = reverb.process_wet(mono);
+            for (channel, samples) in channels.iter_mut().enumerate() {
+                samples[i] = samples[i] * dry + if channel % 2 == 0 { left } else { right };
+            }
         }
-        
-        Ok(output)
+        Ok(())
     }
     
     /// Расчитывает нагрузку на Neural Engine
@@ -387,8 +525,13 @@ impl NeuralVoiceProcessor {
                 VoiceEffect::Modulation { .. } => 10.0,
                 VoiceEffect::Reverb { .. } => 35.0,
                 VoiceEffect::Chorus { .. } => 20.0,
+                VoiceEffect::Flanger { .. } => 10.0,
+                VoiceEffect::Phaser { .. } => 10.0,
+                VoiceEffect::RingModulator { .. } => 5.0,
+                VoiceEffect::Vocoder { .. } => 25.0,
                 VoiceEffect::Distortion { .. } => 5.0,
                 VoiceEffect::AutoTune { .. } => 40.0,
+                VoiceEffect::Convolution { .. } => 30.0,
             }
         }).sum();
         
@@ -421,17 +564,16 @@ impl NeuralVoiceProcessor {
         let base_score = 1.0 - clipping_penalty - effects_penalty;
         let dynamic_bonus = (dynamic_range - 1.0).min(0.2);
         
-        (base_score + dynamic_bonus).max(0.0).min(1.0)
+        (base_score + dynamic_bonus).clamp(0.0, 1.0)
     }
     
     /// Обновляет статистику производительности
     fn update_performance_stats(&mut self, processing_time: u64) {
-        self.processing_times.push_back(processing_time);
-        
-        // Сохраняем только последние 100 измерений
-        if self.processing_times.len() > 100 {
+        // Сохраняем только последние измерения (без роста очереди в аудио потоке)
+        if self.processing_times.len() >= PERFORMANCE_HISTORY {
             self.processing_times.pop_front();
         }
+        self.processing_times.push_back(processing_time);
     }
     
     /// Возвращает среднюю задержку в наносекундах
@@ -503,265 +645,175 @@ fn create_dummy_kernel(_device: &Device) -> Result<ComputePipelineState, String>
 
 // Специализированные процессоры эффектов
 
-/// Процессор изменения высоты тона
-struct PitchProcessor {
-    sample_rate: f32,
-    window_size: usize,
-    overlap: usize,
-    fft_buffer: Vec<f32>,
+/// Состояние эффекта цепочки, которое должно сохраняться между блоками
+enum EffectState {
+    Stateless,
+    Pitch(Box<PitchShifter>),
+    Formant(Box<FormantShifter>),
+    AutoTune(Box<AutoTuner>),
+    Reverb(Box<Reverb>),
+    Convolution(Box<Convolver>),
+    Chorus(Box<Chorus>),
+    Flanger(Box<Flanger>),
+    Phaser(Box<Phaser>),
+    Modulation(Box<Vibrato>, Box<Tremolo>),
+    RingModulator(Box<RingModulator>),
+    Vocoder(Box<Vocoder>),
 }
 
-impl PitchProcessor {
-    fn new(config: &NeuralConfig) -> Result<Self, String> {
-        Ok(Self {
-            sample_rate: config.sample_rate,
-            window_size: 2048,
-            overlap: 1024,
-            fft_buffer: vec![0.0; 2048],
-        })
+impl EffectState {
+    /// Состояния эффекта для всех каналов. Импульсный отклик читается один раз.
+    fn for_channels(effect: &VoiceEffect, config: &NeuralConfig) -> Result<Vec<Self>, String> {
+        if let VoiceEffect::Convolution { impulse_path, .. } = effect {
+            let impulse = ImpulseResponse::from_wav(impulse_path, config.sample_rate).map_err(|e| e.to_string())?;
+            let convolver = Convolver::new(&impulse, config.buffer_size);
+            return Ok((0..config.channels).map(|_| EffectState::Convolution(Box::new(convolver.clone()))).collect());
+        }
+        Ok((0..config.channels).map(|channel| Self::new(effect, config.sample_rate, channel)).collect())
     }
     
-    fn shift_pitch(&mut self, input: &[f32], semitones: f32) -> Result<Vec<f32>, String> {
-        // Упрощенная реализация pitch shifting
-        let pitch_ratio = 2.0_f32.powf(semitones / 12.0);
-        let mut output = Vec::with_capacity(input.len());
-        
-        for (i, &sample) in input.iter().enumerate() {
-            // Простая интерполяция для pitch shifting
-            let source_index = i as f32 / pitch_ratio;
-            let index = source_index as usize;
-            
-            if index < input.len() - 1 {
-                let frac = source_index - index as f32;
-                let interpolated = input[index] * (1.0 - frac) + input[index + 1] * frac;
-                output.push(interpolated);
-            } else {
-                output.push(sample);
+    /// Состояние эффекта без внешних ресурсов (свертку с откликом из файла создает `for_channels`).
+    /// `channel` задает начальную фазу LFO модуляционных эффектов (стерео разнос)
+    fn new(effect: &VoiceEffect, sample_rate: f32, channel: usize) -> Self {
+        match effect {
+            VoiceEffect::PitchShift(_) => EffectState::Pitch(Box::new(PitchShifter::new(sample_rate))),
+            VoiceEffect::FormantShift(_) => EffectState::Formant(Box::new(FormantShifter::new(sample_rate))),
+            VoiceEffect::AutoTune { .. } => EffectState::AutoTune(Box::new(AutoTuner::new(sample_rate))),
+            VoiceEffect::Reverb { .. } => EffectState::Reverb(Box::new(Reverb::new(sample_rate))),
+            VoiceEffect::Modulation { spread, .. } => {
+                // Вибрато во всех каналах в фазе (иначе плывет стерео картина), тремоло разносится
+                let mut tremolo = Tremolo::new(sample_rate);
+                tremolo.set_phase(channel_phase(channel, *spread));
+                EffectState::Modulation(Box::new(Vibrato::new(sample_rate)), Box::new(tremolo))
+            }
+            VoiceEffect::RingModulator { .. } => EffectState::RingModulator(Box::new(RingModulator::new(sample_rate))),
+            VoiceEffect::Vocoder { bands, .. } => EffectState::Vocoder(Box::new(Vocoder::new(sample_rate, *bands as usize))),
+            VoiceEffect::Chorus { spread, .. } => {
+                let mut chorus = Chorus::new(sample_rate);
+                chorus.set_phase(channel_phase(channel, *spread));
+                EffectState::Chorus(Box::new(chorus))
+            }
+            VoiceEffect::Flanger { spread, .. } => {
+                let mut flanger = Flanger::new(sample_rate);
+                flanger.set_phase(channel_phase(channel, *spread));
+                EffectState::Flanger(Box::new(flanger))
             }
+            VoiceEffect::Phaser { spread, .. } => {
+                let mut phaser = Phaser::new(sample_rate);
+                phaser.set_phase(channel_phase(channel, *spread));
+                EffectState::Phaser(Box::new(phaser))
+            }
+            _ => EffectState::Stateless,
         }
-        
-        Ok(output)
-    }
-}
-
-/// Процессор изменения формант
-struct FormantProcessor {
-    sample_rate: f32,
-    formant_filters: Vec<FormantFilter>,
-}
-
-impl FormantProcessor {
-    fn new(config: &NeuralConfig) -> Result<Self, String> {
-        // Инициализируем фильтры для основных формант
-        let formant_filters = vec![
-            FormantFilter::new(800.0, 80.0),   // F1
-            FormantFilter::new(1200.0, 90.0),  // F2  
-            FormantFilter::new(2400.0, 120.0), // F3
-        ];
-        
-        Ok(Self {
-            sample_rate: config.sample_rate,
-            formant_filters,
-        })
     }
     
-    fn shift_formants(&mut self, input: &[f32], shift: f32) -> Result<Vec<f32>, String> {
-        let mut output = input.to_vec();
-        
-        // Применяем сдвиг к каждому формантному фильтру
-        for filter in &mut self.formant_filters {
-            output = filter.process(&output, shift)?;
+    fn latency(&self) -> usize {
+        match self {
+            EffectState::Stateless
+            | EffectState::Reverb(_)
+            | EffectState::Chorus(_)
+            | EffectState::Flanger(_)
+            | EffectState::Phaser(_)
+            | EffectState::Modulation(..)
+            | EffectState::RingModulator(_)
+            | EffectState::Vocoder(_) => 0,
+            EffectState::Pitch(shifter) => shifter.latency(),
+            EffectState::Formant(shifter) => shifter.latency(),
+            EffectState::AutoTune(tuner) => tuner.latency(),
+            EffectState::Convolution(convolver) => convolver.latency(),
         }
-        
-        Ok(output)
     }
 }
 
-/// Формантный фильтр
-struct FormantFilter {
-    center_freq: f32,
-    bandwidth: f32,
-    state: [f32; 2],
-}
+// Реализации по умолчанию
 
-impl FormantFilter {
-    fn new(center_freq: f32, bandwidth: f32) -> Self {
+impl Default for NeuralConfig {
+    fn default() -> Self {
         Self {
-            center_freq,
-            bandwidth,
-            state: [0.0; 2],
-        }
-    }
-    
-    fn process(&mut self, input: &[f32], shift: f32) -> Result<Vec<f32>, String> {
-        // Простая реализация формантного фильтра
-        let shifted_freq = self.center_freq * (1.0 + shift * 0.5);
-        let omega = 2.0 * std::f32::consts::PI * shifted_freq / 44100.0;
-        let cos_omega = omega.cos();
-        let sin_omega = omega.sin();
-        let alpha = sin_omega / (2.0 * self.bandwidth / shifted_freq);
-        
-        // Коэффициенты IIR фильтра
-        let b0 = alpha;
-        let b1 = 0.0;
-        let b2 = -alpha;
-        let a0 = 1.0 + alpha;
-        let a1 = -2.0 * cos_omega;
-        let a2 = 1.0 - alpha;
-        
-        let mut output = Vec::with_capacity(input.len());
-        
-        for &sample in input {
-            let y = (b0 * sample + b1 * self.state[0] + b2 * self.state[1]
-                   - a1 * self.state[0] - a2 * self.state[1]) / a0;
-            
-            // Обновляем состояние
-            self.state[1] = self.state[0];
-            self.state[0] = y;
-            
-            output.push(y);
+            sample_rate: 44100.0,
+            buffer_size: 512,
+            max_effects: 8,
+            quality_preset: QualityPreset::Medium,
+            enable_real_time: true,
+            channels: 1,
         }
-        
-        Ok(output)
     }
 }
 
-/// Процессор модуляционных эффектов
-struct ModulationProcessor {
-    sample_rate: f32,
-    vibrato_phase: f32,
-    tremolo_phase: f32,
-}
-
-impl ModulationProcessor {
-    fn new(config: &NeuralConfig) -> Result<Self, String> {
-        Ok(Self {
-            sample_rate: config.sample_rate,
-            vibrato_phase: 0.0,
-            tremolo_phase: 0.0,
-        })
-    }
-    
-    fn apply_modulation(&mut self, input: &[f32], vibrato_rate: f32, vibrato_depth: f32, 
-                       tremolo_rate: f32, tremolo_depth: f32) -> Result<Vec<f32>, String> {
-        let mut output = Vec::with_capacity(input.len());
-        
-        for (_i, &sample) in input.iter().enumerate() {
-            // Vibrato (частотная модуляция)
-            let _vibrato_offset = vibrato_depth * (self.vibrato_phase).sin();
-            self.vibrato_phase += 2.0 * std::f32::consts::PI * vibrato_rate / self.sample_rate;
-            
-            // Tremolo (амплитудная модуляция)  
-            let tremolo_gain = 1.0 + tremolo_depth * (self.tremolo_phase).sin();
-            self.tremolo_phase += 2.0 * std::f32::consts::PI * tremolo_rate / self.sample_rate;
-            
-            // Применяем модуляции
-            let modulated = sample * tremolo_gain;
-            output.push(modulated);
-        }
-        
-        Ok(output)
+impl Default for VoiceEffect {
+    fn default() -> Self {
+        VoiceEffect::PitchShift(0.0)
     }
 }
 
-/// Процессор пространственных эффектов
-struct SpatialProcessor {
-    sample_rate: f32,
-    reverb_buffer: VecDeque<f32>,
-    chorus_buffers: Vec<VecDeque<f32>>,
-    chorus_phases: Vec<f32>,
+
+// Helper функции
+
+/// Копирует вход канала в рабочий буфер для эффектов с отдельными входом и выходом
+fn copy_input<'a>(scratch: &'a mut [f32], samples: &[f32]) -> &'a [f32] {
+    let input = &mut scratch[..samples.len()];
+    input.copy_from_slice(samples);
+    input
 }
 
-impl SpatialProcessor {
-    fn new(config: &NeuralConfig) -> Result<Self, String> {
-        let reverb_size = (config.sample_rate * 2.0) as usize; // 2 секунды reverb
-        
-        Ok(Self {
-            sample_rate: config.sample_rate,
-            reverb_buffer: VecDeque::with_capacity(reverb_size),
-            chorus_buffers: vec![VecDeque::new(); 8], // До 8 голосов
-            chorus_phases: vec![0.0; 8],
-        })
-    }
-    
-    fn apply_reverb(&mut self, input: &[f32], room_size: f32, damping: f32, wet_level: f32) -> Result<Vec<f32>, String> {
-        let delay_samples = (room_size * self.sample_rate * 0.1) as usize;
-        let mut output = Vec::with_capacity(input.len());
+/// Применяет изменение голоса (пол, возраст, грубость)
+fn apply_voice_transformation(samples: &mut [f32], sample_rate: f32, gender: f32, age: f32, roughness: f32) {
+    for (i, sample) in samples.iter_mut().enumerate() {
+        let t = i as f32 / sample_rate;
         
-        for &sample in input {
-            // Добавляем сэмпл в reverb буфер
-            self.reverb_buffer.push_back(sample);
-            
-            // Получаем задержанный сигнал
-            let delayed = if self.reverb_buffer.len() > delay_samples {
-                self.reverb_buffer.pop_front().unwrap_or(0.0)
-            } else {
-                0.0
-            };
-            
-            // Применяем damping (затухание)
-            let damped = delayed * (1.0 - damping);
-            
-            // Смешиваем сухой и мокрый сигналы
-            let mixed = sample * (1.0 - wet_level) + damped * wet_level;
-            output.push(mixed);
-        }
+        // Изменение пола через формантное смещение
+        let gender_mod = 1.0 + gender * 0.3;
         
-        Ok(output)
-    }
-    
-    fn apply_chorus(&mut self, input: &[f32], voices: u32, delay: f32, depth: f32, rate: f32) -> Result<Vec<f32>, String> {
-        let delay_samples = (delay * self.sample_rate / 1000.0) as usize;
-        let mut output = Vec::with_capacity(input.len());
+        // Изменение возраста через высокочастотное ослабление
+        let age_filter = 1.0 - age.abs() * 0.2;
         
-        for (_i, &sample) in input.iter().enumerate() {
-            let mut chorus_sum = sample; // Начинаем с оригинального сигнала
-            
-            // Применяем каждый голос хоруса
-            for voice in 0..voices.min(8) {
-                let voice_idx = voice as usize;
-                
-                // Модулируем задержку
-                let lfo = (self.chorus_phases[voice_idx]).sin();
-                let modulated_delay = delay_samples as f32 + depth * lfo * delay_samples as f32 * 0.5;
-                
-                // Обновляем фазу LFO
-                self.chorus_phases[voice_idx] += 2.0 * std::f32::consts::PI * rate / self.sample_rate;
-                
-                // Добавляем сэмпл в буфер голоса
-                if self.chorus_buffers[voice_idx].len() > modulated_delay as usize {
-                    if let Some(delayed_sample) = self.chorus_buffers[voice_idx].get(0) {
-                        chorus_sum += delayed_sample * 0.3; // Смешиваем с меньшей амплитудой
-                    }
-                    self.chorus_buffers[voice_idx].pop_front();
-                }
-                
-                self.chorus_buffers[voice_idx].push_back(sample);
-            }
-            
-            output.push(chorus_sum / (voices as f32 + 1.0)); // Нормализация
-        }
+        // Добавление грубости через нелинейные искажения
+        let roughness_factor = 1.0 + roughness * (t * 100.0).sin() * 0.1;
         
-        Ok(output)
+        *sample = soft_clip(*sample * gender_mod * age_filter * roughness_factor);
     }
 }
 
-// Реализации по умолчанию
-
-impl Default for NeuralConfig {
-    fn default() -> Self {
-        Self {
-            sample_rate: 44100.0,
-            buffer_size: 512,
-            max_effects: 8,
-            quality_preset: QualityPreset::Medium,
-            enable_real_time: true,
-        }
+/// Применяет гармонические эффекты
+fn apply_harmonic_enhancement(samples: &mut [f32], sample_rate: f32, overtones: f32, undertones: f32, distortion: f32) {
+    for (i, sample) in samples.iter_mut().enumerate() {
+        let phase = i as f32 * 2.0 * std::f32::consts::PI / sample_rate;
+        
+        // Добавляем обертоны (высшие гармоники)
+        let overtone_1 = overtones * 0.3 * (phase * 2.0).sin();
+        let overtone_2 = overtones * 0.2 * (phase * 3.0).sin();
+        let overtone_3 = overtones * 0.1 * (phase * 4.0).sin();
+        
+        // Добавляем субгармоники (низшие частоты)
+        let undertone_1 = undertones * 0.2 * (phase * 0.5).sin();
+        let undertone_2 = undertones * 0.1 * (phase * 0.25).sin();
+        
+        // Нелинейные искажения
+        let distorted = if distortion > 0.0 {
+            sample.signum() * (sample.abs().powf(1.0 - distortion * 0.5))
+        } else {
+            *sample
+        };
+        
+        let enhanced = distorted + overtone_1 + overtone_2 + overtone_3 + undertone_1 + undertone_2;
+        *sample = soft_clip(enhanced * 0.7); // Нормализация
     }
 }
 
-impl Default for VoiceEffect {
-    fn default() -> Self {
-        VoiceEffect::PitchShift(0.0)
+/// Применяет искажения
+fn apply_distortion(samples: &mut [f32], drive: f32, tone: f32, level: f32) {
+    for sample in samples.iter_mut() {
+        // Усиление сигнала
+        let driven = *sample * (1.0 + drive * 10.0);
+        
+        // Нелинейные искажения
+        let distorted = if driven > 0.0 {
+            1.0 - (-driven).exp()
+        } else {
+            -1.0 + driven.exp()
+        };
+        
+        // Тональная коррекция (простой фильтр) и финальный уровень
+        *sample = soft_clip(distorted * (1.0 + tone * 0.5) * level);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,422 @@
+// Офлайн обработка WAV файлов: применяет эффект или цепочку голосовых эффектов к записи
+use crate::channels::{self, MAX_CHANNELS};
+use crate::convolution::ImpulseResponse;
+use crate::resample::{Resampler, ResamplerQuality};
+use crate::neural_engine::{NeuralConfig, NeuralVoiceProcessor, VoiceEffect};
+use crate::{AudioPipeline, EffectType};
+
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Размер блока по умолчанию для офлайн обработки
+const DEFAULT_BLOCK_SIZE: usize = 1024;
+
+/// Что применять к записи
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcessingChain {
+    Effect(EffectType),               // Эффект конвейера (как в реальном времени)
+    VoiceEffects(Vec<VoiceEffect>),   // Цепочка эффектов Neural Engine процессора
+}
+
+/// Формат сэмплов выходного файла
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    SameAsInput,
+    Int16,
+    Int24,
+    Int32,
+    Float32,
+}
+
+/// Параметры офлайн обработки
+#[derive(Debug, Clone)]
+pub struct OfflineOptions {
+    pub block_size: usize,
+    pub output_sample_rate: Option<u32>, // None - как у входного файла
+    pub output_format: OutputFormat,
+    pub impulse_response: Option<PathBuf>, // Импульсный отклик для EffectType::Convolution
+    pub carrier: Option<PathBuf>,          // Несущая (сайдчейн) для EffectType::Vocoder
+}
+
+/// Итоги обработки файла
+#[derive(Debug, Clone)]
+pub struct OfflineReport {
+    pub channels: u16,
+    pub input_sample_rate: u32,
+    pub output_sample_rate: u32,
+    pub input_frames: u64,
+    pub output_frames: u64,
+    pub processing_time_ms: f32,
+}
+
+#[derive(Debug)]
+pub enum OfflineError {
+    Wav(String),
+    UnsupportedFormat(String),
+    Processing(String),
+}
+
+impl std::fmt::Display for OfflineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OfflineError::Wav(msg) => write!(f, "Ошибка WAV: {}", msg),
+            OfflineError::UnsupportedFormat(msg) => write!(f, "Неподдерживаемый формат: {}", msg),
+            OfflineError::Processing(msg) => write!(f, "Ошибка обработки: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for OfflineError {}
+
+impl From<hound::Error> for OfflineError {
+    fn from(e: hound::Error) -> Self {
+        OfflineError::Wav(e.to_string())
+    }
+}
+
+/// Обрабатывает WAV файл цепочкой эффектов и записывает результат
+pub fn process_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    chain: &ProcessingChain,
+) -> Result<OfflineReport, OfflineError> {
+    process_file_with_options(input, output, chain, &OfflineOptions::default(), |_| {})
+}
+
+/// Обрабатывает WAV файл с заданными параметрами, сообщая прогресс (0.0 - 1.0)
+pub fn process_file_with_options<P, Q, F>(
+    input: P,
+    output: Q,
+    chain: &ProcessingChain,
+    options: &OfflineOptions,
+    progress: F,
+) -> Result<OfflineReport, OfflineError>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    F: FnMut(f32),
+{
+    let reader = WavReader::open(input.as_ref())?;
+    let input_spec = reader.spec();
+    let output_spec = output_spec(&input_spec, options)?;
+    let writer = WavWriter::create(output.as_ref(), output_spec)?;
+
+    process_stream(reader, writer, chain, options, progress)
+}
+
+/// Обрабатывает поток WAV данных (файл, память и т.п.)
+pub fn process_stream<R, W, F>(
+    mut reader: WavReader<R>,
+    mut writer: WavWriter<W>,
+    chain: &ProcessingChain,
+    options: &OfflineOptions,
+    mut progress: F,
+) -> Result<OfflineReport, OfflineError>
+where
+    R: Read,
+    W: Write + Seek,
+    F: FnMut(f32),
+{
+    let start_time = Instant::now();
+    let input_spec = reader.spec();
+    let output_spec = writer.spec();
+    let channels = input_spec.channels as usize;
+    let block_size = options.block_size.max(1);
+    let total_frames = reader.duration() as u64;
+
+    if output_spec.channels != input_spec.channels {
+        return Err(OfflineError::UnsupportedFormat(
+            "количество каналов на входе и выходе должно совпадать".to_string(),
+        ));
+    }
+    if channels > MAX_CHANNELS {
+        return Err(OfflineError::UnsupportedFormat(format!(
+            "{} каналов (максимум {})",
+            channels, MAX_CHANNELS
+        )));
+    }
+
+    // Эффекты считают задержки и фильтры от частоты исходного файла,
+    // а к выходной частоте приводим уже обработанный сигнал
+    let sample_rate = input_spec.sample_rate as f32;
+    let impulse = match (chain, &options.impulse_response) {
+        (ProcessingChain::Effect(EffectType::Convolution), Some(path)) => Some(
+            ImpulseResponse::from_wav(path, sample_rate).map_err(|e| OfflineError::Processing(e.to_string()))?,
+        ),
+        (ProcessingChain::Effect(EffectType::Convolution), None) => {
+            return Err(OfflineError::Processing("для свертки нужен импульсный отклик".to_string()));
+        }
+        _ => None,
+    };
+    // Несущая вокодера читается целиком и дополняется тишиной до конца обработки
+    let mut carrier = match (chain, &options.carrier) {
+        (ProcessingChain::Effect(_), Some(path)) => Some(read_carrier(path, sample_rate)?),
+        (ProcessingChain::VoiceEffects(_), Some(_)) => {
+            return Err(OfflineError::Processing("несущая работает только с эффектом конвейера".to_string()));
+        }
+        _ => None,
+    };
+    // Все каналы обрабатываются вместе, чтобы стерео эффекты (реверберация, хорус) давали ширину
+    let mut processor = FileProcessor::new(chain, sample_rate, block_size, channels, impulse.as_ref())?;
+    let mut resamplers: Vec<Resampler> = (0..channels)
+        .map(|_| Resampler::with_quality(input_spec.sample_rate as f32, output_spec.sample_rate as f32, ResamplerQuality::High))
+        .collect();
+
+    let mut interleaved = vec![0.0f32; block_size * channels];
+    let mut planar_in = vec![vec![0.0f32; block_size]; channels];
+    let mut planar_out = vec![vec![0.0f32; block_size]; channels];
+    let mut resampled: Vec<Vec<f32>> = vec![Vec::new(); channels];
+    let mut input_frames = 0u64;
+    let mut output_frames = 0u64;
+
+    // Задержку обработки (например, сдвига высоты тона) компенсируем:
+    // отбрасываем ее в начале и досчитываем столько же тишины в конце
+    let latency = processor.latency();
+    let mut skip = latency;
+    let mut tail = latency;
+    if let Some(ref mut carrier) = carrier {
+        carrier.resize(carrier.len().max(total_frames as usize + latency), 0.0);
+    }
+    let mut carrier_position = 0;
+
+    progress(0.0);
+    loop {
+        let read = read_samples(&mut reader, &mut interleaved)?;
+        let mut frames = read / channels;
+        if frames > 0 {
+            input_frames += frames as u64;
+        } else if tail > 0 {
+            frames = tail.min(block_size);
+            tail -= frames;
+            interleaved[..frames * channels].fill(0.0);
+        } else {
+            // Конец записи: досчитываем то, что придерживает фильтр передискретизации
+            for (resampler, samples) in resamplers.iter_mut().zip(&mut resampled) {
+                samples.clear();
+                resampler.flush(samples);
+            }
+            output_frames += write_frames(&mut writer, &output_spec, &resampled)? as u64;
+            break;
+        }
+
+        channels::resize_planar(&mut planar_in, channels, frames);
+        channels::resize_planar(&mut planar_out, channels, frames);
+        channels::deinterleave(&interleaved[..frames * channels], &mut planar_in);
+        let carrier_block = carrier.as_ref().map(|carrier| {
+            let end = (carrier_position + frames).min(carrier.len());
+            &carrier[carrier_position.min(end)..end]
+        });
+        carrier_position += frames;
+        processor.process(&planar_in, carrier_block, &mut planar_out)?;
+
+        let skipped = skip.min(frames);
+        skip -= skipped;
+        for channel in 0..channels {
+            resampled[channel].clear();
+            resamplers[channel].process(&planar_out[channel][skipped..frames], &mut resampled[channel]);
+        }
+
+        output_frames += write_frames(&mut writer, &output_spec, &resampled)? as u64;
+        if total_frames > 0 {
+            progress((input_frames as f32 / total_frames as f32).min(1.0));
+        }
+    }
+
+    writer.finalize()?;
+    progress(1.0);
+
+    Ok(OfflineReport {
+        channels: input_spec.channels,
+        input_sample_rate: input_spec.sample_rate,
+        output_sample_rate: output_spec.sample_rate,
+        input_frames,
+        output_frames,
+        processing_time_ms: start_time.elapsed().as_secs_f32() * 1000.0,
+    })
+}
+
+impl Default for OfflineOptions {
+    fn default() -> Self {
+        Self {
+            block_size: DEFAULT_BLOCK_SIZE,
+            output_sample_rate: None,
+            output_format: OutputFormat::SameAsInput,
+            impulse_response: None,
+            carrier: None,
+        }
+    }
+}
+
+/// Обработчик всех каналов файла
+enum FileProcessor {
+    Pipeline(Box<AudioPipeline>),
+    Voice(Box<NeuralVoiceProcessor>),
+}
+
+impl FileProcessor {
+    fn new(
+        chain: &ProcessingChain,
+        sample_rate: f32,
+        block_size: usize,
+        channels: usize,
+        impulse: Option<&ImpulseResponse>,
+    ) -> Result<Self, OfflineError> {
+        match chain {
+            ProcessingChain::Effect(effect) => {
+                let mut pipeline = AudioPipeline::with_channels(sample_rate, block_size, channels);
+                if let Some(impulse) = impulse {
+                    pipeline.set_impulse_response(impulse);
+                }
+                pipeline.set_effect(*effect);
+                pipeline.start_processing();
+                Ok(FileProcessor::Pipeline(Box::new(pipeline)))
+            }
+            ProcessingChain::VoiceEffects(effects) => {
+                let config = NeuralConfig {
+                    sample_rate,
+                    buffer_size: block_size,
+                    max_effects: effects.len().max(1),
+                    channels,
+                    ..NeuralConfig::default()
+                };
+                let mut processor = NeuralVoiceProcessor::new(config).map_err(OfflineError::Processing)?;
+                for effect in effects {
+                    processor.add_effect(effect.clone()).map_err(OfflineError::Processing)?;
+                }
+                Ok(FileProcessor::Voice(Box::new(processor)))
+            }
+        }
+    }
+
+    /// Задержка обработки в сэмплах
+    fn latency(&self) -> usize {
+        match self {
+            FileProcessor::Pipeline(pipeline) => pipeline.latency_samples(),
+            FileProcessor::Voice(processor) => processor.latency_samples(),
+        }
+    }
+
+    /// `carrier` - моно несущая вокодера на этот блок (только для конвейера)
+    fn process(&mut self, input: &[Vec<f32>], carrier: Option<&[f32]>, output: &mut [Vec<f32>]) -> Result<(), OfflineError> {
+        match self {
+            FileProcessor::Pipeline(pipeline) => match carrier {
+                Some(carrier) => pipeline.process_planar_with_sidechain(input, &[carrier], output),
+                None => pipeline.process_planar(input, output),
+            },
+            FileProcessor::Voice(processor) => {
+                output.clone_from_slice(input);
+                processor.process_planar(output).map_err(OfflineError::Processing)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Helper функции
+
+/// Читает несущую из WAV: каналы сводятся в моно, частота приводится к `sample_rate`
+fn read_carrier(path: &Path, sample_rate: f32) -> Result<Vec<f32>, OfflineError> {
+    let mut reader = WavReader::open(path)?;
+    let spec = reader.spec();
+    let channels = spec.channels.max(1) as usize;
+    let mut interleaved = vec![0.0f32; reader.len() as usize];
+    let read = read_samples(&mut reader, &mut interleaved)?;
+    let mono: Vec<f32> = interleaved[..read]
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+
+    let mut resampler = Resampler::with_quality(spec.sample_rate as f32, sample_rate, ResamplerQuality::High);
+    let mut carrier = Vec::with_capacity((mono.len() as f64 * resampler.ratio()) as usize + 1);
+    resampler.process(&mono, &mut carrier);
+    resampler.flush(&mut carrier);
+    Ok(carrier)
+}
+
+/// Формат выходного файла с учетом параметров
+fn output_spec(input: &WavSpec, options: &OfflineOptions) -> Result<WavSpec, OfflineError> {
+    let (bits_per_sample, sample_format) = match options.output_format {
+        OutputFormat::SameAsInput => (input.bits_per_sample, input.sample_format),
+        OutputFormat::Int16 => (16, SampleFormat::Int),
+        OutputFormat::Int24 => (24, SampleFormat::Int),
+        OutputFormat::Int32 => (32, SampleFormat::Int),
+        OutputFormat::Float32 => (32, SampleFormat::Float),
+    };
+
+    let spec = WavSpec {
+        channels: input.channels,
+        sample_rate: options.output_sample_rate.unwrap_or(input.sample_rate),
+        bits_per_sample,
+        sample_format,
+    };
+    validate_spec(&spec)?;
+    Ok(spec)
+}
+
+/// Проверяет, что формат поддерживается
+fn validate_spec(spec: &WavSpec) -> Result<(), OfflineError> {
+    let supported = match spec.sample_format {
+        SampleFormat::Int => matches!(spec.bits_per_sample, 8 | 16 | 24 | 32),
+        SampleFormat::Float => spec.bits_per_sample == 32,
+    };
+    if !supported {
+        return Err(OfflineError::UnsupportedFormat(format!(
+            "{} бит {:?}",
+            spec.bits_per_sample, spec.sample_format
+        )));
+    }
+    if spec.channels == 0 || spec.sample_rate == 0 {
+        return Err(OfflineError::UnsupportedFormat("пустой формат".to_string()));
+    }
+    Ok(())
+}
+
+/// Читает до `buffer.len()` сэмплов (чередующихся по каналам), нормализуя их в [-1.0, 1.0].
+/// Возвращает количество прочитанных сэмплов (0 - конец файла).
+pub(crate) fn read_samples<R: Read>(reader: &mut WavReader<R>, buffer: &mut [f32]) -> Result<usize, hound::Error> {
+    let spec = reader.spec();
+    let mut read = 0;
+
+    match spec.sample_format {
+        SampleFormat::Float => {
+            for sample in reader.samples::<f32>().take(buffer.len()) {
+                buffer[read] = sample?;
+                read += 1;
+            }
+        }
+        SampleFormat::Int => {
+            let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+            for sample in reader.samples::<i32>().take(buffer.len()) {
+                buffer[read] = sample? as f32 * scale;
+                read += 1;
+            }
+        }
+    }
+
+    Ok(read)
+}
+
+/// Записывает планарные каналы чередующимися кадрами; возвращает количество кадров
+fn write_frames<W: Write + Seek>(writer: &mut WavWriter<W>, spec: &WavSpec, channels: &[Vec<f32>]) -> Result<usize, hound::Error> {
+    let frames = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+    for frame in 0..frames {
+        for channel_samples in channels {
+            write_sample(writer, spec, channel_samples[frame])?;
+        }
+    }
+    Ok(frames)
+}
+
+/// Записывает нормализованный сэмпл в формате выходного файла
+fn write_sample<W: Write + Seek>(writer: &mut WavWriter<W>, spec: &WavSpec, sample: f32) -> Result<(), hound::Error> {
+    match spec.sample_format {
+        SampleFormat::Float => writer.write_sample(sample),
+        SampleFormat::Int => {
+            let max = ((1u64 << (spec.bits_per_sample - 1)) - 1) as f32;
+            let value = (sample.clamp(-1.0, 1.0) * max).round() as i32;
+            writer.write_sample(value)
+        }
+    }
+}
@@ -0,0 +1,105 @@
+// Потоковый сдвиг высоты тона на базе signalsmith-stretch
+use signalsmith_stretch::Stretch;
+
+/// Максимальный сдвиг высоты тона в полутонах (в обе стороны)
+pub const MAX_SHIFT_SEMITONES: f32 = 24.0;
+
+/// Размер внутреннего блока для посэмпловой обработки
+const SAMPLE_BLOCK_SIZE: usize = 128;
+
+/// Потоковый сдвиг высоты тона с сохранением длительности.
+///
+/// Состояние анализатора переносится между вызовами, поэтому на границах блоков нет щелчков.
+/// Один экземпляр обрабатывает один поток: либо блоками через `process`,
+/// либо по сэмплу через `process_sample`, но не вперемешку.
+pub struct PitchShifter {
+    stretch: Stretch,
+    semitones: f32,
+    ratio: f32,
+    // Буферы для посэмпловой обработки
+    sample_input: Vec<f32>,
+    sample_output: Vec<f32>,
+    sample_position: usize,
+}
+
+impl PitchShifter {
+    pub fn new(sample_rate: f32) -> Self {
+        // Окно ~120 мс с шагом в четверть окна (как в пресете signalsmith по умолчанию)
+        Self::with_window(sample_rate, 0.12)
+    }
+
+    /// Сдвиг с заданной длиной окна в секундах: короче окно - меньше задержка, но хуже низкие голоса
+    pub fn with_window(sample_rate: f32, window_seconds: f32) -> Self {
+        let window_size = ((sample_rate.max(1000.0) * window_seconds) as usize).max(64);
+        let interval = window_size / 4;
+
+        Self {
+            stretch: Stretch::new(1, window_size, interval),
+            semitones: 0.0,
+            ratio: 1.0,
+            sample_input: vec![0.0; SAMPLE_BLOCK_SIZE],
+            sample_output: vec![0.0; SAMPLE_BLOCK_SIZE],
+            sample_position: 0,
+        }
+    }
+
+    /// Устанавливает сдвиг в полутонах (ограничивается ±24)
+    pub fn set_semitones(&mut self, semitones: f32) {
+        let semitones = semitones.clamp(-MAX_SHIFT_SEMITONES, MAX_SHIFT_SEMITONES);
+        if semitones != self.semitones {
+            self.stretch.set_transpose_factor_semitones(semitones, None);
+            self.semitones = semitones;
+            self.ratio = 2.0_f32.powf(semitones / 12.0);
+        }
+    }
+
+    /// Устанавливает сдвиг как отношение частот (2.0 - октава вверх, 0.5 - октава вниз).
+    /// Дешево вызывать на каждом сэмпле: пересчет только при изменении значения.
+    pub fn set_ratio(&mut self, ratio: f32) {
+        if ratio > 0.0 && ratio != self.ratio {
+            self.set_semitones(12.0 * ratio.log2());
+            self.ratio = ratio;
+        }
+    }
+
+    pub fn semitones(&self) -> f32 {
+        self.semitones
+    }
+
+    /// Обрабатывает блок; выход той же длины, что и вход
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        self.stretch.process(input, output);
+    }
+
+    /// Обрабатывает один сэмпл (с дополнительной задержкой во внутренний блок)
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        self.sample_input[self.sample_position] = input;
+        let output = self.sample_output[self.sample_position];
+
+        self.sample_position += 1;
+        if self.sample_position == SAMPLE_BLOCK_SIZE {
+            self.stretch.process(&self.sample_input, &mut self.sample_output);
+            self.sample_position = 0;
+        }
+
+        output
+    }
+
+    /// Задержка блочной обработки в сэмплах
+    pub fn latency(&self) -> usize {
+        self.stretch.input_latency() + self.stretch.output_latency()
+    }
+
+    /// Задержка посэмпловой обработки в сэмплах
+    pub fn sample_latency(&self) -> usize {
+        self.latency() + SAMPLE_BLOCK_SIZE
+    }
+
+    /// Сбрасывает накопленное состояние (например, при смене источника)
+    pub fn reset(&mut self) {
+        self.stretch.reset();
+        self.sample_input.fill(0.0);
+        self.sample_output.fill(0.0);
+        self.sample_position = 0;
+    }
+}
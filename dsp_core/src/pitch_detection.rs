@@ -0,0 +1,287 @@
+// Определение основного тона (алгоритм YIN) и перевод частоты в ноты
+use atomic_float::AtomicF32;
+use rustfft::num_complex::Complex32;
+use rustfft::{Fft, FftPlanner};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// Нижняя граница поиска основного тона, Гц
+pub const MIN_FREQUENCY: f32 = 60.0;
+/// Верхняя граница поиска основного тона, Гц
+pub const MAX_FREQUENCY: f32 = 1000.0;
+
+/// Порог нормированной разностной функции YIN (меньше - строже)
+const YIN_THRESHOLD: f32 = 0.15;
+/// Минимальная уверенность, при которой кадр считается тональным
+const MIN_CONFIDENCE: f32 = 0.6;
+/// Энергия окна (средний квадрат), ниже которой сигнал считается тишиной
+const SILENCE_POWER: f32 = 1e-7;
+
+const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+/// Результат анализа основного тона
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PitchEstimate {
+    pub frequency: f32,  // Гц
+    pub confidence: f32, // 0.0 до 1.0 (1 - минимум нормированной разностной функции)
+}
+
+impl PitchEstimate {
+    /// Ближайшая нота MIDI
+    pub fn note(&self) -> i32 {
+        frequency_to_midi(self.frequency).round() as i32
+    }
+
+    /// Отклонение от ближайшей ноты в центах (-50 до +50)
+    pub fn cents(&self) -> f32 {
+        (frequency_to_midi(self.frequency) - self.note() as f32) * 100.0
+    }
+
+    /// Название ближайшей ноты ("A4")
+    pub fn note_name(&self) -> String {
+        note_name(self.note())
+    }
+}
+
+/// Показания тюнера для C API: частота 0.0 и нота -1, если тон не найден
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PitchReading {
+    pub frequency: f32,
+    pub confidence: f32,
+    pub note: i32,
+    pub cents: f32,
+}
+
+impl Default for PitchReading {
+    fn default() -> Self {
+        Self { frequency: 0.0, confidence: 0.0, note: -1, cents: 0.0 }
+    }
+}
+
+impl From<Option<PitchEstimate>> for PitchReading {
+    fn from(estimate: Option<PitchEstimate>) -> Self {
+        match estimate {
+            Some(estimate) => Self {
+                frequency: estimate.frequency,
+                confidence: estimate.confidence,
+                note: estimate.note(),
+                cents: estimate.cents(),
+            },
+            None => Self::default(),
+        }
+    }
+}
+
+/// Последняя оценка тона, которую аудио поток публикует для UI.
+/// Читается без блокировки конвейера, поэтому тюнер не мешает обработке.
+#[derive(Debug, Default)]
+pub struct PitchMonitor {
+    frequency: AtomicF32,
+    confidence: AtomicF32,
+}
+
+impl PitchMonitor {
+    pub fn publish(&self, estimate: Option<PitchEstimate>) {
+        let (frequency, confidence) = estimate.map_or((0.0, 0.0), |e| (e.frequency, e.confidence));
+        self.confidence.store(confidence, Ordering::Relaxed);
+        self.frequency.store(frequency, Ordering::Relaxed);
+    }
+
+    pub fn load(&self) -> Option<PitchEstimate> {
+        let frequency = self.frequency.load(Ordering::Relaxed);
+        if frequency <= 0.0 {
+            return None;
+        }
+        Some(PitchEstimate { frequency, confidence: self.confidence.load(Ordering::Relaxed) })
+    }
+}
+
+/// Потоковый детектор основного тона YIN.
+///
+/// Хранит последние `window + max_lag` сэмплов и пересчитывает оценку по последнему окну, когда
+/// с прошлого анализа пришло не меньше `hop` сэмплов (не чаще раза за вызов `process`).
+/// Разностная функция считается через взаимную корреляцию на БПФ, O(n log n) вместо O(max_lag·window).
+/// Память выделяется только в конструкторе, поэтому детектор можно вызывать из аудио потока.
+pub struct PitchDetector {
+    sample_rate: f32,
+    window: usize,
+    min_lag: usize,
+    max_lag: usize,
+    hop: usize,
+    history: Vec<f32>,
+    difference: Vec<f32>,
+
+    // Взаимная корреляция окна с историей через БПФ
+    fft: Arc<dyn Fft<f32>>,
+    ifft: Arc<dyn Fft<f32>>,
+    window_spectrum: Vec<Complex32>,
+    history_spectrum: Vec<Complex32>,
+    fft_scratch: Vec<Complex32>,
+    samples_since_update: usize,
+    last_estimate: Option<PitchEstimate>,
+}
+
+impl PitchDetector {
+    pub fn new(sample_rate: f32) -> Self {
+        let sample_rate = sample_rate.max(8000.0);
+        let max_lag = (sample_rate / MIN_FREQUENCY).ceil() as usize;
+        let min_lag = ((sample_rate / MAX_FREQUENCY).floor() as usize).max(2);
+        // Окно интегрирования покрывает хотя бы один период самой низкой ноты
+        let window = max_lag;
+        // Оценка обновляется не чаще раза в 10 мс
+        let hop = ((sample_rate * 0.01) as usize).max(1);
+        // Корреляция без заворота: сдвиг окна до max_lag укладывается в историю
+        let fft_size = (window + max_lag).next_power_of_two();
+
+        let mut planner = FftPlanner::new();
+        let fft = planner.plan_fft_forward(fft_size);
+        let ifft = planner.plan_fft_inverse(fft_size);
+        let scratch_len = fft.get_inplace_scratch_len().max(ifft.get_inplace_scratch_len());
+
+        Self {
+            sample_rate,
+            window,
+            min_lag,
+            max_lag,
+            hop,
+            history: vec![0.0; window + max_lag],
+            difference: vec![0.0; max_lag + 1],
+            fft,
+            ifft,
+            window_spectrum: vec![Complex32::default(); fft_size],
+            history_spectrum: vec![Complex32::default(); fft_size],
+            fft_scratch: vec![Complex32::default(); scratch_len],
+            samples_since_update: 0,
+            last_estimate: None,
+        }
+    }
+
+    /// Добавляет блок и возвращает актуальную оценку (None - тишина или нетональный звук)
+    pub fn process(&mut self, input: &[f32]) -> Option<PitchEstimate> {
+        let len = self.history.len();
+        if input.len() >= len {
+            self.history.copy_from_slice(&input[input.len() - len..]);
+        } else {
+            self.history.copy_within(input.len().., 0);
+            self.history[len - input.len()..].copy_from_slice(input);
+        }
+
+        self.samples_since_update += input.len();
+        if self.samples_since_update >= self.hop {
+            self.samples_since_update = 0;
+            self.last_estimate = self.analyze();
+        }
+        self.last_estimate
+    }
+
+    /// Последняя оценка без подачи новых данных
+    pub fn estimate(&self) -> Option<PitchEstimate> {
+        self.last_estimate
+    }
+
+    /// Время накопления полного окна анализа в сэмплах
+    pub fn latency(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Сбрасывает историю сигнала
+    pub fn reset(&mut self) {
+        self.history.fill(0.0);
+        self.samples_since_update = 0;
+        self.last_estimate = None;
+    }
+
+    fn analyze(&mut self) -> Option<PitchEstimate> {
+        let window = &self.history[..self.window];
+        let energy = window.iter().map(|x| x * x).sum::<f32>();
+        if energy / (self.window as f32) < SILENCE_POWER {
+            return None;
+        }
+
+        // r(lag) = Σ x[j]·x[j + lag] по окну: обратное БПФ от conj(X_окна)·X_истории
+        for (i, (bin, history_bin)) in self.window_spectrum.iter_mut().zip(self.history_spectrum.iter_mut()).enumerate() {
+            *bin = Complex32::new(if i < self.window { self.history[i] } else { 0.0 }, 0.0);
+            *history_bin = Complex32::new(self.history.get(i).copied().unwrap_or(0.0), 0.0);
+        }
+        self.fft.process_with_scratch(&mut self.window_spectrum, &mut self.fft_scratch);
+        self.fft.process_with_scratch(&mut self.history_spectrum, &mut self.fft_scratch);
+        for (history_bin, bin) in self.history_spectrum.iter_mut().zip(&self.window_spectrum) {
+            *history_bin *= bin.conj();
+        }
+        self.ifft.process_with_scratch(&mut self.history_spectrum, &mut self.fft_scratch);
+        let scale = 1.0 / self.history_spectrum.len() as f32;
+
+        // Шаги 2-3 YIN: разностная функция d(lag) = E(0) + E(lag) - 2·r(lag), где E(lag) - энергия
+        // сдвинутого окна (скользящая сумма), и ее кумулятивная нормировка
+        self.difference[0] = 1.0;
+        let mut shifted_energy = energy;
+        let mut running_sum = 0.0;
+        for lag in 1..=self.max_lag {
+            let (leaving, entering) = (self.history[lag - 1], self.history[lag + self.window - 1]);
+            shifted_energy += entering * entering - leaving * leaving;
+            let correlation = self.history_spectrum[lag].re * scale;
+            let d = (energy + shifted_energy - 2.0 * correlation).max(0.0);
+            running_sum += d;
+            self.difference[lag] = if running_sum > 0.0 { d * lag as f32 / running_sum } else { 1.0 };
+        }
+
+        // Шаг 4: первый минимум ниже порога, иначе глобальный минимум
+        let mut best = None;
+        let mut lag = self.min_lag;
+        while lag < self.max_lag {
+            if self.difference[lag] < YIN_THRESHOLD {
+                while lag + 1 < self.max_lag && self.difference[lag + 1] < self.difference[lag] {
+                    lag += 1;
+                }
+                best = Some(lag);
+                break;
+            }
+            lag += 1;
+        }
+        let lag = best.unwrap_or_else(|| {
+            (self.min_lag..self.max_lag)
+                .min_by(|&a, &b| self.difference[a].total_cmp(&self.difference[b]))
+                .unwrap_or(self.min_lag)
+        });
+
+        let confidence = (1.0 - self.difference[lag]).clamp(0.0, 1.0);
+        if confidence < MIN_CONFIDENCE {
+            return None;
+        }
+
+        // Шаг 5: параболическая интерполяция минимума
+        let refined = parabolic_minimum(&self.difference, lag);
+        Some(PitchEstimate { frequency: self.sample_rate / refined, confidence })
+    }
+}
+
+/// Частота в номер ноты MIDI (дробный; 69.0 = A4 = 440 Гц)
+pub fn frequency_to_midi(frequency: f32) -> f32 {
+    69.0 + 12.0 * (frequency / 440.0).log2()
+}
+
+/// Номер ноты MIDI в частоту
+pub fn midi_to_frequency(note: f32) -> f32 {
+    440.0 * 2.0_f32.powf((note - 69.0) / 12.0)
+}
+
+/// Название ноты с октавой (60 -> "C4")
+pub fn note_name(note: i32) -> String {
+    format!("{}{}", NOTE_NAMES[note.rem_euclid(12) as usize], note.div_euclid(12) - 1)
+}
+
+// Helper функции
+
+/// Уточняет положение минимума по соседним точкам
+fn parabolic_minimum(values: &[f32], index: usize) -> f32 {
+    if index == 0 || index + 1 >= values.len() {
+        return index as f32;
+    }
+    let (left, center, right) = (values[index - 1], values[index], values[index + 1]);
+    let denominator = left - 2.0 * center + right;
+    if denominator.abs() < f32::EPSILON {
+        return index as f32;
+    }
+    index as f32 + 0.5 * (left - right) / denominator
+}
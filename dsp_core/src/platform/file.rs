@@ -0,0 +1,323 @@
+// Файловая "платформа": читает вход из WAV и пишет обработанный выход в WAV.
+// Используется для CI, серверов без звуковой карты и воспроизведения баг-репортов.
+use super::{PlatformAudio, ProcessCallback, StreamCounters, StreamStats};
+use crate::offline::read_samples;
+
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Переменная окружения с путем к входному WAV файлу
+pub const INPUT_ENV: &str = "DSP_CORE_INPUT_WAV";
+/// Переменная окружения с путем к выходному WAV файлу
+pub const OUTPUT_ENV: &str = "DSP_CORE_OUTPUT_WAV";
+/// Переменная окружения с режимом подачи блоков ("realtime" или "fast")
+pub const PACING_ENV: &str = "DSP_CORE_FILE_PACING";
+
+/// Размер блока по умолчанию
+const DEFAULT_BUFFER_SIZE: usize = 512;
+
+/// Режим подачи блоков в callback
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilePacing {
+    AsFastAsPossible, // Без пауз - для CI и пакетной обработки
+    RealTime,         // С паузами, имитирующими реальное устройство
+}
+
+/// Платформо-независимая реализация, работающая с WAV файлами
+pub struct FilePlatform {
+    input_path: PathBuf,
+    output_path: PathBuf,
+    pacing: FilePacing,
+    sample_rate: f32,
+    buffer_size: usize,
+    input_channels: u16,
+    total_frames: u64,
+    frames_processed: Arc<AtomicU64>,
+    is_running: Arc<AtomicBool>,
+    callback: Option<ProcessCallback>,
+    counters: Arc<StreamCounters>,
+    worker: Option<JoinHandle<(ProcessCallback, Result<(), FileAudioError>)>>,
+}
+
+#[derive(Debug)]
+pub enum FileAudioError {
+    NotConfigured(&'static str),
+    Wav(String),
+    StreamError(String),
+}
+
+impl std::fmt::Display for FileAudioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileAudioError::NotConfigured(var) => write!(f, "Не задана переменная окружения {}", var),
+            FileAudioError::Wav(msg) => write!(f, "Ошибка WAV: {}", msg),
+            FileAudioError::StreamError(msg) => write!(f, "Ошибка файлового потока: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for FileAudioError {}
+
+impl From<hound::Error> for FileAudioError {
+    fn from(e: hound::Error) -> Self {
+        FileAudioError::Wav(e.to_string())
+    }
+}
+
+impl PlatformAudio for FilePlatform {
+    type Error = FileAudioError;
+
+    /// Берет пути из `DSP_CORE_INPUT_WAV`/`DSP_CORE_OUTPUT_WAV`
+    fn initialize() -> Result<Self, Self::Error> {
+        let input = std::env::var_os(INPUT_ENV).ok_or(FileAudioError::NotConfigured(INPUT_ENV))?;
+        let output = std::env::var_os(OUTPUT_ENV).ok_or(FileAudioError::NotConfigured(OUTPUT_ENV))?;
+        let pacing = match std::env::var(PACING_ENV).as_deref() {
+            Ok("realtime") => FilePacing::RealTime,
+            _ => FilePacing::AsFastAsPossible,
+        };
+
+        Self::open(input, output, pacing)
+    }
+
+    fn get_sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    fn get_buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    fn set_process_callback(&mut self, callback: ProcessCallback) {
+        self.callback = Some(callback);
+    }
+
+    /// Файл не может переполниться или опустеть - считаются только блоки
+    fn stream_stats(&self) -> StreamStats {
+        self.counters.snapshot()
+    }
+
+    /// Запускает чтение файла с начала; выходной файл перезаписывается
+    fn start(&mut self) -> Result<(), Self::Error> {
+        if self.is_running.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        // Предыдущий прогон мог завершиться сам - забираем callback обратно
+        self.join_worker()?;
+
+        let reader = WavReader::open(&self.input_path)?;
+        let writer = WavWriter::create(&self.output_path, output_spec(self.sample_rate as u32))?;
+        let callback = self.callback.take().unwrap_or_else(|| Box::new(passthrough));
+
+        self.frames_processed.store(0, Ordering::Relaxed);
+        self.counters.reset();
+        self.is_running.store(true, Ordering::Relaxed);
+
+        let stream = FileStream {
+            reader,
+            writer,
+            buffer_size: self.buffer_size,
+            block_duration: Duration::from_secs_f64(self.buffer_size as f64 / self.sample_rate as f64),
+            pacing: self.pacing,
+            frames_processed: self.frames_processed.clone(),
+            counters: self.counters.clone(),
+            is_running: self.is_running.clone(),
+        };
+
+        let worker = std::thread::Builder::new()
+            .name("dsp_core-file".to_string())
+            .spawn(move || stream.run(callback))
+            .map_err(|e| FileAudioError::StreamError(format!("Не удалось запустить поток: {}", e)))?;
+
+        self.worker = Some(worker);
+        println!("🎵 Файловый поток запущен: {}", self.input_path.display());
+        Ok(())
+    }
+
+    /// Прерывает обработку; уже записанная часть выходного файла сохраняется
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        self.is_running.store(false, Ordering::Relaxed);
+        self.join_worker()
+    }
+
+    fn supports_low_latency(&self) -> bool {
+        false
+    }
+
+    fn platform_info(&self) -> String {
+        let pacing = match self.pacing {
+            FilePacing::AsFastAsPossible => "максимальная скорость",
+            FilePacing::RealTime => "реальное время",
+        };
+
+        format!(
+            "WAV файл (SR: {:.0} Гц, Buffer: {} сэмплов, каналов: {}, режим: {}, вход: {}, выход: {})",
+            self.sample_rate,
+            self.buffer_size,
+            self.input_channels,
+            pacing,
+            self.input_path.display(),
+            self.output_path.display()
+        )
+    }
+}
+
+impl FilePlatform {
+    /// Открывает входной WAV файл и запоминает путь для выходного
+    pub fn open<P: AsRef<Path>, Q: AsRef<Path>>(input: P, output: Q, pacing: FilePacing) -> Result<Self, FileAudioError> {
+        let reader = WavReader::open(input.as_ref())?;
+        let spec = reader.spec();
+
+        Ok(FilePlatform {
+            input_path: input.as_ref().to_path_buf(),
+            output_path: output.as_ref().to_path_buf(),
+            pacing,
+            sample_rate: spec.sample_rate as f32,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            input_channels: spec.channels,
+            total_frames: reader.duration() as u64,
+            frames_processed: Arc::new(AtomicU64::new(0)),
+            is_running: Arc::new(AtomicBool::new(false)),
+            callback: None,
+            counters: Arc::new(StreamCounters::default()),
+            worker: None,
+        })
+    }
+
+    /// Устанавливает размер блока (применяется при следующем запуске)
+    pub fn set_buffer_size(&mut self, buffer_size: usize) {
+        self.buffer_size = buffer_size.max(1);
+    }
+
+    /// Проверяет, идет ли обработка файла
+    pub fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::Relaxed)
+    }
+
+    /// Ждет, пока весь файл будет обработан и выходной файл записан
+    pub fn wait(&mut self) -> Result<(), FileAudioError> {
+        self.join_worker()
+    }
+
+    /// Прогресс обработки от 0.0 до 1.0
+    pub fn progress(&self) -> f32 {
+        if self.total_frames == 0 {
+            return 1.0;
+        }
+        (self.frames_processed.load(Ordering::Relaxed) as f32 / self.total_frames as f32).min(1.0)
+    }
+
+    fn join_worker(&mut self) -> Result<(), FileAudioError> {
+        let Some(worker) = self.worker.take() else {
+            return Ok(());
+        };
+
+        let (callback, result) = worker
+            .join()
+            .map_err(|_| FileAudioError::StreamError("Файловый поток завершился с паникой".to_string()))?;
+        self.callback = Some(callback);
+        result
+    }
+}
+
+impl Drop for FilePlatform {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Состояние потока, обрабатывающего файл
+struct FileStream<R: Read> {
+    reader: WavReader<R>,
+    writer: WavWriter<std::io::BufWriter<std::fs::File>>,
+    buffer_size: usize,
+    block_duration: Duration,
+    pacing: FilePacing,
+    frames_processed: Arc<AtomicU64>,
+    counters: Arc<StreamCounters>,
+    is_running: Arc<AtomicBool>,
+}
+
+impl<R: Read> FileStream<R> {
+    fn run(self, mut callback: ProcessCallback) -> (ProcessCallback, Result<(), FileAudioError>) {
+        let is_running = self.is_running.clone();
+        let result = self.process(&mut callback);
+        is_running.store(false, Ordering::Relaxed);
+        (callback, result)
+    }
+
+    fn process(mut self, callback: &mut ProcessCallback) -> Result<(), FileAudioError> {
+        let mut input = vec![0.0f32; self.buffer_size];
+        let mut output = vec![0.0f32; self.buffer_size];
+        let mut scratch = Vec::new();
+        let started = Instant::now();
+        let mut blocks = 0u32;
+
+        while self.is_running.load(Ordering::Relaxed) {
+            let frames = read_mono_block(&mut self.reader, &mut input, &mut scratch)?;
+            if frames == 0 {
+                break;
+            }
+
+            // Последний блок дополняем тишиной, чтобы callback всегда получал полный блок
+            input[frames..].fill(0.0);
+            callback(&input, &mut output);
+            self.counters.record_callback();
+
+            for &sample in &output[..frames] {
+                self.writer.write_sample(sample)?;
+            }
+            self.frames_processed.fetch_add(frames as u64, Ordering::Relaxed);
+
+            blocks += 1;
+            if self.pacing == FilePacing::RealTime {
+                let deadline = self.block_duration * blocks;
+                if let Some(remaining) = deadline.checked_sub(started.elapsed()) {
+                    std::thread::sleep(remaining);
+                }
+            }
+        }
+
+        self.writer.finalize()?;
+        Ok(())
+    }
+}
+
+// Helper функции
+
+/// Обработка по умолчанию, пока callback не установлен
+fn passthrough(input: &[f32], output: &mut [f32]) {
+    output.copy_from_slice(input);
+}
+
+/// Формат выходного файла: моно, 32-bit float
+fn output_spec(sample_rate: u32) -> WavSpec {
+    WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    }
+}
+
+/// Читает до `block.len()` кадров, сводя многоканальный вход в моно.
+/// Возвращает количество прочитанных кадров (0 - конец файла).
+fn read_mono_block<R: Read>(
+    reader: &mut WavReader<R>,
+    block: &mut [f32],
+    scratch: &mut Vec<f32>,
+) -> Result<usize, hound::Error> {
+    let channels = reader.spec().channels.max(1) as usize;
+    scratch.resize(block.len() * channels, 0.0);
+
+    let frames = read_samples(reader, scratch)? / channels;
+    for (sample, frame) in block.iter_mut().zip(scratch.chunks(channels)).take(frames) {
+        *sample = frame.iter().sum::<f32>() / channels as f32;
+    }
+
+    Ok(frames)
+}
@@ -0,0 +1,411 @@
+// Linux платформо-специфичная функциональность (ALSA)
+use super::{PlatformAudio, ProcessCallback, StreamCounters, StreamStats};
+
+use alsa::pcm::{Access, Format, HwParams, PCM};
+use alsa::{Direction, ValueOr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Устройство ALSA по умолчанию (через plug-слой PulseAudio/PipeWire/dmix)
+const DEFAULT_DEVICE: &str = "default";
+/// Желаемая частота дискретизации
+const PREFERRED_SAMPLE_RATE: u32 = 44100;
+/// Желаемый размер периода в сэмплах
+const PREFERRED_PERIOD_SIZE: usize = 512;
+/// Количество периодов в кольцевом буфере устройства
+const PERIODS: u32 = 4;
+/// Сколько периодов тишины кладем в воспроизведение перед стартом
+const PLAYBACK_PREFILL_PERIODS: usize = 2;
+
+/// Платформо-специфичная реализация для Linux с ALSA
+pub struct AlsaPlatform {
+    capture_device: String,
+    playback_device: String,
+    streams: Option<AlsaStreams>,
+    sample_rate: f32,
+    buffer_size: usize,
+    is_running: Arc<AtomicBool>,
+    callback: Option<ProcessCallback>,
+    counters: Arc<StreamCounters>,
+    worker: Option<JoinHandle<DuplexResult>>,
+}
+
+/// Что возвращает дуплексный поток: устройства, callback и ошибка, если поток прервался
+type DuplexResult = (AlsaStreams, ProcessCallback, Option<AlsaError>);
+
+/// Открытые PCM потоки захвата и воспроизведения
+struct AlsaStreams {
+    capture: PCM,
+    playback: PCM,
+    format: SampleFormat,
+    period_size: usize,
+}
+
+/// Формат сэмплов, согласованный с устройством
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SampleFormat {
+    F32,
+    S16,
+}
+
+#[derive(Debug)]
+pub enum AlsaError {
+    InitializationFailed(String),
+    DeviceNotFound(String),
+    StreamError(String),
+    UnsupportedFormat,
+}
+
+impl std::fmt::Display for AlsaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlsaError::InitializationFailed(msg) => write!(f, "Ошибка инициализации: {}", msg),
+            AlsaError::DeviceNotFound(name) => write!(f, "ALSA устройство не найдено: {}", name),
+            AlsaError::StreamError(msg) => write!(f, "Ошибка ALSA потока: {}", msg),
+            AlsaError::UnsupportedFormat => write!(f, "Неподдерживаемый формат"),
+        }
+    }
+}
+
+impl std::error::Error for AlsaError {}
+
+impl From<alsa::Error> for AlsaError {
+    fn from(e: alsa::Error) -> Self {
+        AlsaError::StreamError(e.to_string())
+    }
+}
+
+impl PlatformAudio for AlsaPlatform {
+    type Error = AlsaError;
+
+    fn initialize() -> Result<Self, Self::Error> {
+        Self::with_devices(DEFAULT_DEVICE, DEFAULT_DEVICE)
+    }
+
+    fn get_sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    fn get_buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    fn set_process_callback(&mut self, callback: ProcessCallback) {
+        self.callback = Some(callback);
+    }
+
+    fn stream_stats(&self) -> StreamStats {
+        self.counters.snapshot()
+    }
+
+    fn start(&mut self) -> Result<(), Self::Error> {
+        if self.is_running.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        // Предыдущий поток мог завершиться сам из-за ошибки устройства - забираем устройства и
+        // callback обратно (ошибку поток уже вывел, prepare ниже покажет, живо ли устройство)
+        if let Some(e) = self.join_worker()? {
+            println!("⚠️ Предыдущий ALSA поток завершился с ошибкой: {}", e);
+        }
+
+        let streams = self.streams.take().ok_or_else(|| {
+            AlsaError::StreamError("PCM потоки недоступны".to_string())
+        })?;
+        let callback = self.callback.take().unwrap_or_else(|| Box::new(passthrough));
+
+        self.counters.reset();
+        self.is_running.store(true, Ordering::Relaxed);
+        let is_running = self.is_running.clone();
+        let counters = self.counters.clone();
+
+        let worker = std::thread::Builder::new()
+            .name("dsp_core-alsa".to_string())
+            .spawn(move || run_duplex(streams, callback, is_running, counters))
+            .map_err(|e| AlsaError::StreamError(format!("Не удалось запустить поток: {}", e)))?;
+
+        self.worker = Some(worker);
+        println!("🎵 ALSA поток запущен");
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        // Поток мог уже завершиться сам из-за ошибки устройства - все равно забираем устройства
+        self.is_running.store(false, Ordering::Relaxed);
+        if self.worker.is_none() {
+            return Ok(());
+        }
+        let error = self.join_worker()?;
+
+        println!("🛑 ALSA поток остановлен");
+        error.map_or(Ok(()), Err)
+    }
+
+    fn supports_low_latency(&self) -> bool {
+        self.buffer_size <= 256
+    }
+
+    fn platform_info(&self) -> String {
+        format!(
+            "Linux ALSA (SR: {:.0} Гц, Buffer: {} сэмплов, вход: {}, выход: {})",
+            self.sample_rate, self.buffer_size, self.capture_device, self.playback_device
+        )
+    }
+}
+
+impl AlsaPlatform {
+    /// Открывает указанные устройства захвата и воспроизведения (например, "hw:0,0" или "pipewire")
+    pub fn with_devices(capture_device: &str, playback_device: &str) -> Result<Self, AlsaError> {
+        println!("🐧 Инициализация ALSA на Linux...");
+
+        let (capture, format, sample_rate, period_size) = open_pcm(
+            capture_device,
+            Direction::Capture,
+            PREFERRED_SAMPLE_RATE,
+            PREFERRED_PERIOD_SIZE,
+        )?;
+
+        // Воспроизведение настраиваем на параметры, которые согласовал захват
+        let (playback, playback_format, playback_rate, playback_period) =
+            open_pcm(playback_device, Direction::Playback, sample_rate, period_size)?;
+
+        if playback_rate != sample_rate {
+            return Err(AlsaError::InitializationFailed(format!(
+                "Частоты захвата и воспроизведения не совпадают: {} / {} Гц",
+                sample_rate, playback_rate
+            )));
+        }
+        if playback_format != format || playback_period != period_size {
+            return Err(AlsaError::UnsupportedFormat);
+        }
+
+        println!(
+            "✅ ALSA устройства открыты: {} Гц, период {} сэмплов, формат {:?}",
+            sample_rate, period_size, format
+        );
+
+        Ok(AlsaPlatform {
+            capture_device: capture_device.to_string(),
+            playback_device: playback_device.to_string(),
+            streams: Some(AlsaStreams {
+                capture,
+                playback,
+                format,
+                period_size,
+            }),
+            sample_rate: sample_rate as f32,
+            buffer_size: period_size,
+            is_running: Arc::new(AtomicBool::new(false)),
+            callback: None,
+            counters: Arc::new(StreamCounters::default()),
+            worker: None,
+        })
+    }
+
+    /// Проверяет, запущен ли аудио поток
+    pub fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::Relaxed)
+    }
+
+    /// Дожидается завершения потока и забирает устройства и callback; возвращает ошибку,
+    /// с которой поток остановился
+    fn join_worker(&mut self) -> Result<Option<AlsaError>, AlsaError> {
+        let Some(worker) = self.worker.take() else {
+            return Ok(None);
+        };
+
+        let (streams, callback, error) = worker
+            .join()
+            .map_err(|_| AlsaError::StreamError("Аудио поток завершился с паникой".to_string()))?;
+        self.streams = Some(streams);
+        self.callback = Some(callback);
+        Ok(error)
+    }
+}
+
+impl Drop for AlsaPlatform {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+// Helper функции
+
+/// Обработка по умолчанию, пока callback не установлен
+fn passthrough(input: &[f32], output: &mut [f32]) {
+    output.copy_from_slice(input);
+}
+
+/// Открывает PCM устройство и согласовывает моно поток с желаемыми параметрами
+fn open_pcm(
+    device: &str,
+    direction: Direction,
+    sample_rate: u32,
+    period_size: usize,
+) -> Result<(PCM, SampleFormat, u32, usize), AlsaError> {
+    let pcm = PCM::new(device, direction, false)
+        .map_err(|_| AlsaError::DeviceNotFound(device.to_string()))?;
+
+    let format = {
+        let hwp = HwParams::any(&pcm)?;
+        hwp.set_channels(1)
+            .map_err(|e| AlsaError::InitializationFailed(format!("моно недоступно: {}", e)))?;
+        hwp.set_access(Access::RWInterleaved)?;
+
+        let format = if hwp.set_format(Format::float()).is_ok() {
+            SampleFormat::F32
+        } else if hwp.set_format(Format::s16()).is_ok() {
+            SampleFormat::S16
+        } else {
+            return Err(AlsaError::UnsupportedFormat);
+        };
+
+        hwp.set_rate_near(sample_rate, ValueOr::Nearest)?;
+        let period = hwp.set_period_size_near(period_size as alsa::pcm::Frames, ValueOr::Nearest)?;
+        hwp.set_buffer_size_near(period * PERIODS as alsa::pcm::Frames)?;
+        pcm.hw_params(&hwp)?;
+        format
+    };
+
+    // Считываем то, что реально согласовал драйвер
+    let (rate, period) = {
+        let current = pcm.hw_params_current()?;
+        (current.get_rate()?, current.get_period_size()? as usize)
+    };
+
+    if direction == Direction::Playback {
+        let swp = pcm.sw_params_current()?;
+        swp.set_start_threshold(period as alsa::pcm::Frames)?;
+        pcm.sw_params(&swp)?;
+    }
+
+    Ok((pcm, format, rate, period))
+}
+
+/// Цикл дуплексного потока: захват → callback → воспроизведение
+fn run_duplex(
+    streams: AlsaStreams,
+    mut callback: ProcessCallback,
+    is_running: Arc<AtomicBool>,
+    counters: Arc<StreamCounters>,
+) -> DuplexResult {
+    let period_size = streams.period_size;
+    let mut input = vec![0.0f32; period_size];
+    let mut output = vec![0.0f32; period_size];
+    let mut scratch = vec![0i16; period_size];
+    let silence = vec![0.0f32; period_size];
+
+    if let Err(e) = prepare_streams(&streams, &silence, &mut scratch) {
+        println!("⚠️ Не удалось подготовить ALSA потоки: {}", e);
+        is_running.store(false, Ordering::Relaxed);
+        return (streams, callback, Some(e.into()));
+    }
+
+    let mut error = None;
+    while is_running.load(Ordering::Relaxed) {
+        if let Err(e) = read_period(&streams, &mut input, &mut scratch) {
+            // Переполнение захвата (overrun) считаем только по EPIPE; прерванный вызов или
+            // пробуждение после suspend восстанавливаем молча, остальное завершает поток
+            if is_xrun(&e) {
+                counters.record_overrun();
+            }
+            if streams.capture.try_recover(e, true).is_err() {
+                println!("⚠️ Невосстановимая ошибка ALSA захвата: {}", e);
+                error = Some(e.into());
+                break;
+            }
+            continue;
+        }
+
+        callback(&input, &mut output);
+        counters.record_callback();
+
+        if let Err(e) = write_period(&streams, &output, &mut scratch) {
+            // Опустошение воспроизведения (underrun) - восстанавливаемся и снова заполняем буфер
+            if is_xrun(&e) {
+                counters.record_underrun();
+            }
+            if streams.playback.try_recover(e, true).is_err() {
+                println!("⚠️ Невосстановимая ошибка ALSA воспроизведения: {}", e);
+                error = Some(e.into());
+                break;
+            }
+            let _ = prefill_playback(&streams, &silence, &mut scratch);
+        }
+    }
+
+    let _ = streams.capture.drop();
+    let _ = streams.playback.drop();
+    is_running.store(false, Ordering::Relaxed);
+
+    (streams, callback, error)
+}
+
+/// Ошибка - это xrun (EPIPE), а не сбой устройства
+fn is_xrun(error: &alsa::Error) -> bool {
+    // alsa хранит код ошибки со знаком минус, как его вернула libasound
+    std::io::Error::from_raw_os_error(error.errno().abs()).kind() == std::io::ErrorKind::BrokenPipe
+}
+
+/// Подготавливает устройства и запускает захват
+fn prepare_streams(streams: &AlsaStreams, silence: &[f32], scratch: &mut [i16]) -> alsa::Result<()> {
+    streams.capture.prepare()?;
+    streams.playback.prepare()?;
+    prefill_playback(streams, silence, scratch)?;
+    streams.capture.start()
+}
+
+/// Заполняет буфер воспроизведения тишиной (период нулей, выделенный заранее), чтобы захват
+/// успел накопить данные
+fn prefill_playback(streams: &AlsaStreams, silence: &[f32], scratch: &mut [i16]) -> alsa::Result<()> {
+    for _ in 0..PLAYBACK_PREFILL_PERIODS {
+        write_period(streams, silence, scratch)?;
+    }
+    Ok(())
+}
+
+/// Читает ровно один период с устройства захвата
+fn read_period(streams: &AlsaStreams, input: &mut [f32], scratch: &mut [i16]) -> alsa::Result<()> {
+    let mut frames = 0;
+    match streams.format {
+        SampleFormat::F32 => {
+            let io = streams.capture.io_f32()?;
+            while frames < input.len() {
+                frames += io.readi(&mut input[frames..])?;
+            }
+        }
+        SampleFormat::S16 => {
+            let io = streams.capture.io_i16()?;
+            while frames < input.len() {
+                frames += io.readi(&mut scratch[frames..input.len()])?;
+            }
+            for (sample, &raw) in input.iter_mut().zip(scratch.iter()) {
+                *sample = raw as f32 / 32768.0;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Записывает ровно один период в устройство воспроизведения
+fn write_period(streams: &AlsaStreams, output: &[f32], scratch: &mut [i16]) -> alsa::Result<()> {
+    let mut frames = 0;
+    match streams.format {
+        SampleFormat::F32 => {
+            let io = streams.playback.io_f32()?;
+            while frames < output.len() {
+                frames += io.writei(&output[frames..])?;
+            }
+        }
+        SampleFormat::S16 => {
+            for (raw, &sample) in scratch.iter_mut().zip(output.iter()) {
+                *raw = (sample.clamp(-1.0, 1.0) * 32767.0) as i16;
+            }
+            let io = streams.playback.io_i16()?;
+            while frames < output.len() {
+                frames += io.writei(&scratch[frames..output.len()])?;
+            }
+        }
+    }
+    Ok(())
+}
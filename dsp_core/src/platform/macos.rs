@@ -1,18 +1,45 @@
 // macOS платформо-специфичная функциональность
-use super::PlatformAudio;
+use super::{PlatformAudio, ProcessCallback, StreamCounters, StreamStats};
+use std::sync::Arc;
 
 #[cfg(target_os = "macos")]
 use coreaudio::audio_unit::{AudioUnit, Element, SampleFormat, Scope, StreamFormat};
 #[cfg(target_os = "macos")]
+use coreaudio::audio_unit::audio_format::LinearPcmFlags;
+#[cfg(target_os = "macos")]
+use coreaudio::audio_unit::render_callback::{self, data};
+#[cfg(target_os = "macos")]
 use coreaudio::audio_unit::types::IOType;
+#[cfg(target_os = "macos")]
+use coreaudio::sys;
+#[cfg(target_os = "macos")]
+use ringbuf::HeapRb;
+
+/// Аргументы callback'ов Core Audio: неперемежающиеся float каналы
+#[cfg(target_os = "macos")]
+type Args = render_callback::Args<data::NonInterleaved<f32>>;
+
+/// Частота, если не удалось узнать частоту устройства ввода
+const DEFAULT_SAMPLE_RATE: f64 = 44100.0;
+/// Емкость кольцевого буфера между вводом и выводом (в блоках)
+const RING_BLOCKS: usize = 8;
+/// Наибольший блок вывода, если AudioUnit не сообщил свой (значение Core Audio по умолчанию)
+#[cfg(target_os = "macos")]
+const DEFAULT_MAX_FRAMES_PER_SLICE: usize = 4096;
 
 /// Платформо-специфичная реализация для macOS с Core Audio
 pub struct CoreAudioPlatform {
-    audio_unit: Option<AudioUnit>,
+    #[cfg(target_os = "macos")]
+    input_unit: Option<AudioUnit>,
+    #[cfg(target_os = "macos")]
+    output_unit: Option<AudioUnit>,
     sample_rate: f32,
     buffer_size: usize,
     is_running: bool,
     supports_npu: bool,
+    callback: Option<ProcessCallback>,
+    callback_connected: bool,
+    counters: Arc<StreamCounters>,
 }
 
 #[derive(Debug)]
@@ -51,21 +78,26 @@ impl PlatformAudio for CoreAudioPlatform {
             println!("ℹ️  Intel Mac - используем CPU для обработки");
         }
         
-        // Инициализируем Core Audio
+        // Инициализируем Core Audio: отдельные AudioUnit для микрофона и для вывода
         #[cfg(target_os = "macos")]
         {
-            match create_audio_unit() {
-                Ok(audio_unit) => {
-                    Ok(CoreAudioPlatform {
-                        audio_unit: Some(audio_unit),
-                        sample_rate: 44100.0,
-                        buffer_size: 512,
-                        is_running: false,
-                        supports_npu,
-                    })
-                }
-                Err(e) => Err(CoreAudioError::InitializationFailed(e.to_string())),
-            }
+            let device = default_input_device().ok_or(CoreAudioError::DeviceNotFound)?;
+            let (input_unit, sample_rate) = create_input_unit(device)
+                .map_err(|e| CoreAudioError::InitializationFailed(e.to_string()))?;
+            let output_unit = create_output_unit(sample_rate)
+                .map_err(|e| CoreAudioError::InitializationFailed(e.to_string()))?;
+            
+            Ok(CoreAudioPlatform {
+                input_unit: Some(input_unit),
+                output_unit: Some(output_unit),
+                sample_rate: sample_rate as f32,
+                buffer_size: 512,
+                is_running: false,
+                supports_npu,
+                callback: None,
+                callback_connected: false,
+                counters: Arc::new(StreamCounters::default()),
+            })
         }
         
         #[cfg(not(target_os = "macos"))]
@@ -84,6 +116,14 @@ impl PlatformAudio for CoreAudioPlatform {
         self.buffer_size
     }
 
+    fn set_process_callback(&mut self, callback: ProcessCallback) {
+        self.callback = Some(callback);
+    }
+
+    fn stream_stats(&self) -> StreamStats {
+        self.counters.snapshot()
+    }
+
     fn start(&mut self) -> Result<(), Self::Error> {
         if self.is_running {
             return Ok(());
@@ -91,7 +131,23 @@ impl PlatformAudio for CoreAudioPlatform {
 
         #[cfg(target_os = "macos")]
         {
-            if let Some(ref mut audio_unit) = self.audio_unit {
+            // Новый callback подключаем заново, иначе остается подключенный ранее
+            if let Some(callback) = self.callback.take() {
+                self.connect_callback(callback)?;
+            } else if !self.callback_connected {
+                self.connect_callback(Box::new(passthrough))?;
+            }
+
+            self.counters.reset();
+            let buffer_size = self.buffer_size as u32;
+            for audio_unit in [self.input_unit.as_mut(), self.output_unit.as_mut()].into_iter().flatten() {
+                // Размер буфера устройства - пожелание, HAL может выбрать другой
+                let _ = audio_unit.set_property(
+                    sys::kAudioDevicePropertyBufferFrameSize,
+                    Scope::Global,
+                    Element::Output,
+                    Some(&buffer_size),
+                );
                 audio_unit.start().map_err(|e| {
                     CoreAudioError::AudioUnitError(format!("Не удалось запустить: {}", e))
                 })?;
@@ -110,7 +166,7 @@ impl PlatformAudio for CoreAudioPlatform {
 
         #[cfg(target_os = "macos")]
         {
-            if let Some(ref mut audio_unit) = self.audio_unit {
+            for audio_unit in [self.input_unit.as_mut(), self.output_unit.as_mut()].into_iter().flatten() {
                 audio_unit.stop().map_err(|e| {
                     CoreAudioError::AudioUnitError(format!("Не удалось остановить: {}", e))
                 })?;
@@ -151,6 +207,7 @@ impl CoreAudioPlatform {
     }
     
     /// Устанавливает низкую задержку для критичных приложений
+    /// (применяется при следующем запуске потока)
     pub fn set_low_latency_mode(&mut self, enable: bool) -> Result<(), CoreAudioError> {
         if enable {
             self.buffer_size = 64; // Минимальный размер буфера
@@ -161,10 +218,101 @@ impl CoreAudioPlatform {
         }
         Ok(())
     }
+    
+    /// Проверяет, запущен ли аудио поток
+    pub fn is_running(&self) -> bool {
+        self.is_running
+    }
+    
+    /// Связывает ввод и вывод через кольцевой буфер и подключает функцию обработки.
+    ///
+    /// Поток ввода складывает сэмплы микрофона в буфер (при переполнении считается overrun),
+    /// поток вывода забирает их, вызывает callback и пишет результат во все каналы вывода
+    /// (если данных не хватило - дополняет тишиной и считает underrun).
+    #[cfg(target_os = "macos")]
+    fn connect_callback(&mut self, mut callback: ProcessCallback) -> Result<(), CoreAudioError> {
+        let (Some(input_unit), Some(output_unit)) = (self.input_unit.as_mut(), self.output_unit.as_mut()) else {
+            return Err(CoreAudioError::AudioUnitError("AudioUnit не создан".to_string()));
+        };
+        
+        let ring = HeapRb::<f32>::new(self.buffer_size * RING_BLOCKS);
+        let (mut producer, mut consumer) = ring.split();
+        // Один блок тишины в запасе, чтобы первый вызов вывода не опустошил буфер
+        for _ in 0..self.buffer_size {
+            let _ = producer.push(0.0);
+        }
+        
+        let counters = self.counters.clone();
+        input_unit
+            .set_input_callback(move |args: Args| {
+                let Args { num_frames, mut data, .. } = args;
+                if let Some(channel) = data.channels_mut().next() {
+                    if producer.push_slice(&channel[..num_frames]) < num_frames {
+                        counters.record_overrun();
+                    }
+                }
+                Ok(())
+            })
+            .map_err(|e| CoreAudioError::AudioUnitError(format!("Не удалось подключить ввод: {}", e)))?;
+        
+        // Буферы выделяем заранее под наибольший блок, который может запросить вывод:
+        // в аудио потоке память не выделяется. Больший блок (не должен приходить) режем на части.
+        let max_frames = output_unit
+            .get_property::<u32>(sys::kAudioUnitProperty_MaximumFramesPerSlice, Scope::Global, Element::Output)
+            .map(|frames| frames as usize)
+            .unwrap_or(DEFAULT_MAX_FRAMES_PER_SLICE)
+            .max(self.buffer_size);
+        let counters = self.counters.clone();
+        let mut input_block = vec![0.0f32; max_frames];
+        let mut output_block = vec![0.0f32; max_frames];
+        output_unit
+            .set_render_callback(move |args: Args| {
+                let Args { num_frames, mut data, .. } = args;
+                let mut underrun = false;
+                for start in (0..num_frames).step_by(max_frames) {
+                    let frames = max_frames.min(num_frames - start);
+                    let input = &mut input_block[..frames];
+                    let output = &mut output_block[..frames];
+                    
+                    let available = consumer.pop_slice(input);
+                    if available < frames {
+                        input[available..].fill(0.0);
+                        underrun = true;
+                    }
+                    
+                    callback(input, output);
+                    counters.record_callback();
+                    
+                    for channel in data.channels_mut() {
+                        channel[start..start + frames].copy_from_slice(output);
+                    }
+                }
+                if underrun {
+                    counters.record_underrun();
+                }
+                Ok(())
+            })
+            .map_err(|e| CoreAudioError::AudioUnitError(format!("Не удалось подключить вывод: {}", e)))?;
+        
+        self.callback_connected = true;
+        Ok(())
+    }
+}
+
+impl Drop for CoreAudioPlatform {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
 }
 
 // Helper функции
 
+/// Обработка по умолчанию, пока callback не установлен
+#[cfg(target_os = "macos")]
+fn passthrough(input: &[f32], output: &mut [f32]) {
+    output.copy_from_slice(input);
+}
+
 /// Проверяет, является ли система Apple Silicon
 fn is_apple_silicon() -> bool {
     #[cfg(target_arch = "aarch64")]
@@ -177,26 +325,73 @@ fn is_apple_silicon() -> bool {
     }
 }
 
-/// Создает и настраивает AudioUnit для Core Audio
+/// Поток 32-bit float без перемежения (формат callback'ов)
 #[cfg(target_os = "macos")]
-fn create_audio_unit() -> Result<AudioUnit, Box<dyn std::error::Error>> {
-    // Создаем HAL Output Unit (для воспроизведения)
-    let mut audio_unit = AudioUnit::new(IOType::HalOutput)?;
-    
-    // Настраиваем формат потока
-    let stream_format = StreamFormat {
-        sample_rate: 44100.0,
+fn float_stream_format(sample_rate: f64, channels: u32) -> StreamFormat {
+    StreamFormat {
+        sample_rate,
         sample_format: SampleFormat::F32,
-        channels: 2, // Стерео
+        flags: LinearPcmFlags::IS_FLOAT | LinearPcmFlags::IS_PACKED | LinearPcmFlags::IS_NON_INTERLEAVED,
+        channels_per_frame: channels,
+    }
+}
+
+/// Возвращает устройство ввода по умолчанию
+#[cfg(target_os = "macos")]
+fn default_input_device() -> Option<sys::AudioDeviceID> {
+    let address = sys::AudioObjectPropertyAddress {
+        mSelector: sys::kAudioHardwarePropertyDefaultInputDevice,
+        mScope: sys::kAudioObjectPropertyScopeGlobal,
+        mElement: sys::kAudioObjectPropertyElementMaster,
     };
     
-    // Устанавливаем формат потока
-    audio_unit.set_stream_format(stream_format)?;
+    let mut device: sys::AudioDeviceID = 0;
+    let mut size = std::mem::size_of::<sys::AudioDeviceID>() as u32;
+    let status = unsafe {
+        sys::AudioObjectGetPropertyData(
+            sys::kAudioObjectSystemObject,
+            &address,
+            0,
+            std::ptr::null(),
+            &mut size,
+            &mut device as *mut _ as *mut _,
+        )
+    };
     
-    Ok(audio_unit)
+    (status == sys::kAudioHardwareNoError as i32 && device != 0).then_some(device)
+}
+
+/// Создает HAL AudioUnit для захвата с микрофона.
+/// Возвращает его вместе с частотой устройства (HAL не меняет частоту на вводе).
+#[cfg(target_os = "macos")]
+fn create_input_unit(device: sys::AudioDeviceID) -> Result<(AudioUnit, f64), coreaudio::Error> {
+    let mut audio_unit = AudioUnit::new(IOType::HalOutput)?;
+    
+    // Включаем ввод и выключаем вывод
+    let enable: u32 = 1;
+    let disable: u32 = 0;
+    audio_unit.set_property(sys::kAudioOutputUnitProperty_EnableIO, Scope::Input, Element::Input, Some(&enable))?;
+    audio_unit.set_property(sys::kAudioOutputUnitProperty_EnableIO, Scope::Output, Element::Output, Some(&disable))?;
+    audio_unit.set_property(sys::kAudioOutputUnitProperty_CurrentDevice, Scope::Global, Element::Output, Some(&device))?;
+    
+    let sample_rate = audio_unit
+        .get_property::<f64>(sys::kAudioUnitProperty_SampleRate, Scope::Input, Element::Input)
+        .unwrap_or(DEFAULT_SAMPLE_RATE);
+    
+    // Формат, в котором HAL отдает нам данные микрофона
+    let asbd = float_stream_format(sample_rate, 1).to_asbd();
+    audio_unit.set_property(sys::kAudioUnitProperty_StreamFormat, Scope::Output, Element::Input, Some(&asbd))?;
+    
+    Ok((audio_unit, sample_rate))
 }
 
-// Константы больше не нужны - используем высокоуровневый API
+/// Создает AudioUnit вывода на устройство по умолчанию (стерео, моно сигнал дублируется)
+#[cfg(target_os = "macos")]
+fn create_output_unit(sample_rate: f64) -> Result<AudioUnit, coreaudio::Error> {
+    let mut audio_unit = AudioUnit::new(IOType::DefaultOutput)?;
+    audio_unit.set_stream_format(float_stream_format(sample_rate, 2), Scope::Input)?;
+    Ok(audio_unit)
+}
 
 /// Core ML интеграция для Apple Silicon
 #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
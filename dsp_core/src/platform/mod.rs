@@ -3,6 +3,70 @@
 #[cfg(target_os = "macos")]
 pub mod macos;
 
+#[cfg(target_os = "linux")]
+pub mod linux;
+
+// Работа с WAV файлами вместо аудио устройства (все платформы)
+pub mod file;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Функция обработки аудио потока: получает входной блок и заполняет выходной
+pub type ProcessCallback = Box<dyn FnMut(&[f32], &mut [f32]) + Send + 'static>;
+
+/// Статистика аудио потока с момента последнего запуска
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StreamStats {
+    pub callbacks: u64, // Сколько блоков обработано
+    pub overruns: u64,  // Вход переполнился - часть захваченных данных потеряна
+    pub underruns: u64, // Выход опустел - устройство проиграло тишину
+}
+
+impl StreamStats {
+    /// Общее количество сбоев потока (xrun)
+    pub fn xruns(&self) -> u64 {
+        self.overruns + self.underruns
+    }
+}
+
+/// Счетчики потока, которые аудио поток обновляет без блокировок
+#[derive(Debug, Default)]
+pub struct StreamCounters {
+    callbacks: AtomicU64,
+    overruns: AtomicU64,
+    underruns: AtomicU64,
+}
+
+impl StreamCounters {
+    pub fn record_callback(&self) {
+        self.callbacks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_overrun(&self) {
+        self.overruns.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_underrun(&self) {
+        self.underruns.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Обнуляет счетчики (при каждом запуске потока)
+    pub fn reset(&self) {
+        self.callbacks.store(0, Ordering::Relaxed);
+        self.overruns.store(0, Ordering::Relaxed);
+        self.underruns.store(0, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> StreamStats {
+        StreamStats {
+            callbacks: self.callbacks.load(Ordering::Relaxed),
+            overruns: self.overruns.load(Ordering::Relaxed),
+            underruns: self.underruns.load(Ordering::Relaxed),
+        }
+    }
+}
+
 // Общий трейт для платформо-специфичной аудио обработки
 pub trait PlatformAudio {
     type Error;
@@ -18,6 +82,14 @@ pub trait PlatformAudio {
     /// Получает размер буфера
     fn get_buffer_size(&self) -> usize;
     
+    /// Устанавливает функцию обработки. Аудио поток сам забирает вход с устройства,
+    /// вызывает ее для каждого блока и отдает результат на выход.
+    /// Применяется при следующем запуске потока.
+    fn set_process_callback(&mut self, callback: ProcessCallback);
+    
+    /// Получает статистику потока (обработанные блоки, overrun/underrun)
+    fn stream_stats(&self) -> StreamStats;
+    
     /// Запускает аудио поток
     fn start(&mut self) -> Result<(), Self::Error>;
     
@@ -36,29 +108,34 @@ pub trait PlatformAudio {
     }
 }
 
-// Определяем тип только для macOS - убираем поддержку других ОС
+// Выбираем реализацию для текущей ОС
 #[cfg(target_os = "macos")]
 pub type PlatformAudioImpl = macos::CoreAudioPlatform;
 
-// Заглушка для неподдерживаемых платформ (не-macOS)
-#[cfg(not(target_os = "macos"))]
+#[cfg(target_os = "linux")]
+pub type PlatformAudioImpl = linux::AlsaPlatform;
+
+// Заглушка для неподдерживаемых платформ
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
 pub struct DefaultPlatform;
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
 impl PlatformAudio for DefaultPlatform {
     type Error = &'static str;
     
     fn initialize() -> Result<Self, Self::Error> {
-        Err("Поддерживается только macOS M1/M2/M3/M4")
+        Err("Поддерживаются только macOS и Linux")
     }
     
     fn get_sample_rate(&self) -> f32 { 44100.0 }
     fn get_buffer_size(&self) -> usize { 512 }
+    fn set_process_callback(&mut self, _callback: ProcessCallback) {}
+    fn stream_stats(&self) -> StreamStats { StreamStats::default() }
     fn start(&mut self) -> Result<(), Self::Error> { Ok(()) }
     fn stop(&mut self) -> Result<(), Self::Error> { Ok(()) }
     fn supports_low_latency(&self) -> bool { false }
-    fn platform_info(&self) -> String { "Только macOS M1/M2/M3/M4 поддерживается".to_string() }
+    fn platform_info(&self) -> String { "Поддерживаются только macOS и Linux".to_string() }
 }
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
 pub type PlatformAudioImpl = DefaultPlatform;
\ No newline at end of file
@@ -0,0 +1,219 @@
+// Пресеты: полное состояние конвейера (параметры DSP + цепочка голосовых эффектов) в JSON
+use crate::equalizer::EqBand;
+use crate::neural_engine::{QualityPreset, VoiceEffect};
+use crate::{EffectType, NoiseType};
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Версия схемы пресета.
+///
+/// Новые поля добавляются со значениями по умолчанию и версию не меняют: старые файлы
+/// читаются новыми версиями, а неизвестные поля новых файлов старыми версиями пропускаются.
+/// Версия растет только при несовместимых изменениях; пресет более новой версии отклоняется.
+pub const PRESET_VERSION: u32 = 1;
+
+/// Встроенная библиотека пресетов: имя и JSON из каталога `presets/` (вшиты в библиотеку)
+const BUILTIN_PRESETS: &[(&str, &str)] = &[
+    ("robot", include_str!("../presets/robot.json")),
+    ("giant", include_str!("../presets/giant.json")),
+    ("chipmunk", include_str!("../presets/chipmunk.json")),
+    ("telephone", include_str!("../presets/telephone.json")),
+    ("radio_announcer", include_str!("../presets/radio_announcer.json")),
+    ("ghost", include_str!("../presets/ghost.json")),
+    ("monster", include_str!("../presets/monster.json")),
+    ("cave", include_str!("../presets/cave.json")),
+    ("underwater", include_str!("../presets/underwater.json")),
+    ("alien", include_str!("../presets/alien.json")),
+];
+
+#[derive(Debug)]
+pub enum PresetError {
+    Json(String),
+    Io(String),
+    UnsupportedVersion(u32),
+    Apply(String),
+    NotFound(String),
+}
+
+impl std::fmt::Display for PresetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PresetError::Json(msg) => write!(f, "Ошибка разбора пресета: {}", msg),
+            PresetError::Io(msg) => write!(f, "Ошибка чтения/записи пресета: {}", msg),
+            PresetError::UnsupportedVersion(version) => write!(
+                f,
+                "Пресет версии {} не поддерживается (максимум {})",
+                version, PRESET_VERSION
+            ),
+            PresetError::Apply(msg) => write!(f, "Не удалось применить пресет: {}", msg),
+            PresetError::NotFound(name) => write!(f, "Встроенный пресет \"{}\" не найден", name),
+        }
+    }
+}
+
+impl std::error::Error for PresetError {}
+
+impl From<serde_json::Error> for PresetError {
+    fn from(e: serde_json::Error) -> Self {
+        PresetError::Json(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for PresetError {
+    fn from(e: std::io::Error) -> Self {
+        PresetError::Io(e.to_string())
+    }
+}
+
+/// Снимок состояния конвейера. Частота дискретизации, размер блока и количество каналов
+/// зависят от устройства и в пресет не входят.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub version: u32,
+    pub name: String,
+    pub description: String,
+
+    // Усиление
+    pub input_gain: f32,
+    pub output_gain: f32,
+
+    // Эффект конвейера
+    pub effect: EffectType,
+    pub effect_mix: f32,
+    pub effect_bypass: bool,
+
+    // Генератор шума
+    pub noise_type: NoiseType,
+    pub noise_level: f32,
+
+    // DSP параметры
+    pub pitch_shift: f32,
+    pub reverb_size: f32,
+    pub reverb_damping: f32,
+    pub delay_time: f32,
+    pub delay_feedback: f32,
+
+    // Фильтры
+    pub lowpass_freq: f32,
+    pub highpass_freq: f32,
+    pub bandpass_center: f32,
+    pub bandpass_q: f32,
+
+    // Эквалайзер до и после эффекта
+    pub pre_eq: Vec<EqBand>,
+    pub post_eq: Vec<EqBand>,
+
+    // Робот
+    pub robot_pitch: f32,
+
+    // Динамика (уровни в дБ, времена в секундах)
+    pub gate_enabled: bool,
+    pub gate_threshold: f32,
+    pub gate_ratio: f32,
+    pub gate_range: f32,
+    pub gate_attack: f32,
+    pub gate_release: f32,
+    pub compressor_enabled: bool,
+    pub compressor_threshold: f32,
+    pub compressor_ratio: f32,
+    pub compressor_knee: f32,
+    pub compressor_attack: f32,
+    pub compressor_release: f32,
+    pub compressor_makeup: f32,
+    pub limiter_enabled: bool,
+    pub limiter_ceiling: f32,
+    pub limiter_release: f32,
+
+    // Neural Engine: эффекты в порядке применения
+    pub voice_effects: Vec<VoiceEffect>,
+    pub quality_preset: QualityPreset,
+}
+
+impl Preset {
+    /// Разбирает пресет из JSON, проверяя версию схемы
+    pub fn from_json(json: &str) -> Result<Self, PresetError> {
+        let preset: Preset = serde_json::from_str(json)?;
+        if preset.version > PRESET_VERSION {
+            return Err(PresetError::UnsupportedVersion(preset.version));
+        }
+        Ok(preset)
+    }
+
+    pub fn to_json(&self) -> Result<String, PresetError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Загружает пресет из JSON файла
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PresetError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Сохраняет пресет в JSON файл
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PresetError> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+}
+
+/// Имена встроенных пресетов в порядке библиотеки
+pub fn list_presets() -> Vec<&'static str> {
+    BUILTIN_PRESETS.iter().map(|(name, _)| *name).collect()
+}
+
+/// Встроенный пресет по имени (без учета регистра)
+pub fn builtin_preset(name: &str) -> Result<Preset, PresetError> {
+    BUILTIN_PRESETS
+        .iter()
+        .find(|(builtin, _)| builtin.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| PresetError::NotFound(name.to_string()))
+        .and_then(|(_, json)| Preset::from_json(json))
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        Self {
+            version: PRESET_VERSION,
+            name: String::new(),
+            description: String::new(),
+            input_gain: 1.0,
+            output_gain: 1.0,
+            effect: EffectType::None,
+            effect_mix: 1.0,
+            effect_bypass: false,
+            noise_type: NoiseType::None,
+            noise_level: 0.0,
+            pitch_shift: 1.0,
+            reverb_size: 0.5,
+            reverb_damping: 0.5,
+            delay_time: 0.3,
+            delay_feedback: 0.4,
+            lowpass_freq: 20000.0,
+            highpass_freq: 20.0,
+            bandpass_center: 1000.0,
+            bandpass_q: 2.0,
+            pre_eq: Vec::new(),
+            post_eq: Vec::new(),
+            robot_pitch: 110.0,
+            gate_enabled: false,
+            gate_threshold: -50.0,
+            gate_ratio: 10.0,
+            gate_range: 60.0,
+            gate_attack: 0.001,
+            gate_release: 0.1,
+            compressor_enabled: false,
+            compressor_threshold: -18.0,
+            compressor_ratio: 4.0,
+            compressor_knee: 6.0,
+            compressor_attack: 0.005,
+            compressor_release: 0.1,
+            compressor_makeup: 0.0,
+            limiter_enabled: false,
+            limiter_ceiling: -1.0,
+            limiter_release: 0.05,
+            voice_effects: Vec::new(),
+            quality_preset: QualityPreset::default(),
+        }
+    }
+}
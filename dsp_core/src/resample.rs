@@ -0,0 +1,303 @@
+// Передискретизация: многофазный фильтр на оконном sinc и переход между частотой устройства и обработки
+use std::collections::VecDeque;
+
+/// Количество фаз в таблице коэффициентов (между фазами - линейная интерполяция)
+const PHASES: usize = 256;
+/// Полоса пропускания относительно меньшей из частот Найквиста
+const ROLLOFF: f64 = 0.92;
+
+/// Качество передискретизации: длина фильтра против нагрузки на процессор
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResamplerQuality {
+    Low,     // 16 отводов, ~60 дБ подавления зеркал
+    #[default]
+    Medium,  // 32 отвода, ~80 дБ
+    High,    // 64 отвода, ~100 дБ (офлайн обработка)
+}
+
+impl ResamplerQuality {
+    /// Половина длины фильтра (в сэмплах входа при повышении частоты)
+    fn half_taps(&self) -> usize {
+        match self {
+            ResamplerQuality::Low => 8,
+            ResamplerQuality::Medium => 16,
+            ResamplerQuality::High => 32,
+        }
+    }
+
+    /// Параметр окна Кайзера
+    fn kaiser_beta(&self) -> f64 {
+        match self {
+            ResamplerQuality::Low => 6.0,
+            ResamplerQuality::Medium => 8.0,
+            ResamplerQuality::High => 10.0,
+        }
+    }
+}
+
+/// Потоковый передискретизатор с многофазным sinc фильтром.
+///
+/// Коэффициенты считаются заранее для 256 дробных позиций, поэтому отношение частот может быть
+/// любым (44.1 -> 48 кГц, 48 -> 16 кГц и т.д.). При понижении частоты фильтр растягивается
+/// и срезает все выше новой частоты Найквиста. Выход выровнен по времени со входом,
+/// но выдается с опозданием на `latency()` сэмплов входа, пока копится окно фильтра.
+/// Частоты округляются до целых герц, а позиция хранится точной дробью, поэтому длина выхода
+/// не зависит от того, какими блоками подается вход.
+pub struct Resampler {
+    step_num: u64,      // Шаг по входу на один выходной сэмпл: step_num / step_den
+    step_den: u64,
+    half_taps: usize,   // Половина длины фильтра с учетом растяжения
+    table: Vec<f32>,    // (PHASES + 1) фаз по 2 * half_taps коэффициентов
+    history: Vec<f32>,  // Необработанный вход с запасом в half_taps - 1 сэмпл слева
+    index: usize,       // Позиция следующего выходного сэмпла в `history`: целая часть
+    fraction: u64,      // и дробная часть (в долях step_den)
+}
+
+impl Resampler {
+    pub fn new(input_rate: f32, output_rate: f32) -> Self {
+        Self::with_quality(input_rate, output_rate, ResamplerQuality::default())
+    }
+
+    pub fn with_quality(input_rate: f32, output_rate: f32, quality: ResamplerQuality) -> Self {
+        let input_rate = (input_rate.round() as u64).max(1);
+        let output_rate = (output_rate.round() as u64).max(1);
+        let divisor = gcd(input_rate, output_rate);
+        let (step_num, step_den) = (input_rate / divisor, output_rate / divisor);
+        let step = step_num as f64 / step_den as f64;
+        // При понижении частоты полоса сужается, а фильтр во столько же раз удлиняется
+        let scale = (1.0 / step).min(1.0);
+        let half_taps = if step_num == step_den { 0 } else { (quality.half_taps() as f64 / scale).ceil() as usize };
+        let cutoff = scale * ROLLOFF;
+        let taps = half_taps * 2;
+
+        let mut table = vec![0.0f32; (PHASES + 1) * taps];
+        for (phase, row) in table.chunks_mut(taps.max(1)).enumerate() {
+            let frac = phase as f64 / PHASES as f64;
+            let mut sum = 0.0;
+            for (tap, coefficient) in row.iter_mut().enumerate() {
+                // Расстояние от отвода до выходной позиции в сэмплах входа
+                let distance = tap as f64 - (half_taps as f64 - 1.0) - frac;
+                let value = cutoff * sinc(cutoff * distance) * kaiser(distance / half_taps as f64, quality.kaiser_beta());
+                *coefficient = value as f32;
+                sum += value;
+            }
+            // Единичное усиление на постоянном токе для каждой фазы
+            row.iter_mut().for_each(|coefficient| *coefficient = (*coefficient as f64 / sum) as f32);
+        }
+
+        let mut resampler = Self {
+            step_num,
+            step_den,
+            half_taps,
+            table,
+            history: Vec::new(),
+            index: 0,
+            fraction: 0,
+        };
+        resampler.reset();
+        resampler
+    }
+
+    /// Отношение частот выход / вход
+    pub fn ratio(&self) -> f64 {
+        self.step_den as f64 / self.step_num as f64
+    }
+
+    /// Сколько сэмплов входа фильтр ждет, прежде чем выдать выход
+    pub fn latency(&self) -> usize {
+        self.half_taps
+    }
+
+    /// Передискретизирует блок, дописывая результат в `output`
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        if self.half_taps == 0 {
+            output.extend_from_slice(input);
+            return;
+        }
+        self.history.extend_from_slice(input);
+        let available = self.history.len();
+        self.render(available, output);
+    }
+
+    /// Досчитывает выход для последних сэмплов входа (конец потока) и сбрасывает состояние
+    pub fn flush(&mut self, output: &mut Vec<f32>) {
+        if self.half_taps == 0 {
+            return;
+        }
+        let end = self.history.len();
+        self.history.resize(end + self.half_taps, 0.0);
+        self.render(end, output);
+        self.reset();
+    }
+
+    /// Сбрасывает накопленный вход
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.history.resize(self.half_taps.saturating_sub(1), 0.0);
+        self.index = self.half_taps.saturating_sub(1);
+        self.fraction = 0;
+    }
+
+    /// Считает выходные сэмплы с позициями до `end` (индекс в `history`)
+    fn render(&mut self, end: usize, output: &mut Vec<f32>) {
+        let taps = self.half_taps * 2;
+        while self.index < end && self.index + self.half_taps < self.history.len() {
+            let index = self.index;
+            let phase_position = self.fraction as f64 / self.step_den as f64 * PHASES as f64;
+            let phase = (phase_position as usize).min(PHASES - 1);
+            let blend = (phase_position - phase as f64) as f32;
+
+            let window = &self.history[index + 1 - self.half_taps..index + 1 + self.half_taps];
+            let current = &self.table[phase * taps..(phase + 1) * taps];
+            let next = &self.table[(phase + 1) * taps..(phase + 2) * taps];
+            let mut a = 0.0;
+            let mut b = 0.0;
+            for ((&sample, &h0), &h1) in window.iter().zip(current).zip(next) {
+                a += sample * h0;
+                b += sample * h1;
+            }
+            output.push(a + (b - a) * blend);
+
+            self.fraction += self.step_num;
+            self.index += (self.fraction / self.step_den) as usize;
+            self.fraction %= self.step_den;
+        }
+
+        // Слева оставляем только то, что нужно фильтру для следующей позиции
+        let consumed = (self.index + 1).saturating_sub(self.half_taps).min(self.history.len());
+        self.history.drain(..consumed);
+        self.index -= consumed;
+    }
+}
+
+/// Переход между частотой устройства и частотой обработки.
+///
+/// Каждый блок устройства переводится на частоту обработки, обрабатывается и возвращается обратно.
+/// Количество сэмплов после передискретизации плавает от блока к блоку, поэтому выход идет через
+/// очередь, заранее заполненную тишиной на `latency()` сэмплов устройства.
+pub struct RateConverter {
+    device_rate: f32,
+    processing_rate: f32,
+    to_processing: Vec<Resampler>,
+    to_device: Vec<Resampler>,
+    processing: Vec<Vec<f32>>,
+    resampled: Vec<f32>,
+    queues: Vec<VecDeque<f32>>,
+    latency: usize,
+}
+
+impl RateConverter {
+    pub fn new(device_rate: f32, processing_rate: f32, channels: usize) -> Self {
+        let to_processing: Vec<Resampler> = (0..channels).map(|_| Resampler::new(device_rate, processing_rate)).collect();
+        let to_device: Vec<Resampler> = (0..channels).map(|_| Resampler::new(processing_rate, device_rate)).collect();
+
+        // Оба фильтра придерживают вход; плюс по сэмплу на дробные позиции с каждой стороны
+        let held_back = to_processing.first().map_or(0, |r| r.latency()) as f64
+            + to_device.first().map_or(0, |r| r.latency()) as f64 * device_rate as f64 / processing_rate as f64;
+        let latency = held_back.ceil() as usize + 2;
+
+        let mut converter = Self {
+            device_rate,
+            processing_rate,
+            to_processing,
+            to_device,
+            processing: vec![Vec::new(); channels],
+            resampled: Vec::new(),
+            queues: vec![VecDeque::new(); channels],
+            latency,
+        };
+        converter.reset();
+        converter
+    }
+
+    pub fn device_rate(&self) -> f32 {
+        self.device_rate
+    }
+
+    pub fn processing_rate(&self) -> f32 {
+        self.processing_rate
+    }
+
+    /// Задержка перехода в сэмплах устройства
+    pub fn latency(&self) -> usize {
+        self.latency
+    }
+
+    /// Переводит каналы устройства (все одной длины) на частоту обработки, вызывает `process`
+    /// и записывает результат обратно на место входа.
+    pub fn process<F: FnMut(&mut [Vec<f32>])>(&mut self, channels: &mut [Vec<f32>], mut process: F) {
+        let count = self.convert_input(channels).len();
+        process(&mut self.processing[..count]);
+
+        for (channel, samples) in channels.iter_mut().enumerate().take(count) {
+            self.resampled.clear();
+            self.to_device[channel].process(&self.processing[channel], &mut self.resampled);
+            let queue = &mut self.queues[channel];
+            queue.extend(self.resampled.iter().copied());
+            for sample in samples.iter_mut() {
+                *sample = queue.pop_front().unwrap_or(0.0);
+            }
+        }
+    }
+
+    /// Только переводит каналы устройства на частоту обработки (без обратного пути).
+    /// Нужен для дополнительных входов, например несущей вокодера: при том же размере блока
+    /// длина результата совпадает с той, что получает `process` у конвертера с теми же частотами.
+    pub fn convert_input(&mut self, channels: &[Vec<f32>]) -> &[Vec<f32>] {
+        let count = channels.len().min(self.processing.len());
+        for ((samples, resampler), buffer) in channels.iter().zip(&mut self.to_processing).zip(&mut self.processing) {
+            buffer.clear();
+            resampler.process(samples, buffer);
+        }
+        &self.processing[..count]
+    }
+
+    /// Сбрасывает фильтры и очереди
+    pub fn reset(&mut self) {
+        self.to_processing.iter_mut().chain(&mut self.to_device).for_each(Resampler::reset);
+        for queue in &mut self.queues {
+            queue.clear();
+            queue.resize(self.latency, 0.0);
+        }
+    }
+}
+
+// Helper функции
+
+/// Наибольший общий делитель
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Нормированный sinc: sin(pi x) / (pi x)
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        let px = std::f64::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+/// Окно Кайзера на отрезке [-1, 1]
+fn kaiser(x: f64, beta: f64) -> f64 {
+    if x.abs() > 1.0 {
+        return 0.0;
+    }
+    bessel_i0(beta * (1.0 - x * x).sqrt()) / bessel_i0(beta)
+}
+
+/// Модифицированная функция Бесселя нулевого порядка (ряд)
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..50 {
+        term *= half / k as f64;
+        sum += term * term;
+        if term * term < sum * 1e-16 {
+            break;
+        }
+    }
+    sum
+}
@@ -0,0 +1,228 @@
+// Алгоритмическая реверберация (Freeverb: параллельные гребенчатые фильтры + последовательные allpass)
+
+/// Длины гребенчатых фильтров в сэмплах при 44.1 кГц (взаимно простые, чтобы не было резонансов)
+const COMB_TUNING: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+/// Длины allpass фильтров в сэмплах при 44.1 кГц
+const ALLPASS_TUNING: [usize; 4] = [556, 441, 341, 225];
+/// Разнос длин правого канала относительно левого
+const STEREO_SPREAD: usize = 23;
+
+/// Входное ослабление: сумма 8 гребенок не должна перегружаться
+const FIXED_GAIN: f32 = 0.015;
+/// room_size 0.0 - 1.0 отображается в обратную связь 0.7 - 0.98
+const ROOM_SCALE: f32 = 0.28;
+const ROOM_OFFSET: f32 = 0.7;
+/// damping 0.0 - 1.0 отображается в коэффициент НЧ фильтра 0.0 - 0.4
+const DAMP_SCALE: f32 = 0.4;
+const ALLPASS_FEEDBACK: f32 = 0.5;
+
+/// Максимальная предзадержка в секундах
+pub const MAX_PRE_DELAY: f32 = 0.25;
+
+/// Гребенчатый фильтр с НЧ фильтром в обратной связи (затухание высоких частот)
+struct Comb {
+    buffer: Vec<f32>,
+    position: usize,
+    filter_state: f32,
+}
+
+impl Comb {
+    fn new(length: usize) -> Self {
+        Self { buffer: vec![0.0; length.max(1)], position: 0, filter_state: 0.0 }
+    }
+
+    #[inline]
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.position];
+        self.filter_state = output * (1.0 - damping) + self.filter_state * damping;
+        self.buffer[self.position] = input + self.filter_state * feedback;
+        self.position = (self.position + 1) % self.buffer.len();
+        output
+    }
+
+    fn clear(&mut self) {
+        self.buffer.fill(0.0);
+        self.filter_state = 0.0;
+    }
+}
+
+/// Allpass фильтр Шредера - размывает отражения, не меняя спектр
+struct Allpass {
+    buffer: Vec<f32>,
+    position: usize,
+}
+
+impl Allpass {
+    fn new(length: usize) -> Self {
+        Self { buffer: vec![0.0; length.max(1)], position: 0 }
+    }
+
+    #[inline]
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.position];
+        self.buffer[self.position] = input + delayed * ALLPASS_FEEDBACK;
+        self.position = (self.position + 1) % self.buffer.len();
+        delayed - input
+    }
+
+    fn clear(&mut self) {
+        self.buffer.fill(0.0);
+    }
+}
+
+/// Стерео реверберация Freeverb с предзадержкой.
+///
+/// Все буферы выделяются в конструкторе; сеттеры только пересчитывают коэффициенты,
+/// поэтому их можно вызывать на каждом сэмпле из аудио потока.
+pub struct Reverb {
+    sample_rate: f32,
+    combs_left: Vec<Comb>,
+    combs_right: Vec<Comb>,
+    allpasses_left: Vec<Allpass>,
+    allpasses_right: Vec<Allpass>,
+    pre_delay_buffer: Vec<f32>,
+    pre_delay_position: usize,
+    pre_delay_samples: usize,
+
+    room_size: f32,
+    damping: f32,
+    width: f32,
+    wet: f32,
+    dry: f32,
+    feedback: f32,
+    damp: f32,
+    wet_direct: f32,
+    wet_cross: f32,
+}
+
+impl Reverb {
+    pub fn new(sample_rate: f32) -> Self {
+        let scale = sample_rate / 44100.0;
+        let scaled = |length: usize| (length as f32 * scale) as usize;
+
+        let mut reverb = Self {
+            sample_rate,
+            combs_left: COMB_TUNING.iter().map(|&l| Comb::new(scaled(l))).collect(),
+            combs_right: COMB_TUNING.iter().map(|&l| Comb::new(scaled(l + STEREO_SPREAD))).collect(),
+            allpasses_left: ALLPASS_TUNING.iter().map(|&l| Allpass::new(scaled(l))).collect(),
+            allpasses_right: ALLPASS_TUNING.iter().map(|&l| Allpass::new(scaled(l + STEREO_SPREAD))).collect(),
+            pre_delay_buffer: vec![0.0; (sample_rate * MAX_PRE_DELAY) as usize + 1],
+            pre_delay_position: 0,
+            pre_delay_samples: 0,
+            room_size: 0.5,
+            damping: 0.5,
+            width: 1.0,
+            wet: 0.3,
+            dry: 1.0,
+            feedback: 0.0,
+            damp: 0.0,
+            wet_direct: 0.0,
+            wet_cross: 0.0,
+        };
+        reverb.update_coefficients();
+        reverb
+    }
+
+    /// Размер помещения 0.0 - 1.0 (длина хвоста)
+    pub fn set_room_size(&mut self, room_size: f32) {
+        let room_size = room_size.clamp(0.0, 1.0);
+        if room_size != self.room_size {
+            self.room_size = room_size;
+            self.update_coefficients();
+        }
+    }
+
+    /// Затухание высоких частот в хвосте 0.0 - 1.0
+    pub fn set_damping(&mut self, damping: f32) {
+        let damping = damping.clamp(0.0, 1.0);
+        if damping != self.damping {
+            self.damping = damping;
+            self.update_coefficients();
+        }
+    }
+
+    /// Ширина стереобазы 0.0 (моно) - 1.0
+    pub fn set_width(&mut self, width: f32) {
+        let width = width.clamp(0.0, 1.0);
+        if width != self.width {
+            self.width = width;
+            self.update_coefficients();
+        }
+    }
+
+    /// Предзадержка перед хвостом в секундах (до 0.25)
+    pub fn set_pre_delay(&mut self, seconds: f32) {
+        let samples = (seconds.clamp(0.0, MAX_PRE_DELAY) * self.sample_rate) as usize;
+        self.pre_delay_samples = samples.min(self.pre_delay_buffer.len() - 1);
+    }
+
+    /// Уровни обработанного и исходного сигнала 0.0 - 1.0
+    pub fn set_mix(&mut self, wet: f32, dry: f32) {
+        let wet = wet.clamp(0.0, 1.0);
+        if wet != self.wet {
+            self.wet = wet;
+            self.update_coefficients();
+        }
+        self.dry = dry.clamp(0.0, 1.0);
+    }
+
+    /// Уровень исходного сигнала
+    pub fn dry(&self) -> f32 {
+        self.dry
+    }
+
+    /// Обрабатывает моно сэмпл и возвращает стерео пару
+    pub fn process_stereo(&mut self, input: f32) -> (f32, f32) {
+        let (left, right) = self.process_wet(input);
+        (left + input * self.dry, right + input * self.dry)
+    }
+
+    /// Возвращает только обработанную стерео пару (без исходного сигнала) -
+    /// для многоканальной обработки, где исходный сигнал у каждого канала свой
+    pub fn process_wet(&mut self, input: f32) -> (f32, f32) {
+        // Предзадержка (кольцевой буфер)
+        let length = self.pre_delay_buffer.len();
+        self.pre_delay_buffer[self.pre_delay_position] = input;
+        let read = (self.pre_delay_position + length - self.pre_delay_samples) % length;
+        let delayed = self.pre_delay_buffer[read] * FIXED_GAIN;
+        self.pre_delay_position = (self.pre_delay_position + 1) % length;
+
+        let mut left = 0.0;
+        let mut right = 0.0;
+        for (comb_left, comb_right) in self.combs_left.iter_mut().zip(&mut self.combs_right) {
+            left += comb_left.process(delayed, self.feedback, self.damp);
+            right += comb_right.process(delayed, self.feedback, self.damp);
+        }
+        for (allpass_left, allpass_right) in self.allpasses_left.iter_mut().zip(&mut self.allpasses_right) {
+            left = allpass_left.process(left);
+            right = allpass_right.process(right);
+        }
+
+        (
+            left * self.wet_direct + right * self.wet_cross,
+            right * self.wet_direct + left * self.wet_cross,
+        )
+    }
+
+    /// Обрабатывает моно сэмпл (среднее стерео выхода)
+    pub fn process(&mut self, input: f32) -> f32 {
+        let (left, right) = self.process_stereo(input);
+        (left + right) * 0.5
+    }
+
+    /// Очищает хвост
+    pub fn clear(&mut self) {
+        self.combs_left.iter_mut().chain(&mut self.combs_right).for_each(Comb::clear);
+        self.allpasses_left.iter_mut().chain(&mut self.allpasses_right).for_each(Allpass::clear);
+        self.pre_delay_buffer.fill(0.0);
+    }
+
+    fn update_coefficients(&mut self) {
+        self.feedback = self.room_size * ROOM_SCALE + ROOM_OFFSET;
+        self.damp = self.damping * DAMP_SCALE;
+        // Коэффициент 3.0 компенсирует FIXED_GAIN (как в оригинальном Freeverb)
+        let wet = self.wet * 3.0;
+        self.wet_direct = wet * (self.width * 0.5 + 0.5);
+        self.wet_cross = wet * ((1.0 - self.width) * 0.5);
+    }
+}
@@ -0,0 +1,151 @@
+// Сглаживание параметров: плавный переход к новому значению вместо скачка (без щелчков "молнии")
+
+/// Форма перехода к новому значению
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SmoothingMode {
+    /// Линейный переход за заданное время - значение приходит к цели точно
+    #[default]
+    Linear,
+    /// Однополюсный фильтр: время - постоянная времени (63% пути), дальше экспоненциальный хвост
+    OnePole,
+}
+
+/// Времена сглаживания в секундах (0 - без сглаживания)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothingConfig {
+    pub gain_time: f32,   // Входное/выходное усиление и уровень шума
+    pub mix_time: f32,    // Микс эффекта
+    pub filter_time: f32, // Частоты фильтров
+    pub delay_time: f32,  // Время задержки эха
+}
+
+impl Default for SmoothingConfig {
+    fn default() -> Self {
+        Self {
+            gain_time: 0.02,
+            mix_time: 0.02,
+            filter_time: 0.05,
+            delay_time: 0.1,
+        }
+    }
+}
+
+impl SmoothingConfig {
+    /// Все параметры меняются скачком (как до появления сглаживания)
+    pub fn disabled() -> Self {
+        Self { gain_time: 0.0, mix_time: 0.0, filter_time: 0.0, delay_time: 0.0 }
+    }
+}
+
+/// Сглаженное значение параметра.
+///
+/// Цель задается раз в блок (`set_target`), а `next_value()` вызывается на каждом сэмпле.
+/// Первая цель принимается сразу, чтобы при создании не было перехода от случайного значения.
+#[derive(Debug, Clone)]
+pub struct SmoothedValue {
+    mode: SmoothingMode,
+    current: f32,
+    target: f32,
+    ramp_samples: u32,
+    remaining: u32,   // Линейный режим: сколько сэмплов осталось до цели
+    step: f32,
+    coefficient: f32, // Однополюсный режим
+    initialized: bool,
+}
+
+impl SmoothedValue {
+    pub fn new(mode: SmoothingMode, time_seconds: f32, sample_rate: f32) -> Self {
+        let mut value = Self {
+            mode,
+            current: 0.0,
+            target: 0.0,
+            ramp_samples: 0,
+            remaining: 0,
+            step: 0.0,
+            coefficient: 0.0,
+            initialized: false,
+        };
+        value.set_time(time_seconds, sample_rate);
+        value
+    }
+
+    /// Меняет время сглаживания (текущий переход продолжается с новой скоростью)
+    pub fn set_time(&mut self, time_seconds: f32, sample_rate: f32) {
+        let samples = (time_seconds.max(0.0) * sample_rate).round();
+        self.ramp_samples = samples as u32;
+        self.coefficient = if samples < 1.0 { 0.0 } else { (-1.0 / samples).exp() };
+        if self.remaining > 0 {
+            self.remaining = 0;
+            self.start_ramp();
+        }
+    }
+
+    /// Новая цель; повтор той же цели не перезапускает переход
+    pub fn set_target(&mut self, target: f32) {
+        if !self.initialized {
+            self.reset(target);
+            return;
+        }
+        if target == self.target {
+            return;
+        }
+        self.target = target;
+        self.start_ramp();
+    }
+
+    /// Следующий сэмпл перехода
+    #[inline]
+    pub fn next_value(&mut self) -> f32 {
+        match self.mode {
+            SmoothingMode::Linear => {
+                if self.remaining > 0 {
+                    self.remaining -= 1;
+                    self.current = if self.remaining == 0 { self.target } else { self.current + self.step };
+                }
+            },
+            SmoothingMode::OnePole => {
+                if self.current != self.target {
+                    self.current = self.target + (self.current - self.target) * self.coefficient;
+                    // Хвост экспоненты короче минимального шага не слышен
+                    if (self.current - self.target).abs() <= self.target.abs().max(1.0) * 1e-5 {
+                        self.current = self.target;
+                    }
+                }
+            },
+        }
+        self.current
+    }
+
+    /// Текущее значение (без шага)
+    pub fn value(&self) -> f32 {
+        self.current
+    }
+
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    /// Идет ли переход
+    pub fn is_smoothing(&self) -> bool {
+        self.current != self.target
+    }
+
+    /// Сразу устанавливает значение без перехода
+    pub fn reset(&mut self, value: f32) {
+        self.current = value;
+        self.target = value;
+        self.remaining = 0;
+        self.initialized = true;
+    }
+
+    fn start_ramp(&mut self) {
+        match self.mode {
+            SmoothingMode::Linear if self.ramp_samples > 0 => {
+                self.remaining = self.ramp_samples;
+                self.step = (self.target - self.current) / self.ramp_samples as f32;
+            },
+            SmoothingMode::OnePole if self.coefficient > 0.0 => {},
+            _ => self.current = self.target,
+        }
+    }
+}
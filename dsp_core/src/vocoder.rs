@@ -0,0 +1,257 @@
+// Робот без нейросетей: кольцевой модулятор и канальный вокодер с внутренним генератором несущей
+use std::f32::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+
+use crate::BiquadFilter;
+
+/// Допустимое число полос вокодера
+pub const MIN_VOCODER_BANDS: usize = 16;
+pub const MAX_VOCODER_BANDS: usize = 32;
+/// Полосы вокодера равномерно по октавам от нижней до верхней частоты
+const LOWEST_BAND: f32 = 100.0;
+const HIGHEST_BAND: f32 = 8000.0;
+/// Огибающая полосы: быстрая атака и короткий спад, чтобы речь оставалась разборчивой
+const ENVELOPE_ATTACK: f32 = 0.002;
+const ENVELOPE_RELEASE: f32 = 0.02;
+/// Полоса несущей тише этого уровня не усиливается (иначе вокодер поднимает ее шум)
+const CARRIER_FLOOR: f32 = 1e-3;
+
+/// Форма несущей вокодера
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CarrierWaveform {
+    /// Пила: все гармоники, классический "робот"
+    #[default]
+    Saw,
+    /// Меандр: только нечетные гармоники, более полый звук
+    Pulse,
+    /// Белый шум: шепот без высоты тона
+    Noise,
+}
+
+/// Генератор несущей. Пила и меандр с PolyBLEP сглаживанием разрывов (без алиасинга на высоких тонах).
+pub struct Carrier {
+    waveform: CarrierWaveform,
+    sample_rate: f32,
+    phase: f32,     // 0.0 - 1.0
+    increment: f32,
+    noise_state: u32,
+}
+
+impl Carrier {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut carrier = Self {
+            waveform: CarrierWaveform::default(),
+            sample_rate,
+            phase: 0.0,
+            increment: 0.0,
+            noise_state: 12345,
+        };
+        carrier.set_frequency(110.0);
+        carrier
+    }
+
+    pub fn set_waveform(&mut self, waveform: CarrierWaveform) {
+        self.waveform = waveform;
+    }
+
+    /// Высота несущей в Гц (фаза продолжается - смена высоты без щелчка)
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.increment = frequency.clamp(1.0, self.sample_rate * 0.45) / self.sample_rate;
+    }
+
+    #[inline]
+    pub fn next_value(&mut self) -> f32 {
+        let (phase, increment) = (self.phase, self.increment);
+        let value = match self.waveform {
+            CarrierWaveform::Saw => 2.0 * phase - 1.0 - poly_blep(phase, increment),
+            CarrierWaveform::Pulse => {
+                let square = if phase < 0.5 { 1.0 } else { -1.0 };
+                square + poly_blep(phase, increment) - poly_blep((phase + 0.5).fract(), increment)
+            },
+            CarrierWaveform::Noise => {
+                self.noise_state = self.noise_state.wrapping_mul(1103515245).wrapping_add(12345);
+                (self.noise_state as f32 / u32::MAX as f32) * 2.0 - 1.0
+            },
+        };
+        self.phase += increment;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+        }
+        value
+    }
+}
+
+/// Кольцевой модулятор: голос умножается на синусоиду - металлический звон без высоты исходного голоса
+pub struct RingModulator {
+    sample_rate: f32,
+    phase: f32,
+    increment: f32,
+    mix: f32,
+}
+
+impl RingModulator {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut modulator = Self { sample_rate, phase: 0.0, increment: 0.0, mix: 1.0 };
+        modulator.set_frequency(30.0);
+        modulator
+    }
+
+    /// Частота модулятора в Гц (30 Гц - "далек", сотни Гц - колокольный звон)
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.increment = frequency.clamp(0.0, self.sample_rate * 0.45) / self.sample_rate;
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let modulated = input * (self.phase * TAU).sin();
+        self.phase = (self.phase + self.increment).fract();
+        input * (1.0 - self.mix) + modulated * self.mix
+    }
+}
+
+/// Полоса вокодера: анализ голоса и синтез из несущей одинаковыми фильтрами (по два биквада - 4-й порядок)
+struct VocoderBand {
+    analysis: [BiquadFilter; 2],
+    synthesis: [BiquadFilter; 2],
+    envelope: f32,         // Огибающая голоса
+    carrier_envelope: f32, // Огибающая несущей (для выравнивания ее спектра)
+}
+
+impl VocoderBand {
+    fn new(frequency: f32, q: f32, sample_rate: f32) -> Self {
+        let filter = || {
+            let mut filter = BiquadFilter::new();
+            filter.bandpass(frequency, sample_rate, q);
+            filter
+        };
+        Self {
+            analysis: [filter(), filter()],
+            synthesis: [filter(), filter()],
+            envelope: 0.0,
+            carrier_envelope: 0.0,
+        }
+    }
+}
+
+/// Канальный вокодер: огибающие полос голоса накладываются на те же полосы несущей.
+///
+/// Спектр несущей выравнивается по полосам, поэтому громкость не зависит от ее формы,
+/// а внешняя несущая (музыка, синтезатор) звучит так же ровно, как внутренняя.
+pub struct Vocoder {
+    sample_rate: f32,
+    bands: Vec<VocoderBand>,
+    carrier: Carrier,
+    attack: f32,
+    release: f32,
+    mix: f32,
+}
+
+impl Vocoder {
+    /// `bands` - число полос, 16 - 32
+    pub fn new(sample_rate: f32, bands: usize) -> Self {
+        let mut vocoder = Self {
+            sample_rate,
+            bands: Vec::new(),
+            carrier: Carrier::new(sample_rate),
+            attack: envelope_coefficient(ENVELOPE_ATTACK, sample_rate),
+            release: envelope_coefficient(ENVELOPE_RELEASE, sample_rate),
+            mix: 1.0,
+        };
+        vocoder.set_bands(bands);
+        vocoder
+    }
+
+    pub fn band_count(&self) -> usize {
+        self.bands.len()
+    }
+
+    /// Перестраивает полосы (состояние фильтров сбрасывается); то же число полос ничего не меняет
+    pub fn set_bands(&mut self, bands: usize) {
+        let count = bands.clamp(MIN_VOCODER_BANDS, MAX_VOCODER_BANDS);
+        if count == self.bands.len() {
+            return;
+        }
+        let highest = HIGHEST_BAND.min(self.sample_rate * 0.45);
+        let ratio = (highest / LOWEST_BAND).powf(1.0 / (count - 1) as f32);
+        // Соседние полосы пересекаются на -3 дБ
+        let q = 1.0 / (ratio.sqrt() - 1.0 / ratio.sqrt());
+        self.bands = (0..count)
+            .map(|band| VocoderBand::new(LOWEST_BAND * ratio.powi(band as i32), q, self.sample_rate))
+            .collect();
+    }
+
+    /// Высота внутренней несущей в Гц
+    pub fn set_pitch(&mut self, frequency: f32) {
+        self.carrier.set_frequency(frequency);
+    }
+
+    pub fn set_waveform(&mut self, waveform: CarrierWaveform) {
+        self.carrier.set_waveform(waveform);
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// Голос через внутреннюю несущую
+    pub fn process(&mut self, input: f32) -> f32 {
+        let carrier = self.carrier.next_value();
+        self.process_with_carrier(input, carrier)
+    }
+
+    /// Голос `input` накладывается на внешнюю несущую `carrier`
+    pub fn process_with_carrier(&mut self, input: f32, carrier: f32) -> f32 {
+        let (attack, release) = (self.attack, self.release);
+        let mut wet = 0.0;
+        for band in &mut self.bands {
+            let voice = band.analysis.iter_mut().fold(input, |signal, filter| filter.process(signal));
+            let tone = band.synthesis.iter_mut().fold(carrier, |signal, filter| filter.process(signal));
+            band.envelope = follow_envelope(band.envelope, voice.abs(), attack, release);
+            band.carrier_envelope = follow_envelope(band.carrier_envelope, tone.abs(), attack, release);
+            wet += tone * band.envelope / band.carrier_envelope.max(CARRIER_FLOOR);
+        }
+        input * (1.0 - self.mix) + wet * self.mix
+    }
+
+    /// Очищает фильтры и огибающие
+    pub fn reset(&mut self) {
+        for band in &mut self.bands {
+            band.analysis.iter_mut().chain(band.synthesis.iter_mut()).for_each(BiquadFilter::reset);
+            band.envelope = 0.0;
+            band.carrier_envelope = 0.0;
+        }
+    }
+}
+
+// Helper функции
+
+/// Поправка PolyBLEP для разрыва на фазе 0 (`increment` - шаг фазы за сэмпл)
+#[inline]
+fn poly_blep(phase: f32, increment: f32) -> f32 {
+    if phase < increment {
+        let t = phase / increment;
+        2.0 * t - t * t - 1.0
+    } else if phase > 1.0 - increment {
+        let t = (phase - 1.0) / increment;
+        t * t + 2.0 * t + 1.0
+    } else {
+        0.0
+    }
+}
+
+/// Коэффициент однополюсного сглаживания с постоянной времени `time` секунд
+fn envelope_coefficient(time: f32, sample_rate: f32) -> f32 {
+    (-1.0 / (time * sample_rate)).exp()
+}
+
+/// Шаг следящего за огибающей: быстрый подъем, медленный спад
+#[inline]
+fn follow_envelope(envelope: f32, level: f32, attack: f32, release: f32) -> f32 {
+    let coefficient = if level > envelope { attack } else { release };
+    level + (envelope - level) * coefficient
+}